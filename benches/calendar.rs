@@ -0,0 +1,116 @@
+//! Benchmarks for the calendar engine: generating windows of weeks, paging
+//! through them, and computing moon phases over long ranges
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use nhmoon::bench::WeekPager;
+use nhmoon::{Conway, DateStyler, Meeus, NetHack, Phase, PhaseCalculator, PhasesIn};
+use ratatui::style::{Style, Stylize};
+use std::num::NonZeroUsize;
+use time::macros::{date, offset};
+use time::Date;
+
+/// Number of weeks in a window, roughly that of a tall terminal
+const WEEK_QTY: NonZeroUsize = match NonZeroUsize::new(50) {
+    Some(n) => n,
+    None => panic!("WEEK_QTY must be nonzero"),
+};
+
+const START: Date = date!(2024 - 03 - 10);
+
+/// A styler that highlights full & new moons, like the calendar's default
+#[derive(Clone, Copy, Debug)]
+struct MoonStyler<P>(P);
+
+impl<P: PhaseCalculator> DateStyler for MoonStyler<P> {
+    fn date_style(&self, date: Date) -> Style {
+        match self.0.phase(date) {
+            Phase::Full => Style::new().yellow().bold(),
+            Phase::New => Style::new().blue().bold(),
+            Phase::Normal => Style::new(),
+        }
+    }
+}
+
+fn bench_around_date(c: &mut Criterion) {
+    let mut group = c.benchmark_group("around_date");
+    group.bench_function("unstyled", |b| {
+        b.iter(|| WeekPager::around_date(black_box(START), WEEK_QTY, ()));
+    });
+    group.bench_function("nethack", |b| {
+        let styler = MoonStyler(NetHack);
+        b.iter(|| WeekPager::around_date(black_box(START), WEEK_QTY, styler));
+    });
+    group.bench_function("meeus", |b| {
+        let styler = MoonStyler(Meeus(offset!(UTC)));
+        b.iter(|| WeekPager::around_date(black_box(START), WEEK_QTY, styler));
+    });
+    group.finish();
+}
+
+fn bench_paging(c: &mut Criterion) {
+    let styler = MoonStyler(NetHack);
+    let pager = WeekPager::around_date(START, WEEK_QTY, styler);
+    let mut group = c.benchmark_group("paging");
+    for pages in [1, 10, 100] {
+        group.bench_with_input(BenchmarkId::new("forwards", pages), &pages, |b, &n| {
+            b.iter_batched_ref(
+                || pager.clone(),
+                |p| (0..n).all(|_| p.page_forwards()),
+                criterion::BatchSize::SmallInput,
+            );
+        });
+        group.bench_with_input(BenchmarkId::new("backwards", pages), &pages, |b, &n| {
+            b.iter_batched_ref(
+                || pager.clone(),
+                |p| (0..n).all(|_| p.page_backwards()),
+                criterion::BatchSize::SmallInput,
+            );
+        });
+    }
+    group.bench_function("week_forwards_x100", |b| {
+        b.iter_batched_ref(
+            || pager.clone(),
+            |p| (0..100).all(|_| p.week_forwards()),
+            criterion::BatchSize::SmallInput,
+        );
+    });
+    group.bench_function("jump_to", |b| {
+        b.iter_batched_ref(
+            || pager.clone(),
+            |p| p.jump_to(black_box(date!(1987 - 06 - 15))),
+            criterion::BatchSize::SmallInput,
+        );
+    });
+    group.finish();
+}
+
+fn bench_phases(c: &mut Criterion) {
+    let start = date!(2000 - 01 - 01);
+    let end = date!(2100 - 01 - 01);
+    let mut group = c.benchmark_group("phases_in_century");
+    group.sample_size(20);
+    group.bench_function("nethack", |b| {
+        b.iter(|| {
+            PhasesIn::new(NetHack, black_box(start)..end)
+                .filter(|&(_, p)| p == Phase::Full)
+                .count()
+        });
+    });
+    group.bench_function("conway", |b| {
+        b.iter(|| {
+            PhasesIn::new(Conway, black_box(start)..end)
+                .filter(|&(_, p)| p == Phase::Full)
+                .count()
+        });
+    });
+    group.bench_function("meeus", |b| {
+        b.iter(|| {
+            PhasesIn::new(Meeus(offset!(UTC)), black_box(start)..end)
+                .filter(|&(_, p)| p == Phase::Full)
+                .count()
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_around_date, bench_paging, bench_phases);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+//! Generates the table of NetHack's moon phases consulted by `src/moon.rs`
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+/// NetHack's `phase_of_the_moon()`, given the remainder of the number of years
+/// since 1900 divided by 19 (which is negative for years before 1900) and the
+/// one-based day of the year
+fn phase_index(cycle_year: i32, ordinal: i32) -> i32 {
+    let goldn = cycle_year + 1;
+    let mut epact = (11 * goldn + 18) % 30;
+    if (epact == 25 && goldn > 11) || epact == 24 {
+        epact += 1;
+    }
+    (((((ordinal - 1 + epact) * 6) + 11) % 177) / 22) & 7
+}
+
+fn main() -> std::io::Result<()> {
+    println!("cargo:rerun-if-changed=build.rs");
+    let mut code = String::from(concat!(
+        "/// NetHack's phase of the moon for each day of the year, indexed by the\n",
+        "/// remainder of the number of years since 1900 divided by 19 (plus 18, as\n",
+        "/// the remainder is negative for years before 1900) and then by the\n",
+        "/// zero-based day of the year.  Generated by `build.rs`.\n",
+        "static NETHACK_PHASES: [[u8; 366]; 37] = [\n",
+    ));
+    for cycle_year in -18..=18 {
+        code.push_str("    [");
+        for ordinal in 1..=366 {
+            let _ = write!(code, "{},", phase_index(cycle_year, ordinal));
+        }
+        code.push_str("],\n");
+    }
+    code.push_str("];\n");
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").expect("OUT_DIR should be set"));
+    std::fs::write(out_dir.join("nethack_phases.rs"), code)
+}
@@ -0,0 +1,147 @@
+use crate::calendar::YearNumbering;
+use crate::datefmt::DateFormat;
+use crate::moon::{Hemisphere, LunarPhase, PhaseCalculator, Phoon};
+use crate::popup::{Popup, PopupStyle};
+use ratatui::prelude::*;
+use time::Date;
+
+/// Width of the picture of the Moon, in columns
+const ART_WIDTH: u16 = 14;
+
+/// Height of the picture of the Moon, in lines
+const ART_HEIGHT: u16 = 7;
+
+/// Popup showing one frame of the Moon's progress through a month: a picture
+/// of the Moon as it appears on `date` along with the name of its phase
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct PhaseAnimation {
+    pub(crate) date: Date,
+    /// Whether the animation only steps when Left & Right are pressed, as
+    /// with reduced motion
+    pub(crate) manual: bool,
+    pub(crate) phoon: Phoon,
+    pub(crate) hemisphere: Hemisphere,
+    pub(crate) date_format: DateFormat,
+    pub(crate) year_numbering: YearNumbering,
+    pub(crate) style: PopupStyle,
+}
+
+impl PhaseAnimation {
+    fn lines(&self) -> Vec<Line<'static>> {
+        let lunar = self.phoon.lunar_phase(self.date);
+        let mut lines = moon_art(lunar, self.hemisphere)
+            .into_iter()
+            .map(|row| Line::raw(row).centered())
+            .collect::<Vec<_>>();
+        lines.push(Line::raw(""));
+        lines.push(Line::raw(format!(
+            "{} ({})",
+            self.date_format.format(self.date, self.year_numbering),
+            self.date.weekday()
+        )));
+        lines.push(Line::raw(if self.style.ascii {
+            lunar.name().to_owned()
+        } else {
+            format!("{} {}", lunar.glyph(self.hemisphere), lunar.name())
+        }));
+        lines.push(Line::raw(""));
+        if self.manual {
+            lines.push(Line::raw("Left & Right step a day."));
+        }
+        lines.push(Line::raw("Press the Any Key to dismiss."));
+        lines
+    }
+}
+
+impl Widget for PhaseAnimation {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Popup::new(" Phases ", self.lines(), self.style).render(area, buf);
+    }
+}
+
+/// Draws the Moon in the given phase as seen from the given hemisphere, with
+/// `@` for the lit part and `.` for the dark part
+fn moon_art(phase: LunarPhase, hemisphere: Hemisphere) -> Vec<String> {
+    let index = match phase {
+        LunarPhase::New => 0,
+        LunarPhase::WaxingCrescent => 1,
+        LunarPhase::FirstQuarter => 2,
+        LunarPhase::WaxingGibbous => 3,
+        LunarPhase::Full => 4,
+        LunarPhase::WaningGibbous => 5,
+        LunarPhase::LastQuarter => 6,
+        LunarPhase::WaningCrescent => 7,
+    };
+    let angle = f64::from(index) * std::f64::consts::FRAC_PI_4;
+    let waxing = index < 4;
+    (0..ART_HEIGHT)
+        .map(|row| {
+            let y = ((f64::from(row) + 0.5) / f64::from(ART_HEIGHT)).mul_add(2.0, -1.0);
+            // Half the width of the Moon at this height; the terminator is
+            // the half-ellipse spanning it whose width depends on the phase
+            let half_width = (1.0 - y * y).sqrt();
+            let terminator = half_width * angle.cos();
+            (0..ART_WIDTH)
+                .map(|col| {
+                    let mut x = ((f64::from(col) + 0.5) / f64::from(ART_WIDTH)).mul_add(2.0, -1.0);
+                    if x.abs() > half_width {
+                        return ' ';
+                    }
+                    // Seen from the south, the Moon is flipped
+                    if hemisphere == Hemisphere::South {
+                        x = -x;
+                    }
+                    let lit = if waxing {
+                        x > terminator
+                    } else {
+                        x < -terminator
+                    };
+                    if lit {
+                        '@'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moon_art() {
+        assert_eq!(
+            moon_art(LunarPhase::FirstQuarter, Hemisphere::North),
+            [
+                "   ....@@@@   ",
+                " ......@@@@@@ ",
+                ".......@@@@@@@",
+                ".......@@@@@@@",
+                ".......@@@@@@@",
+                " ......@@@@@@ ",
+                "   ....@@@@   ",
+            ]
+        );
+        assert_eq!(
+            moon_art(LunarPhase::FirstQuarter, Hemisphere::South),
+            [
+                "   @@@@....   ",
+                " @@@@@@...... ",
+                "@@@@@@@.......",
+                "@@@@@@@.......",
+                "@@@@@@@.......",
+                " @@@@@@...... ",
+                "   @@@@....   ",
+            ]
+        );
+        assert!(moon_art(LunarPhase::New, Hemisphere::North)
+            .iter()
+            .all(|row| !row.contains('@')));
+        assert!(moon_art(LunarPhase::Full, Hemisphere::South)
+            .iter()
+            .all(|row| !row.contains('.')));
+    }
+}
@@ -1,56 +1,952 @@
-use crate::calendar::{Calendar, DateStyler, WeekWindow};
-use crate::help::Help;
+use crate::animation::PhaseAnimation;
+use crate::astro::Location;
+use crate::calendar::{
+    Calendar, DateStyler, MonthBorders, WeekSpacing, WeekStart, WeekWindow, YearNumbering,
+};
+use crate::calendars::OtherCalendars;
+use crate::compare::Comparison;
+use crate::config::{save_config, ConfigError, ConfigSource, ConfigWatcher};
+use crate::control::ControlCommand;
+use crate::crash::ActionLog;
+use crate::datefmt::DateFormat;
+use crate::day_list::{DayList, View};
+use crate::dump::buffer_text;
+use crate::feedback::{Alert, Feedback, FeedbackMap};
+use crate::footer::LuckFooter;
+use crate::help::{ChordHints, Help, Welcome};
+use crate::info::DayInfo;
+use crate::keymap::{Action, Keymap};
+use crate::legend::Legend;
+use crate::luck::Luck;
+use crate::markdown::phase_table;
+use crate::month_menu::{parse_month_query, MonthMenu, MONTH_KEYS};
+use crate::moon::{Phase, PhaseCalculator};
+use crate::options::Options;
+use crate::options_menu::OptionsMenu;
+use crate::popup::{Confirm, Notice, PopupStyle, Prompt};
+use crate::session::Session;
+use crate::settings::{Highlighter, Setting, Settings};
+use crate::term::{osc52, Background, Capabilities};
+use crate::theme::{load_theme, save_theme, Theme};
+use crate::theme_editor::{StyleField, ThemeEditor};
+use anyhow::Context;
 use crossterm::{
-    event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     execute,
     style::Print,
 };
-use ratatui::prelude::*;
+use ratatui::{backend::TestBackend, prelude::*, terminal::CompletedFrame};
+use std::collections::HashMap;
+use std::fs;
 use std::io;
+use std::num::NonZeroUsize;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+use time::{Date, Month, OffsetDateTime, UtcOffset};
+
+/// How long to wait for input before checking whether the configuration file
+/// has changed
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait for input before checking for commands from
+/// `--command-fifo`
+const COMMAND_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long `--auto-scroll` waits after a keypress before advancing again
+const AUTO_SCROLL_PAUSE: Duration = Duration::from_secs(60);
+
+/// How long to show each day of a month when animating its phases
+const ANIMATION_FRAME: Duration = Duration::from_millis(250);
+
+/// How long to show the in-between frame when smooth scrolling
+const GLIDE_FRAME: Duration = Duration::from_millis(25);
+
+/// How long to draw the screen in reverse video for `Feedback::Flash`
+const FLASH_FRAME: Duration = Duration::from_millis(100);
+
+/// A backend that can sound the terminal bell
+pub(crate) trait Bell {
+    fn bell(&mut self) -> io::Result<()>;
+}
+
+impl<W: io::Write> Bell for CrosstermBackend<W> {
+    fn bell(&mut self) -> io::Result<()> {
+        execute!(self, Print("\x07"))
+    }
+}
 
-pub(crate) type CrossTerminal = Terminal<CrosstermBackend<io::Stdout>>;
+/// The bell is silent when rendering to a buffer with `--dump-buffer`
+impl Bell for TestBackend {
+    fn bell(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A backend that can put text on the system clipboard
+pub(crate) trait Clipboard {
+    fn copy(&mut self, text: &str) -> io::Result<()>;
+}
+
+/// Text is copied by asking the terminal to do it, which not all terminals
+/// support
+impl<W: io::Write> Clipboard for CrosstermBackend<W> {
+    fn copy(&mut self, text: &str) -> io::Result<()> {
+        execute!(self, Print(osc52(text)))
+    }
+}
+
+/// Nothing is copied when rendering to a buffer with `--dump-buffer`
+impl Clipboard for TestBackend {
+    fn copy(&mut self, _text: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
 
 #[derive(Debug)]
-pub(crate) struct App<S> {
-    terminal: CrossTerminal,
-    weeks: WeekWindow<S>,
-    quitting: bool,
-    helping: bool,
+pub(crate) struct App<'a, B: Backend, X = ()> {
+    terminal: &'a mut Terminal<B>,
+    state: State<X>,
+    /// The configuration file to which to save settings
+    config: ConfigSource,
+    watcher: ConfigWatcher,
+    /// Options given on the command line, which take precedence over those in
+    /// the configuration file when it is reloaded
+    overrides: Options,
+    /// The settings as of the last time the configuration was loaded.  On
+    /// reload, only the settings that differ from these are applied, so that
+    /// unsaved changes made in the options popup are kept.
+    loaded: Settings,
+    /// Actions to perform after the calendar is first drawn
+    script: Vec<Action>,
+    /// What the terminal can display, to which reloaded themes are adapted
+    capabilities: Capabilities,
+    /// Selects the theme to use if a reloaded configuration doesn't set one
+    background: Background,
+    /// How to alert the user to keys & actions that can't be carried out
+    feedback: FeedbackMap,
+    spacing: WeekSpacing,
+    /// The number of weeks to show regardless of the terminal's height, if
+    /// any
+    weeks: Option<NonZeroUsize>,
+    grid: bool,
+    month_labels: bool,
+    sparkline: bool,
+    /// Whether to write textual markers after full & new moons
+    markers: bool,
+    /// Whether to name full & new moons in the right margin
+    phase_labels: bool,
+    /// Whether to quote NetHack's messages for today's phase in the info
+    /// popup & footer
+    game_messages: bool,
+    /// Whether to avoid anything that moves on its own: the calendar jumps
+    /// instead of gliding, and the phase animation only steps when keys are
+    /// pressed
+    reduced_motion: bool,
+    /// The message shown for the last alert with `Feedback::Message`, until
+    /// the next keypress
+    notice: Option<&'static str>,
+    /// Lines of commands sent by other programs
+    commands: Option<Receiver<io::Result<String>>>,
+    /// What to change in a copy of the calendar drawn on the right, if any
+    compare: Option<Comparison>,
+    /// How often to advance the calendar by a month on its own
+    auto_scroll: Option<Duration>,
+    /// When to next advance the calendar by a month
+    next_scroll: Option<Instant>,
+    /// When the phase animation last stepped on its own, or when it was last
+    /// checked while closed
+    last_frame: Instant,
 }
 
-impl<S: DateStyler> App<S> {
-    pub(crate) fn new(terminal: CrossTerminal, weeks: WeekWindow<S>) -> App<S> {
+impl<'a, B: Backend + Bell + Clipboard, X: DateStyler + Clone> App<'a, B, X> {
+    pub(crate) fn new(
+        terminal: &'a mut Terminal<B>,
+        weeks: WeekWindow<Highlighter<X>>,
+    ) -> App<'a, B, X> {
+        let loaded = weeks.styler().settings;
         App {
             terminal,
-            weeks,
-            quitting: false,
-            helping: false,
+            state: State::new(weeks),
+            config: ConfigSource::Default,
+            watcher: ConfigWatcher::new(ConfigSource::Default),
+            overrides: Options::default(),
+            loaded,
+            script: Vec::new(),
+            capabilities: Capabilities::FULL,
+            background: Background::Dark,
+            feedback: FeedbackMap::default(),
+            spacing: WeekSpacing::Uniform,
+            weeks: None,
+            grid: false,
+            month_labels: false,
+            sparkline: false,
+            markers: false,
+            phase_labels: false,
+            game_messages: false,
+            reduced_motion: false,
+            notice: None,
+            commands: None,
+            compare: None,
+            auto_scroll: None,
+            next_scroll: None,
+            last_frame: Instant::now(),
         }
     }
 
-    pub(crate) fn run(mut self) -> io::Result<()> {
-        while !self.quitting {
-            self.draw()?;
-            self.handle_input()?;
+    pub(crate) fn location(mut self, location: Option<Location>) -> Self {
+        self.state.location = location;
+        self
+    }
+
+    /// Set the calendars in which the info popup also shows the date
+    pub(crate) fn calendars(mut self, calendars: OtherCalendars) -> Self {
+        self.state.calendars = calendars;
+        self
+    }
+
+    /// Set how the info popup shows dates
+    pub(crate) fn date_format(mut self, date_format: DateFormat) -> Self {
+        self.state.date_format = date_format;
+        self
+    }
+
+    /// Set how the calendar and info popup show years before 1 CE
+    pub(crate) fn year_numbering(mut self, year_numbering: YearNumbering) -> Self {
+        self.state.year_numbering = year_numbering;
+        self
+    }
+
+    pub(crate) fn keymap(mut self, keymap: Keymap) -> Self {
+        self.state.keymap = keymap;
+        self
+    }
+
+    pub(crate) fn config(mut self, config: ConfigSource) -> Self {
+        self.watcher = ConfigWatcher::new(config.clone());
+        self.config = config;
+        self
+    }
+
+    pub(crate) fn overrides(mut self, overrides: Options) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Draw borders in ASCII and adapt reloaded themes if the terminal can't
+    /// display everything.  The initial theme must already have been adapted.
+    /// The color depth is overridden by that in any reloaded options.
+    pub(crate) fn capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Set how to alert the user to invalid keys, the ends of time, and
+    /// failed jumps
+    pub(crate) fn feedback(mut self, feedback: FeedbackMap) -> Self {
+        self.feedback = feedback;
+        self
+    }
+
+    pub(crate) fn spacing(mut self, spacing: WeekSpacing) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Always show `weeks` weeks, centered vertically, instead of filling
+    /// the terminal
+    pub(crate) fn weeks(mut self, weeks: Option<NonZeroUsize>) -> Self {
+        self.weeks = weeks;
+        self
+    }
+
+    /// Set which borders between months to draw, until changed with the
+    /// `month-borders` action
+    pub(crate) fn month_borders(mut self, borders: MonthBorders) -> Self {
+        self.state.month_borders = borders;
+        self
+    }
+
+    /// Draw lines around every day of the calendar
+    pub(crate) fn grid(mut self, grid: bool) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    /// Write each month's abbreviated name under its first day
+    pub(crate) fn month_labels(mut self, month_labels: bool) -> Self {
+        self.month_labels = month_labels;
+        self
+    }
+
+    /// Whether to draw a sparkline of the moon's phases under each week
+    pub(crate) fn sparkline(mut self, sparkline: bool) -> Self {
+        self.sparkline = sparkline;
+        self
+    }
+
+    /// Whether to write a `*` after each full moon and an `o` after each new
+    /// moon
+    pub(crate) fn markers(mut self, markers: bool) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    /// Whether to write "Full Moon" & "New Moon" in the right margin beside
+    /// weeks without a month name there
+    pub(crate) fn phase_labels(mut self, phase_labels: bool) -> Self {
+        self.phase_labels = phase_labels;
+        self
+    }
+
+    /// Whether to quote the messages that NetHack prints on a full or new
+    /// moon in the info popup & dashboard footer
+    pub(crate) fn game_messages(mut self, game_messages: bool) -> Self {
+        self.game_messages = game_messages;
+        self
+    }
+
+    /// Set the view to start in.  The list view starts with the day that the
+    /// calendar would be centered on selected.
+    pub(crate) fn view(mut self, view: View) -> Self {
+        self.state.list = (view == View::List).then(|| self.state.weeks.focus_date());
+        self
+    }
+
+    /// Record each action performed in `log` for inclusion in crash reports
+    pub(crate) fn action_log(mut self, log: ActionLog) -> Self {
+        self.state.action_log = log;
+        self
+    }
+
+    /// Ask before quitting with the `quit` action
+    pub(crate) fn confirm_quit(mut self, confirm_quit: bool) -> Self {
+        self.state.confirm_quit = confirm_quit;
+        self
+    }
+
+    /// Turn off gliding and the phase animation's automatic stepping
+    pub(crate) fn reduced_motion(mut self, reduced_motion: bool) -> Self {
+        self.reduced_motion = reduced_motion;
+        self
+    }
+
+    /// Carry out the commands received on `commands` while running
+    pub(crate) fn commands(mut self, commands: Option<Receiver<io::Result<String>>>) -> Self {
+        self.commands = commands;
+        self
+    }
+
+    /// Draw a second copy of the calendar to the right of the first, changed
+    /// as described by `comparison`
+    pub(crate) fn compare(mut self, comparison: Option<Comparison>) -> Self {
+        self.compare = comparison;
+        self
+    }
+
+    /// Advance the calendar by a month every time the given interval passes
+    /// without a keypress
+    pub(crate) fn auto_scroll(mut self, interval: Option<Duration>) -> Self {
+        self.auto_scroll = interval;
+        self.next_scroll = interval.map(|d| Instant::now() + d);
+        self
+    }
+
+    pub(crate) fn background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Show the welcome popup on startup
+    pub(crate) fn welcome(mut self, welcome: bool) -> Self {
+        if welcome {
+            self.state.popup = Some(Popup::Welcome);
+        }
+        self
+    }
+
+    /// Perform the given actions on startup, as though their keys had been
+    /// pressed
+    pub(crate) fn script(mut self, script: Vec<Action>) -> Self {
+        self.script = script;
+        self
+    }
+
+    /// Perform the scripted actions, stopping early if one of them quits
+    fn run_script(&mut self) -> io::Result<()> {
+        for action in std::mem::take(&mut self.script) {
+            let effect = self.state.apply(action);
+            self.perform(effect)?;
+            if self.state.quitting {
+                break;
+            }
         }
         Ok(())
     }
 
     fn draw(&mut self) -> io::Result<()> {
+        self.draw_offset(0, false).map(|_| ())
+    }
+
+    /// Generate the weeks to show at the terminal's current size, running
+    /// the styler on each of their dates, without drawing anything.  Doing
+    /// this before taking over the screen means that the first frame can be
+    /// drawn as soon as the screen is taken over instead of leaving it blank
+    /// while slow stylers run.
+    pub(crate) fn prepare(mut self) -> io::Result<Self> {
+        let size = self.terminal.size()?;
+        let (cal_area, _) = screen_areas(size, self.dashboard());
+        let mut scratch = Buffer::empty(size);
+        self.calendar(0)
+            .render(cal_area, &mut scratch, &mut self.state.weeks);
+        Ok(self)
+    }
+
+    /// The calendar widget to draw, with the weeks shifted down by `offset`
+    /// lines
+    fn calendar(&self, offset: i16) -> Calendar<Highlighter<X>> {
+        Calendar::new()
+            .styles(self.state.weeks.styler().theme.calendar_styles())
+            .ascii(!self.capabilities.unicode)
+            .spacing(self.spacing)
+            .weeks(self.weeks)
+            .grid(self.grid)
+            .month_borders(self.state.month_borders)
+            .month_labels(self.month_labels)
+            .sparkline(self.sparkline)
+            .markers(self.markers)
+            .phase_labels(self.phase_labels)
+            .year_numbering(self.state.year_numbering)
+            .scroll_offset(offset)
+    }
+
+    /// Draw the screen with the weeks shifted down by `offset` lines, in
+    /// reverse video if `flash` is true
+    fn draw_offset(&mut self, offset: i16, flash: bool) -> io::Result<CompletedFrame<'_>> {
+        let cal = self.calendar(offset);
+        let compare = self.compare.as_ref().map(|c| {
+            let highlighter = c.apply(self.state.weeks.styler());
+            let cal = self
+                .calendar(offset)
+                .styles(highlighter.theme.calendar_styles());
+            let labels = c.labels(self.state.weeks.styler());
+            (highlighter, cal, labels)
+        });
+        let ascii = !self.capabilities.unicode;
+        let reduced_motion = self.reduced_motion;
+        let dashboard = self.dashboard();
+        let game_messages = self.game_messages;
+        let notice = self.notice;
+        let state = &mut self.state;
         self.terminal.draw(|frame| {
             let size = frame.size();
-            let defstyle = Style::default().white().on_black();
-            frame.buffer_mut().set_style(size, defstyle);
-            let cal = Calendar::<S>::new();
-            frame.render_stateful_widget(cal, size, &mut self.weeks);
-            if self.helping {
-                frame.render_widget(Help(defstyle), size);
-            }
-        })?;
+            let highlighter = state.weeks.styler().clone();
+            let theme = highlighter.theme;
+            frame.buffer_mut().set_style(size, theme.base);
+            let (cal_area, footer_area) = screen_areas(size, dashboard);
+            if let Some(footer_area) = footer_area {
+                frame.render_widget(
+                    LuckFooter {
+                        luck: Luck::for_date(state.weeks.today()),
+                        date_format: state.date_format,
+                        year_numbering: state.year_numbering,
+                        game_messages,
+                        style: theme.header,
+                    },
+                    footer_area,
+                );
+            }
+            if let Some(selected) = state.list {
+                frame.render_widget(
+                    DayList {
+                        styler: &highlighter,
+                        selected,
+                        today: state.weeks.today(),
+                        date_format: state.date_format,
+                        year_numbering: state.year_numbering,
+                        ascii,
+                    },
+                    cal_area,
+                );
+                // Screen readers follow the cursor, so put it on the selected
+                // day
+                if cal_area.height > 0 {
+                    frame.set_cursor(cal_area.x, DayList::<X>::selected_row(cal_area));
+                }
+            } else if let Some((right_highlighter, right_cal, (left_label, right_label))) = compare
+            {
+                let [left, _, right] = Layout::horizontal([
+                    Constraint::Ratio(1, 2),
+                    Constraint::Length(1),
+                    Constraint::Ratio(1, 2),
+                ])
+                .areas(cal_area);
+                let right_theme = right_highlighter.theme;
+                frame.buffer_mut().set_style(right, right_theme.base);
+                let mut right_weeks = state.weeks.clone();
+                right_weeks.set_styler(right_highlighter);
+                for (area, label, theme) in
+                    [(left, left_label, theme), (right, right_label, right_theme)]
+                {
+                    let [label_area, _] =
+                        Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
+                    frame.render_widget(
+                        Line::styled(label, theme.header).alignment(Alignment::Center),
+                        label_area,
+                    );
+                }
+                let below_label = |area: Rect| Rect {
+                    y: area.y.saturating_add(1).min(area.bottom()),
+                    height: area.height.saturating_sub(1),
+                    ..area
+                };
+                frame.render_stateful_widget(cal, below_label(left), &mut state.weeks);
+                frame.render_stateful_widget(right_cal, below_label(right), &mut right_weeks);
+            } else {
+                frame.render_stateful_widget(cal, cal_area, &mut state.weeks);
+            }
+            let popup_style = PopupStyle {
+                ascii,
+                ..theme.popup_style()
+            };
+            match state.popup {
+                Some(Popup::Welcome) => frame.render_widget(
+                    Welcome {
+                        keymap: &state.keymap,
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::Help) => frame.render_widget(
+                    Help {
+                        keymap: &state.keymap,
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::Info) => frame.render_widget(
+                    DayInfo {
+                        date: state.list.unwrap_or_else(|| state.weeks.today()),
+                        phoon: highlighter.phoon(),
+                        marked: state.weeks.marked_dates(),
+                        location: state.location,
+                        calendars: state.calendars,
+                        date_format: state.date_format,
+                        year_numbering: state.year_numbering,
+                        hemisphere: highlighter.settings.hemisphere,
+                        offset: highlighter.offset,
+                        game_messages,
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::Options { selected }) => frame.render_widget(
+                    OptionsMenu {
+                        settings: highlighter.settings,
+                        selected,
+                        status: state.status.as_deref(),
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::Legend) => frame.render_widget(
+                    Legend {
+                        theme,
+                        settings: highlighter.settings,
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::Months { year, selected }) => frame.render_widget(
+                    MonthMenu {
+                        year,
+                        selected,
+                        year_numbering: state.year_numbering,
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::YearPrompt) => frame.render_widget(
+                    Prompt {
+                        title: " Jump to Year ",
+                        label: "Year: ",
+                        input: &state.input,
+                        preview: None,
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::FindMonth { year }) => {
+                    let preview = match parse_month_query(&state.input, year) {
+                        Some((year, month)) => match state.year_numbering.split(year) {
+                            (year, Some(era)) => format!("Jumps to {month} {year} {era}"),
+                            (year, None) => format!("Jumps to {month} {year}"),
+                        },
+                        None if state.input.is_empty() => {
+                            String::from("e.g., \"mar 27\" or \"2027-03\"")
+                        }
+                        None => String::from("No matching month"),
+                    };
+                    frame.render_widget(
+                        Prompt {
+                            title: " Find Month ",
+                            label: "Month: ",
+                            input: &state.input,
+                            preview: Some(&preview),
+                            style: popup_style,
+                        },
+                        size,
+                    );
+                }
+                Some(Popup::Phases { date }) => frame.render_widget(
+                    PhaseAnimation {
+                        date,
+                        manual: reduced_motion,
+                        phoon: highlighter.phoon(),
+                        hemisphere: highlighter.settings.hemisphere,
+                        date_format: state.date_format,
+                        year_numbering: state.year_numbering,
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::ThemeEditor { element, field }) => frame.render_widget(
+                    ThemeEditor {
+                        theme,
+                        element,
+                        field,
+                        status: state.status.as_deref(),
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::ThemeName { .. }) => frame.render_widget(
+                    Prompt {
+                        title: " Save Theme ",
+                        label: "Name: ",
+                        input: &state.input,
+                        preview: Some("Saved in the themes directory as NAME.toml"),
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::ConfirmQuit) => frame.render_widget(
+                    Confirm {
+                        title: " Quit ",
+                        question: "Really quit?",
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                Some(Popup::Error) => frame.render_widget(
+                    Notice {
+                        title: " Error ",
+                        message: state.status.as_deref().unwrap_or_default(),
+                        style: popup_style,
+                    },
+                    size,
+                ),
+                None => (),
+            }
+            if let Some(prefix) = state.chord_prefix {
+                frame.render_widget(
+                    ChordHints {
+                        keymap: &state.keymap,
+                        prefix,
+                        style: theme.header,
+                    },
+                    size,
+                );
+            }
+            if let Some(notice) = notice {
+                let width = u16::try_from(notice.len()).unwrap_or(u16::MAX);
+                let area = Rect {
+                    x: size.right().saturating_sub(width),
+                    y: size.bottom().saturating_sub(1),
+                    width: width.min(size.width),
+                    height: size.height.min(1),
+                };
+                frame.render_widget(Line::styled(notice, theme.header), area);
+            }
+            if flash {
+                frame
+                    .buffer_mut()
+                    .set_style(size, Style::new().add_modifier(Modifier::REVERSED));
+            }
+        })
+    }
+
+    /// Carry out a side effect requested by the state
+    fn perform(&mut self, effect: Effect) -> io::Result<()> {
+        match effect {
+            Effect::Nothing => Ok(()),
+            Effect::Alert(alert) => self.alert(alert),
+            Effect::SaveSettings => {
+                self.save_settings();
+                Ok(())
+            }
+            Effect::SaveTheme => {
+                self.save_theme();
+                Ok(())
+            }
+            Effect::Play(register) => self.play_macro(register),
+            Effect::CopyTable => self.copy_table(),
+            Effect::Glide(offset) => {
+                // With month spacing, most weeks are only one line apart, so
+                // there's no in-between position to draw
+                if !self.reduced_motion && (self.grid || self.spacing == WeekSpacing::Uniform) {
+                    self.draw_offset(offset, false)?;
+                    std::thread::sleep(GLIDE_FRAME);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Feed the keys recorded in `register` back through the state, stopping
+    /// at the first one that fails
+    fn play_macro(&mut self, register: char) -> io::Result<()> {
+        let keys = self
+            .state
+            .macros
+            .get(&register)
+            .cloned()
+            .unwrap_or_default();
+        self.state.replaying = true;
+        let mut r = Ok(());
+        for key in keys {
+            let effect = self.state.handle_key(key);
+            r = self.perform(effect);
+            if r.is_err() || matches!(effect, Effect::Alert(_)) || self.state.quitting {
+                break;
+            }
+        }
+        self.state.replaying = false;
+        r
+    }
+
+    /// Carry out any commands received from other programs since this was
+    /// last called.  Invalid commands and failures are reported in the error
+    /// popup.
+    fn run_commands(&mut self) {
+        let Some(commands) = self.commands.as_ref() else {
+            return;
+        };
+        let lines = commands.try_iter().collect::<Vec<_>>();
+        for line in lines {
+            let r = match line {
+                Ok(line) => match ControlCommand::parse(&line, self.state.date_format) {
+                    Ok(Some(cmd)) => self.run_command(cmd),
+                    Ok(None) => Ok(()),
+                    Err(e) => Err(anyhow::Error::new(e)),
+                },
+                Err(e) => {
+                    self.commands = None;
+                    Err(anyhow::Error::new(e).context("failed to read commands"))
+                }
+            };
+            if let Err(e) = r {
+                self.state.status = Some(format!("{e:#}"));
+                self.state.popup = Some(Popup::Error);
+            }
+        }
+    }
+
+    fn run_command(&mut self, cmd: ControlCommand) -> anyhow::Result<()> {
+        match cmd {
+            ControlCommand::Action(action) => {
+                let effect = self.state.apply(action);
+                self.perform(effect)?;
+            }
+            ControlCommand::Jump(date) => self.state.go_to(date),
+            ControlCommand::Screenshot(path) => {
+                let text = buffer_text(self.draw_offset(0, false)?.buffer);
+                fs::write(&path, text)
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+            }
+        }
         Ok(())
     }
 
+    fn save_settings(&mut self) {
+        let settings = self.state.weeks.styler().settings;
+        let values = Setting::ALL.map(|s| (s.key(), s.value(&settings)));
+        self.state.status = Some(match save_config(&self.config, &values) {
+            Ok(path) => {
+                self.watcher.mark_seen();
+                format!("Saved to {}", path.display())
+            }
+            Err(e) => format!("Error: {:#}", anyhow::Error::new(e)),
+        });
+    }
+
+    fn save_theme(&mut self) {
+        let name = std::mem::take(&mut self.state.input);
+        let theme = self.state.weeks.styler().theme;
+        self.state.status = Some(match save_theme(&name, theme) {
+            Ok(path) => format!("Saved to {}", path.display()),
+            Err(e) => format!("Error: {:#}", anyhow::Error::new(e)),
+        });
+    }
+
+    fn check_config(&mut self) {
+        let reloaded = self.watcher.poll().map(|r| {
+            let opts = self.overrides.clone().or(r?);
+            let theme = self
+                .capabilities
+                .with_colors(opts.color_depth)
+                .adapt_theme(load_theme(opts.theme(self.background.default_theme()))?);
+            Ok::<_, ConfigError>((opts, theme))
+        });
+        let state = &mut self.state;
+        match reloaded {
+            Some(Ok((opts, theme))) => {
+                self.feedback = opts.feedback();
+                self.spacing = opts.spacing();
+                self.weeks = opts.weeks;
+                self.grid = opts.grid();
+                self.month_labels = opts.month_labels();
+                self.sparkline = opts.sparkline();
+                self.markers = opts.markers();
+                self.phase_labels = opts.phase_labels();
+                self.game_messages = opts.game_messages();
+                self.reduced_motion = opts.reduced_motion();
+                if let Some(compare) = self.compare.as_mut() {
+                    compare.base_theme = opts.theme(self.background.default_theme()).to_owned();
+                }
+                state.location = opts.location;
+                state.calendars = opts.calendars();
+                state.date_format = opts.date_format();
+                state.year_numbering = opts.year_numbering();
+                state.month_borders = opts.month_borders();
+                state.keymap = opts.keymap();
+                state.confirm_quit = opts.confirm_quit();
+                let settings = Settings::from(&opts);
+                let mut highlighter = state.weeks.styler().clone();
+                highlighter.settings.apply_changes(&self.loaded, &settings);
+                highlighter.theme = theme;
+                self.loaded = settings;
+                let WeekStart(week_start) = highlighter.settings.week_start;
+                state.weeks.set_styler(highlighter);
+                state.weeks.set_week_start(week_start);
+            }
+            Some(Err(e)) => {
+                state.status = Some(format!("{:#}", anyhow::Error::new(e)));
+                state.popup = Some(Popup::Error);
+            }
+            None => (),
+        }
+    }
+
+    /// Whether to keep today centered as the date changes and show a footer
+    /// of luck information, as set by `--dashboard` or the options popup
+    fn dashboard(&self) -> bool {
+        self.state.weeks.styler().settings.dashboard
+    }
+
+    /// If the date has changed, make the new date today and center it
+    fn follow_today(&mut self) {
+        let offset = self.state.weeks.styler().offset;
+        let now = OffsetDateTime::now_local()
+            .unwrap_or_else(|_| OffsetDateTime::now_utc().to_offset(offset));
+        self.state.set_today(now.date());
+    }
+
+    /// Step the phase animation if it's open and a frame has passed since it
+    /// last stepped.  Input is polled more often than that when watching for
+    /// commands or auto-scrolling, so this can't step on every timeout.
+    fn advance_animation(&mut self, now: Instant) {
+        let animating = matches!(self.state.popup, Some(Popup::Phases { .. }));
+        if !animating || self.reduced_motion {
+            self.last_frame = now;
+        } else if now.saturating_duration_since(self.last_frame) >= ANIMATION_FRAME {
+            self.state.step_animation(true);
+            self.last_frame = now;
+        }
+    }
+
+    /// Advance the calendar by a month if it's time to do so
+    fn auto_advance(&mut self) {
+        if let (Some(interval), Some(deadline)) = (self.auto_scroll, self.next_scroll) {
+            let now = Instant::now();
+            if now >= deadline {
+                self.state.advance_month();
+                self.next_scroll = Some(now + interval);
+            }
+        }
+    }
+
+    /// Copy the dates currently shown as a Markdown table
+    fn copy_table(&mut self) -> io::Result<()> {
+        let state = &self.state;
+        let Some((start, end)) = state.weeks.shown_dates() else {
+            return Ok(());
+        };
+        let table = phase_table(
+            &state.weeks.styler().phoon(),
+            start,
+            end,
+            state.date_format,
+            state.year_numbering,
+        );
+        self.terminal.backend_mut().copy(&table)
+    }
+
+    /// Give the feedback configured for `alert`
+    fn alert(&mut self, alert: Alert) -> io::Result<()> {
+        match self.feedback.get(alert) {
+            Feedback::Bell => self.terminal.backend_mut().bell(),
+            Feedback::Flash => {
+                self.draw_offset(0, true)?;
+                std::thread::sleep(FLASH_FRAME);
+                Ok(())
+            }
+            Feedback::Message => {
+                self.notice = Some(alert.message());
+                Ok(())
+            }
+            Feedback::None => Ok(()),
+        }
+    }
+}
+
+impl<W: io::Write, X: DateStyler + Clone> App<'_, CrosstermBackend<W>, X> {
+    /// Run the calendar until the user quits, returning its final state
+    pub(crate) fn run(mut self) -> io::Result<Session> {
+        // The calendar doesn't know how many weeks to show until it's been
+        // drawn, so the script can't be run until then.
+        self.draw()?;
+        self.run_script()?;
+        while !self.state.quitting {
+            self.draw()?;
+            self.handle_input()?;
+        }
+        Ok(Session::from_window(&self.state.weeks))
+    }
+
     fn handle_input(&mut self) -> io::Result<()> {
+        let mut interval = if self.commands.is_some() {
+            COMMAND_INTERVAL
+        } else {
+            RELOAD_INTERVAL
+        };
+        if let Some(deadline) = self.next_scroll {
+            interval = interval.min(deadline.saturating_duration_since(Instant::now()));
+        }
+        let animating = matches!(self.state.popup, Some(Popup::Phases { .. }));
+        if animating && !self.reduced_motion {
+            let next_frame = self.last_frame + ANIMATION_FRAME;
+            interval = interval.min(next_frame.saturating_duration_since(Instant::now()));
+        }
+        if !poll(interval)? {
+            self.advance_animation(Instant::now());
+            self.check_config();
+            self.run_commands();
+            if self.dashboard() {
+                self.follow_today();
+            }
+            self.auto_advance();
+            return Ok(());
+        }
         let normal_modifiers = KeyModifiers::NONE | KeyModifiers::SHIFT;
         if let Event::Key(KeyEvent {
             code,
@@ -59,72 +955,1280 @@ impl<S: DateStyler> App<S> {
             ..
         }) = read()?
         {
-            if normal_modifiers.contains(modifiers) {
-                self.handle_key(code)?;
-            } else {
-                self.beep()?;
+            if let Some(interval) = self.auto_scroll {
+                self.next_scroll = Some(Instant::now() + interval.max(AUTO_SCROLL_PAUSE));
             }
+            self.notice = None;
+            let effect = if normal_modifiers.contains(modifiers) {
+                self.state.handle_key(code)
+            } else if modifiers == KeyModifiers::CONTROL && code == KeyCode::Char('c') {
+                // Always quit at once, even if `quit` asks first
+                self.state.quitting = true;
+                Effect::Nothing
+            } else {
+                Effect::Alert(Alert::InvalidKey)
+            };
+            self.perform(effect)?;
         }
         // else: Redraw on resize, and we might as well redraw on other stuff
         // too
         Ok(())
     }
+}
 
-    fn handle_key(&mut self, key: KeyCode) -> io::Result<()> {
-        if self.helping {
-            self.helping = false;
-            return Ok(());
+/// Split the screen into the area in which the calendar is drawn and, in
+/// dashboard mode, the line below it for the footer
+fn screen_areas(size: Rect, dashboard: bool) -> (Rect, Option<Rect>) {
+    if dashboard {
+        let [above, footer] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(size);
+        (above, Some(footer))
+    } else {
+        (size, None)
+    }
+}
+
+impl<X: DateStyler + Clone> App<'_, TestBackend, X> {
+    /// Perform the scripted actions without waiting for input and return the
+    /// rendered frames.  If `frames` is `None`, only the final frame is
+    /// returned; otherwise, the frames drawn on startup and after each action
+    /// are returned, up to `frames` of them.
+    pub(crate) fn dump(mut self, frames: Option<usize>) -> io::Result<Vec<Buffer>> {
+        self.draw()?;
+        let mut buffers = vec![self.terminal.backend().buffer().clone()];
+        for action in std::mem::take(&mut self.script) {
+            if self.state.quitting || frames.is_some_and(|n| buffers.len() >= n) {
+                break;
+            }
+            let effect = self.state.apply(action);
+            self.perform(effect)?;
+            self.draw()?;
+            buffers.push(self.terminal.backend().buffer().clone());
         }
-        match key {
-            KeyCode::Char('j') | KeyCode::Down => self.scroll_down()?,
-            KeyCode::Char('k') | KeyCode::Up => self.scroll_up()?,
-            KeyCode::Char('z') | KeyCode::PageDown => self.page_down()?,
-            KeyCode::Char('w') | KeyCode::PageUp => self.page_up()?,
-            KeyCode::Char('0') | KeyCode::Home => self.reset(),
-            KeyCode::Char('q') | KeyCode::Esc => self.quit(),
-            KeyCode::Char('?') => self.helping = true,
-            _ => self.beep()?,
+        if frames.is_none() {
+            buffers = buffers.split_off(buffers.len() - 1);
         }
-        Ok(())
+        Ok(buffers)
     }
+}
+
+/// Builder for running the calendar pager inside a terminal owned by another
+/// program, e.g., as a sub-screen of a larger TUI.
+///
+/// The embedded calendar uses the default theme and settings, doesn't read or
+/// write any configuration file, and doesn't show the welcome popup.
+#[derive(Clone, Debug)]
+pub struct AppBuilder<X = ()> {
+    today: Date,
+    offset: UtcOffset,
+    styler: X,
+    keymap: Keymap,
+}
 
-    fn scroll_down(&mut self) -> io::Result<()> {
-        if self.weeks.one_week_forwards().is_err() {
-            self.beep()?;
+impl AppBuilder {
+    /// Start building a calendar for today's date in the local timezone (or
+    /// UTC, if the local offset cannot be determined) using the default key
+    /// bindings and no additional styler
+    pub fn new() -> AppBuilder {
+        let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+        AppBuilder {
+            today: now.date(),
+            offset: now.offset(),
+            styler: (),
+            keymap: Keymap::default(),
         }
-        Ok(())
     }
+}
+
+impl Default for AppBuilder {
+    fn default() -> AppBuilder {
+        AppBuilder::new()
+    }
+}
 
-    fn scroll_up(&mut self) -> io::Result<()> {
-        if self.weeks.one_week_backwards().is_err() {
-            self.beep()?;
+impl<X: DateStyler + Clone> AppBuilder<X> {
+    /// Set the date that the calendar treats as today
+    pub fn today(mut self, today: Date) -> Self {
+        self.today = today;
+        self
+    }
+
+    /// Set a styler whose styles are patched on top of the calendar's own
+    /// highlighting
+    pub fn styler<Y: DateStyler + Clone>(self, styler: Y) -> AppBuilder<Y> {
+        AppBuilder {
+            today: self.today,
+            offset: self.offset,
+            styler,
+            keymap: self.keymap,
         }
+    }
+
+    /// Set the key bindings
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    /// Run the calendar in `terminal` until the user quits.
+    ///
+    /// The caller is responsible for putting the terminal into raw mode (and,
+    /// usually, the alternate screen) beforehand and for restoring it
+    /// afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if drawing to the terminal or reading input fails
+    pub fn run<W: io::Write>(self, terminal: &mut Terminal<CrosstermBackend<W>>) -> io::Result<()> {
+        let capabilities = Capabilities::detect();
+        let highlighter = Highlighter {
+            settings: Settings::default(),
+            theme: capabilities.adapt_theme(Theme::default()),
+            offset: self.offset,
+            extra: self.styler,
+        };
+        App::new(terminal, WeekWindow::new(self.today, highlighter))
+            .keymap(self.keymap)
+            .capabilities(capabilities)
+            .config(ConfigSource::Disabled)
+            .run()?;
         Ok(())
     }
+}
+
+/// Everything about the calendar that changes in response to input.  Input
+/// is processed by the methods of this type without performing any I/O;
+/// anything that the terminal or filesystem needs to do in response is
+/// returned as an [`Effect`] for `App` to carry out.
+#[derive(Debug)]
+struct State<X = ()> {
+    weeks: WeekWindow<Highlighter<X>>,
+    location: Option<Location>,
+    calendars: OtherCalendars,
+    date_format: DateFormat,
+    year_numbering: YearNumbering,
+    /// Which borders between months are drawn
+    month_borders: MonthBorders,
+    keymap: Keymap,
+    /// Whether the `quit` action asks before quitting
+    confirm_quit: bool,
+    /// The actions most recently performed
+    action_log: ActionLog,
+    /// The day selected in the list view, or `None` if the calendar is shown
+    /// as a grid of weeks
+    list: Option<Date>,
+    quitting: bool,
+    popup: Option<Popup>,
+    /// Message about the last attempt to save settings from the options popup
+    /// or to reload the configuration file
+    status: Option<String>,
+    /// What has been typed so far into the current prompt
+    input: String,
+    /// The last jump made and whether it went forwards, for `repeat-jump` &
+    /// `reverse-jump`
+    last_jump: Option<(Jump, bool)>,
+    /// What the next key pressed will be taken as the register for
+    awaiting_register: Option<RegisterUse>,
+    /// The first key of the chord being typed, if any
+    chord_prefix: Option<KeyCode>,
+    /// The register being recorded into and the keys recorded so far
+    recording: Option<(char, Vec<KeyCode>)>,
+    /// Keys recorded into each register
+    macros: HashMap<char, Vec<KeyCode>>,
+    /// Whether a macro is being replayed, during which other macros can't be
+    /// played
+    replaying: bool,
+}
+
+impl<X: DateStyler + Clone> State<X> {
+    fn new(weeks: WeekWindow<Highlighter<X>>) -> State<X> {
+        State {
+            weeks,
+            location: None,
+            calendars: OtherCalendars::default(),
+            date_format: DateFormat::default(),
+            year_numbering: YearNumbering::default(),
+            month_borders: MonthBorders::default(),
+            keymap: Keymap::default(),
+            confirm_quit: false,
+            action_log: ActionLog::default(),
+            list: None,
+            quitting: false,
+            popup: None,
+            status: None,
+            input: String::new(),
+            last_jump: None,
+            awaiting_register: None,
+            chord_prefix: None,
+            recording: None,
+            macros: HashMap::new(),
+            replaying: false,
+        }
+    }
 
-    fn page_down(&mut self) -> io::Result<()> {
-        if self.weeks.one_page_forwards().is_err() {
-            self.beep()?;
+    /// Respond to a keypress, dismissing or navigating the current popup if
+    /// there is one and otherwise performing the action bound to the key
+    fn handle_key(&mut self, key: KeyCode) -> Effect {
+        let recording = self.recording.is_some();
+        let effect = self.dispatch_key(key);
+        // Only keys pressed after recording started and before it stopped are
+        // recorded
+        if let Some((_, keys)) = self.recording.as_mut().filter(|_| recording) {
+            keys.push(key);
         }
-        Ok(())
+        effect
     }
 
-    fn page_up(&mut self) -> io::Result<()> {
-        if self.weeks.one_page_backwards().is_err() {
-            self.beep()?;
+    fn dispatch_key(&mut self, key: KeyCode) -> Effect {
+        if let Some(purpose) = self.awaiting_register.take() {
+            return self.use_register(purpose, key);
         }
-        Ok(())
+        match self.popup {
+            Some(Popup::Options { selected }) => self.handle_options_key(key, selected),
+            Some(Popup::Months { year, selected }) => self.handle_months_key(key, year, selected),
+            Some(Popup::YearPrompt) => self.handle_year_key(key),
+            Some(Popup::FindMonth { year }) => self.handle_find_month_key(key, year),
+            Some(Popup::ThemeEditor { element, field }) => {
+                self.handle_theme_editor_key(key, element, field)
+            }
+            Some(Popup::ThemeName { element, field }) => {
+                self.handle_theme_name_key(key, element, field)
+            }
+            Some(Popup::ConfirmQuit) => {
+                self.popup = None;
+                self.quitting = matches!(key, KeyCode::Char('y' | 'Y') | KeyCode::Enter);
+                Effect::Nothing
+            }
+            Some(Popup::Phases { .. }) if matches!(key, KeyCode::Left | KeyCode::Right) => {
+                if self.step_animation(key == KeyCode::Right) {
+                    Effect::Nothing
+                } else {
+                    Effect::Alert(Alert::FailedJump)
+                }
+            }
+            Some(_) => {
+                self.popup = None;
+                self.status = None;
+                Effect::Nothing
+            }
+            None => match self.chord_prefix.take() {
+                Some(prefix) => match self.keymap.get_chord(prefix, key) {
+                    Some(action) => self.apply(action),
+                    None if key == KeyCode::Esc => Effect::Nothing,
+                    None => Effect::Alert(Alert::InvalidKey),
+                },
+                None if self.keymap.is_prefix(key) => {
+                    self.chord_prefix = Some(key);
+                    Effect::Nothing
+                }
+                None => match self.keymap.get(key) {
+                    Some(action) => self.apply(action),
+                    None => Effect::Alert(Alert::InvalidKey),
+                },
+            },
+        }
+    }
+
+    /// Change the date treated as today, jumping to it if it's different
+    /// from the old one
+    fn set_today(&mut self, today: Date) {
+        if today != self.weeks.today() {
+            self.weeks.set_today(today);
+            self.go_to(today);
+        }
+    }
+
+    /// Move the phase animation to the next (or, if `forwards` is false,
+    /// previous) day of its month.  Returns `false` if it's not open or is
+    /// already at that end of the month.
+    fn step_animation(&mut self, forwards: bool) -> bool {
+        let Some(Popup::Phases { date }) = self.popup else {
+            return false;
+        };
+        let step = if forwards {
+            date.next_day()
+        } else {
+            date.previous_day()
+        };
+        match step.filter(|d| d.month() == date.month()) {
+            Some(date) => {
+                self.popup = Some(Popup::Phases { date });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Jump to the start of the month after the one in which the focused
+    /// week ends, which is the month just jumped to if the last jump was also
+    /// by a month
+    fn advance_month(&mut self) {
+        let week_end = self
+            .weeks
+            .focus_date()
+            .saturating_add(time::Duration::days(6));
+        if let Ok(start) = week_end.replace_day(1) {
+            let _ = self.jump(Jump::Month(start), true);
+        }
+    }
+
+    /// Perform an action
+    fn apply(&mut self, action: Action) -> Effect {
+        self.action_log.push(action);
+        let ok = match (action, self.list) {
+            // In the list view, scrolling moves by a day and paging by a week
+            (Action::ScrollDown, Some(date)) => self.go_to_day(date.next_day()),
+            (Action::ScrollUp, Some(date)) => self.go_to_day(date.previous_day()),
+            (Action::PageDown, Some(date)) => {
+                self.go_to_day(date.checked_add(time::Duration::weeks(1)))
+            }
+            (Action::PageUp, Some(date)) => {
+                self.go_to_day(date.checked_sub(time::Duration::weeks(1)))
+            }
+            (Action::ScrollDown, None) => self.weeks.one_week_forwards().is_ok(),
+            (Action::ScrollUp, None) => self.weeks.one_week_backwards().is_ok(),
+            (Action::PageDown, None) => self.weeks.one_page_forwards().is_ok(),
+            (Action::PageUp, None) => self.weeks.one_page_backwards().is_ok(),
+            (Action::Today, _) => {
+                self.weeks.jump_to_today();
+                self.select(self.weeks.today());
+                true
+            }
+            (Action::Recenter, _) => {
+                self.weeks.recenter();
+                let today = self.weeks.today();
+                let date = self
+                    .weeks
+                    .marked_dates()
+                    .get(self.weeks.current_marked_index())
+                    .copied()
+                    .unwrap_or(today);
+                self.select(date);
+                true
+            }
+            (Action::NextDate | Action::PrevDate, _) => {
+                let ok = self.weeks.cycle_marked(action == Action::NextDate);
+                if let Some(&date) = self
+                    .weeks
+                    .marked_dates()
+                    .get(self.weeks.current_marked_index())
+                {
+                    self.select(date);
+                }
+                ok
+            }
+            (Action::ListView, Some(_)) => {
+                self.list = None;
+                true
+            }
+            (Action::ListView, None) => {
+                let today = self.weeks.today();
+                let date = match self.weeks.shown_dates() {
+                    Some((start, end)) if (start..=end).contains(&today) => today,
+                    _ => self.weeks.focus_date(),
+                };
+                self.list = Some(date);
+                true
+            }
+            (Action::MonthBorders, _) => {
+                self.month_borders = self.month_borders.next();
+                true
+            }
+            (Action::NextFullMoon, _) => self.start_jump(Jump::Phase(Phase::Full), true),
+            (Action::PrevFullMoon, _) => self.start_jump(Jump::Phase(Phase::Full), false),
+            (Action::NextNewMoon, _) => self.start_jump(Jump::Phase(Phase::New), true),
+            (Action::PrevNewMoon, _) => self.start_jump(Jump::Phase(Phase::New), false),
+            (Action::RecordMacro, _) => {
+                if let Some((register, keys)) = self.recording.take() {
+                    self.macros.insert(register, keys);
+                } else {
+                    self.awaiting_register = Some(RegisterUse::Record);
+                }
+                true
+            }
+            (Action::PlayMacro, _) => {
+                if !self.replaying {
+                    self.awaiting_register = Some(RegisterUse::Play);
+                }
+                !self.replaying
+            }
+            (Action::RepeatJump, _) => self.repeat_jump(false),
+            (Action::ReverseJump, _) => self.repeat_jump(true),
+            (Action::Quit, _) if self.confirm_quit => self.open(Popup::ConfirmQuit),
+            (Action::Quit, _) => {
+                self.quitting = true;
+                true
+            }
+            (Action::JumpToYear, _) => {
+                self.input.clear();
+                self.open(Popup::YearPrompt)
+            }
+            (Action::ChooseMonth, _) => {
+                let date = self.focus_middle();
+                self.open(Popup::Months {
+                    year: date.year(),
+                    selected: usize::from(u8::from(date.month()) - 1),
+                })
+            }
+            (Action::FindMonth, _) => {
+                self.input.clear();
+                let year = self.focus_middle().year();
+                self.open(Popup::FindMonth { year })
+            }
+            (Action::AnimatePhases, _) => match self.focus_middle().replace_day(1) {
+                Ok(date) => self.open(Popup::Phases { date }),
+                Err(_) => false,
+            },
+            (Action::CopyTable, _) => self.weeks.shown_dates().is_some(),
+            (Action::Info, _) => self.open(Popup::Info),
+            (Action::Legend, _) => self.open(Popup::Legend),
+            (Action::Options, _) => self.open(Popup::Options { selected: 0 }),
+            (Action::EditTheme, _) => self.open(Popup::ThemeEditor {
+                element: 0,
+                field: 0,
+            }),
+            (Action::Help, _) => self.open(Popup::Help),
+        };
+        match (ok, action) {
+            (false, Action::ScrollDown | Action::ScrollUp | Action::PageDown | Action::PageUp) => {
+                Effect::Alert(Alert::EndOfTime)
+            }
+            (false, _) => Effect::Alert(Alert::FailedJump),
+            // The new weeks are drawn one line away from where they end up,
+            // i.e., halfway between the old position and the new
+            (true, Action::ScrollDown) if self.list.is_none() => Effect::Glide(1),
+            (true, Action::ScrollUp) if self.list.is_none() => Effect::Glide(-1),
+            (true, Action::CopyTable) => Effect::CopyTable,
+            (true, _) => Effect::Nothing,
+        }
+    }
+
+    /// Position the calendar around `date`, also selecting it if the list
+    /// view is shown
+    fn go_to(&mut self, date: Date) {
+        self.weeks.jump_to(date);
+        self.select(date);
+    }
+
+    /// Move the list view's selection to `date`, returning `false` if it's
+    /// past the end of time
+    fn go_to_day(&mut self, date: Option<Date>) -> bool {
+        match date {
+            Some(date) => {
+                self.go_to(date);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Select `date` in the list view, if it's shown
+    fn select(&mut self, date: Date) {
+        if self.list.is_some() {
+            self.list = Some(date);
+        }
+    }
+
+    /// Start recording into or play the register named by `key`
+    fn use_register(&mut self, purpose: RegisterUse, key: KeyCode) -> Effect {
+        let KeyCode::Char(register) = key else {
+            return Effect::Alert(Alert::InvalidKey);
+        };
+        if !register.is_ascii_alphanumeric() {
+            return Effect::Alert(Alert::InvalidKey);
+        }
+        match purpose {
+            RegisterUse::Record => {
+                self.recording = Some((register, Vec::new()));
+                Effect::Nothing
+            }
+            RegisterUse::Play if self.macros.contains_key(&register) => Effect::Play(register),
+            RegisterUse::Play => Effect::Alert(Alert::EmptyRegister),
+        }
+    }
+
+    /// Perform a jump and remember it for `repeat-jump` & `reverse-jump`
+    fn start_jump(&mut self, jump: Jump, forwards: bool) -> bool {
+        match self.jump(jump, forwards) {
+            Some(jump) => {
+                self.last_jump = Some((jump, forwards));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Perform the last jump again, in the opposite direction if `reverse` is
+    /// true
+    fn repeat_jump(&mut self, reverse: bool) -> bool {
+        let Some((jump, forwards)) = self.last_jump else {
+            return false;
+        };
+        match self.jump(jump, forwards != reverse) {
+            Some(jump) => {
+                self.last_jump = Some((jump, forwards));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Perform a jump forwards or backwards, returning the jump to make if
+    /// it's repeated, or `None` if the jump would go past the end of time
+    fn jump(&mut self, jump: Jump, forwards: bool) -> Option<Jump> {
+        match jump {
+            Jump::Phase(phase) => self.jump_to_phase(phase, forwards).then_some(jump),
+            Jump::Month(date) => {
+                let (year, month) = match (forwards, date.month()) {
+                    (true, Month::December) => (date.year().checked_add(1)?, Month::January),
+                    (true, m) => (date.year(), m.next()),
+                    (false, Month::January) => (date.year().checked_sub(1)?, Month::December),
+                    (false, m) => (date.year(), m.previous()),
+                };
+                let date = Date::from_calendar_date(year, month, 1).ok()?;
+                self.go_to(date);
+                Some(Jump::Month(date))
+            }
+            Jump::Year(year) => {
+                let year = if forwards {
+                    year.checked_add(1)?
+                } else {
+                    year.checked_sub(1)?
+                };
+                let date = Date::from_calendar_date(year, Month::January, 1).ok()?;
+                self.go_to(date);
+                Some(Jump::Year(year))
+            }
+        }
+    }
+
+    /// Jump to the start of the next (or, if `forwards` is false, previous)
+    /// run of `phase` days that begins outside of the week that the calendar
+    /// is focused on, or after (or before) the selected day in the list view.
+    /// Returns `false` if there is no such run before the end of time.
+    fn jump_to_phase(&mut self, phase: Phase, forwards: bool) -> bool {
+        let phoon = self.weeks.styler().phoon();
+        let focus = self.list.unwrap_or_else(|| self.weeks.focus_date());
+        let target = if forwards {
+            let week_end = if self.list.is_some() {
+                focus
+            } else {
+                focus
+                    .checked_add(time::Duration::days(6))
+                    .unwrap_or(Date::MAX)
+            };
+            phoon.next_phase(week_end, phase)
+        } else {
+            phoon.previous_phase(focus, phase)
+        };
+        match target {
+            Some(date) => {
+                self.go_to(date);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the middle day of the focused week, so that a week spanning
+    /// New Year's belongs to the year with more of it, or the selected day in
+    /// the list view
+    fn focus_middle(&self) -> Date {
+        if let Some(date) = self.list {
+            return date;
+        }
+        self.weeks
+            .focus_date()
+            .saturating_add(time::Duration::days(3))
+    }
+
+    fn open(&mut self, popup: Popup) -> bool {
+        self.popup = Some(popup);
+        true
+    }
+
+    fn handle_options_key(&mut self, key: KeyCode, selected: usize) -> Effect {
+        let qty = Setting::ALL.len();
+        match key {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.popup = Some(Popup::Options {
+                    selected: (selected + 1) % qty,
+                });
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.popup = Some(Popup::Options {
+                    selected: (selected + qty - 1) % qty,
+                });
+            }
+            KeyCode::Char('l' | ' ') | KeyCode::Right | KeyCode::Enter => {
+                self.adjust(Setting::ALL[selected], true);
+            }
+            KeyCode::Char('h') | KeyCode::Left => self.adjust(Setting::ALL[selected], false),
+            KeyCode::Char('s') => return Effect::SaveSettings,
+            KeyCode::Char('o' | 'q') | KeyCode::Esc => {
+                self.popup = None;
+                self.status = None;
+            }
+            _ => return Effect::Alert(Alert::InvalidKey),
+        }
+        Effect::Nothing
+    }
+
+    fn handle_months_key(&mut self, key: KeyCode, year: i32, selected: usize) -> Effect {
+        let qty = MONTH_KEYS.len();
+        let chosen = match key {
+            KeyCode::Down => {
+                self.popup = Some(Popup::Months {
+                    year,
+                    selected: (selected + 1) % qty,
+                });
+                return Effect::Nothing;
+            }
+            KeyCode::Up => {
+                self.popup = Some(Popup::Months {
+                    year,
+                    selected: (selected + qty - 1) % qty,
+                });
+                return Effect::Nothing;
+            }
+            KeyCode::Enter => selected,
+            KeyCode::Char(c) if MONTH_KEYS.contains(&c) => {
+                MONTH_KEYS.iter().position(|&k| k == c).unwrap_or(selected)
+            }
+            KeyCode::Char('m') | KeyCode::Esc => {
+                self.popup = None;
+                return Effect::Nothing;
+            }
+            _ => return Effect::Alert(Alert::InvalidKey),
+        };
+        let month = u8::try_from(chosen + 1)
+            .ok()
+            .and_then(|m| Month::try_from(m).ok());
+        match month.and_then(|m| Date::from_calendar_date(year, m, 1).ok()) {
+            Some(date) => {
+                self.go_to(date);
+                self.last_jump = Some((Jump::Month(date), true));
+                self.popup = None;
+                Effect::Nothing
+            }
+            None => Effect::Alert(Alert::FailedJump),
+        }
+    }
+
+    /// Edit the year being typed into the year prompt, jumping to January 1
+    /// of it when Enter is pressed.  Years are given in astronomical
+    /// numbering, with a leading `-` for negative years.
+    fn handle_year_key(&mut self, key: KeyCode) -> Effect {
+        let max_digits = Date::MAX.year().to_string().len();
+        match key {
+            KeyCode::Char('-') if self.input.is_empty() => self.input.push('-'),
+            KeyCode::Char(c)
+                if c.is_ascii_digit() && self.input.trim_start_matches('-').len() < max_digits =>
+            {
+                self.input.push(c);
+            }
+            KeyCode::Backspace => {
+                if self.input.pop().is_none() {
+                    return Effect::Alert(Alert::InvalidKey);
+                }
+            }
+            KeyCode::Enter => {
+                let Some(date) = self
+                    .input
+                    .parse::<i32>()
+                    .ok()
+                    .and_then(|y| Date::from_calendar_date(y, Month::January, 1).ok())
+                else {
+                    return Effect::Alert(Alert::FailedJump);
+                };
+                self.go_to(date);
+                self.last_jump = Some((Jump::Year(date.year()), true));
+                self.popup = None;
+            }
+            KeyCode::Esc => self.popup = None,
+            _ => return Effect::Alert(Alert::InvalidKey),
+        }
+        Effect::Nothing
+    }
+
+    /// Edit the text typed into the find-month prompt, jumping to the first
+    /// day of the month it describes when Enter is pressed.  Two-digit years
+    /// are taken to be in the same century as `year`, the year the calendar
+    /// was showing when the prompt was opened.
+    fn handle_find_month_key(&mut self, key: KeyCode, year: i32) -> Effect {
+        match key {
+            KeyCode::Char(c) if !c.is_control() && self.input.len() < 32 => self.input.push(c),
+            KeyCode::Backspace => {
+                if self.input.pop().is_none() {
+                    return Effect::Alert(Alert::InvalidKey);
+                }
+            }
+            KeyCode::Enter => {
+                let Some(date) = parse_month_query(&self.input, year)
+                    .and_then(|(y, m)| Date::from_calendar_date(y, m, 1).ok())
+                else {
+                    return Effect::Alert(Alert::FailedJump);
+                };
+                self.go_to(date);
+                self.last_jump = Some((Jump::Month(date), true));
+                self.popup = None;
+            }
+            KeyCode::Esc => self.popup = None,
+            _ => return Effect::Alert(Alert::InvalidKey),
+        }
+        Effect::Nothing
+    }
+
+    fn handle_theme_editor_key(&mut self, key: KeyCode, element: usize, field: usize) -> Effect {
+        let elements = Theme::ELEMENTS.len();
+        let fields = StyleField::ALL.len();
+        let (element, field) = match key {
+            KeyCode::Char('j') | KeyCode::Down => ((element + 1) % elements, field),
+            KeyCode::Char('k') | KeyCode::Up => ((element + elements - 1) % elements, field),
+            KeyCode::Tab => (element, (field + 1) % fields),
+            KeyCode::BackTab => (element, (field + fields - 1) % fields),
+            KeyCode::Char('l' | ' ') | KeyCode::Right | KeyCode::Enter => {
+                self.edit_style(element, StyleField::ALL[field], true);
+                (element, field)
+            }
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.edit_style(element, StyleField::ALL[field], false);
+                (element, field)
+            }
+            KeyCode::Char('s') => {
+                self.input.clear();
+                self.popup = Some(Popup::ThemeName { element, field });
+                return Effect::Nothing;
+            }
+            KeyCode::Char('T' | 'q') | KeyCode::Esc => {
+                self.popup = None;
+                self.status = None;
+                return Effect::Nothing;
+            }
+            _ => return Effect::Alert(Alert::InvalidKey),
+        };
+        self.popup = Some(Popup::ThemeEditor { element, field });
+        Effect::Nothing
+    }
+
+    fn handle_theme_name_key(&mut self, key: KeyCode, element: usize, field: usize) -> Effect {
+        match key {
+            KeyCode::Char(c)
+                if (c.is_ascii_alphanumeric() || c == '-' || c == '_') && self.input.len() < 32 =>
+            {
+                self.input.push(c);
+                return Effect::Nothing;
+            }
+            KeyCode::Backspace => {
+                if self.input.pop().is_none() {
+                    return Effect::Alert(Alert::InvalidKey);
+                }
+                return Effect::Nothing;
+            }
+            KeyCode::Enter if self.input.is_empty() => return Effect::Alert(Alert::FailedJump),
+            KeyCode::Enter => {
+                self.popup = Some(Popup::ThemeEditor { element, field });
+                return Effect::SaveTheme;
+            }
+            KeyCode::Esc => (),
+            _ => return Effect::Alert(Alert::InvalidKey),
+        }
+        self.popup = Some(Popup::ThemeEditor { element, field });
+        Effect::Nothing
+    }
+
+    /// Change one part of the style of the theme element at index `element`
+    fn edit_style(&mut self, element: usize, field: StyleField, forwards: bool) {
+        let mut highlighter = self.weeks.styler().clone();
+        if let Some(style) = highlighter.theme.styles_mut().into_iter().nth(element) {
+            *style = field.adjust(*style, forwards);
+        }
+        self.weeks.set_styler(highlighter);
+    }
+
+    fn adjust(&mut self, setting: Setting, forwards: bool) {
+        let mut highlighter = self.weeks.styler().clone();
+        setting.adjust(&mut highlighter.settings, forwards);
+        let WeekStart(week_start) = highlighter.settings.week_start;
+        self.weeks.set_styler(highlighter);
+        self.weeks.set_week_start(week_start);
+    }
+}
+
+/// A side effect of processing input that `State` leaves to `App`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Effect {
+    Nothing,
+    /// Alert the user that a key or action couldn't be carried out
+    Alert(Alert),
+    SaveSettings,
+    /// Save the edited theme under the name typed into the prompt
+    SaveTheme,
+    /// Replay the keys recorded in the given register
+    Play(char),
+    /// Copy the dates currently shown to the clipboard as a Markdown table
+    CopyTable,
+    /// Briefly draw the calendar with the weeks shifted down by the given
+    /// number of lines before drawing it normally
+    Glide(i16),
+}
+
+/// What a register named after `record-macro` or `play-macro` is used for
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum RegisterUse {
+    Record,
+    Play,
+}
+
+/// A jump that can be repeated with `repeat-jump` & `reverse-jump`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Jump {
+    /// To the next or previous run of days with the given phase
+    Phase(Phase),
+    /// To the first day of the month after or before the one starting on the
+    /// given date
+    Month(Date),
+    /// To January 1 of the year after or before the given one
+    Year(i32),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Popup {
+    Welcome,
+    Help,
+    Info,
+    Legend,
+    Options {
+        selected: usize,
+    },
+    Months {
+        year: i32,
+        selected: usize,
+    },
+    YearPrompt,
+    /// The find-month prompt, opened while the calendar was showing `year`
+    FindMonth {
+        year: i32,
+    },
+    /// The phase animation, currently showing `date`
+    Phases {
+        date: Date,
+    },
+    /// The theme editor, with the element & style field at the given indices
+    /// selected
+    ThemeEditor {
+        element: usize,
+        field: usize,
+    },
+    /// The prompt for the name to save the edited theme under, returning to
+    /// the theme editor afterwards
+    ThemeName {
+        element: usize,
+        field: usize,
+    },
+    /// Asking whether to quit
+    ConfirmQuit,
+    Error,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn state() -> State {
+        let highlighter = Highlighter {
+            settings: Settings::default(),
+            theme: Theme::default(),
+            offset: UtcOffset::UTC,
+            extra: (),
+        };
+        State::new(WeekWindow::new(date!(2024 - 03 - 10), highlighter))
+    }
+
+    #[test]
+    fn test_popups() {
+        let mut state = state();
+        assert_eq!(state.apply(Action::Help), Effect::Nothing);
+        assert_eq!(state.popup, Some(Popup::Help));
+        assert_eq!(state.handle_key(KeyCode::Char('q')), Effect::Nothing);
+        assert_eq!(state.popup, None);
+        assert!(!state.quitting, "dismissing a popup should not quit");
+        assert_eq!(state.handle_key(KeyCode::Char('q')), Effect::Nothing);
+        assert!(state.quitting, "q should quit");
+    }
+
+    #[test]
+    fn test_confirm_quit() {
+        let mut state = state();
+        state.confirm_quit = true;
+        state.handle_key(KeyCode::Char('q'));
+        assert_eq!(state.popup, Some(Popup::ConfirmQuit));
+        state.handle_key(KeyCode::Char('n'));
+        assert_eq!(state.popup, None);
+        assert!(!state.quitting, "declining should not quit");
+        state.handle_key(KeyCode::Esc);
+        state.handle_key(KeyCode::Char('y'));
+        assert!(state.quitting, "confirming should quit");
+    }
+
+    #[test]
+    fn test_options_popup() {
+        let mut state = state();
+        state.apply(Action::Options);
+        assert_eq!(state.handle_key(KeyCode::Char('j')), Effect::Nothing);
+        assert_eq!(state.popup, Some(Popup::Options { selected: 1 }));
+        assert_eq!(state.handle_key(KeyCode::Char('s')), Effect::SaveSettings);
+        assert_eq!(
+            state.handle_key(KeyCode::Char('x')),
+            Effect::Alert(Alert::InvalidKey)
+        );
+        assert_eq!(state.handle_key(KeyCode::Esc), Effect::Nothing);
+        assert_eq!(state.popup, None);
+    }
+
+    #[test]
+    fn test_jump_to_year() {
+        let mut state = state();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 12));
+        Calendar::<Highlighter>::new().render(buf.area, &mut buf, &mut state.weeks);
+        assert_eq!(state.apply(Action::JumpToYear), Effect::Nothing);
+        assert_eq!(state.popup, Some(Popup::YearPrompt));
+        assert_eq!(
+            state.handle_key(KeyCode::Enter),
+            Effect::Alert(Alert::FailedJump)
+        );
+        for c in "1968".chars() {
+            assert_eq!(state.handle_key(KeyCode::Char(c)), Effect::Nothing);
+        }
+        assert_eq!(
+            state.handle_key(KeyCode::Char('-')),
+            Effect::Alert(Alert::InvalidKey)
+        );
+        assert_eq!(state.handle_key(KeyCode::Backspace), Effect::Nothing);
+        assert_eq!(state.handle_key(KeyCode::Char('9')), Effect::Nothing);
+        assert_eq!(state.input, "1969");
+        assert_eq!(state.handle_key(KeyCode::Enter), Effect::Nothing);
+        assert_eq!(state.popup, None);
+        // 1969-01-01 was a Wednesday, in the week starting on Sunday the 29th
+        assert_eq!(state.weeks.focus_date(), date!(1968 - 12 - 29));
+    }
+
+    #[test]
+    fn test_choose_month() {
+        let mut state = state();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 12));
+        Calendar::<Highlighter>::new().render(buf.area, &mut buf, &mut state.weeks);
+        assert_eq!(state.apply(Action::ChooseMonth), Effect::Nothing);
+        assert_eq!(
+            state.popup,
+            Some(Popup::Months {
+                year: 2024,
+                selected: 2
+            })
+        );
+        assert_eq!(state.handle_key(KeyCode::Down), Effect::Nothing);
+        assert_eq!(
+            state.handle_key(KeyCode::Char('x')),
+            Effect::Alert(Alert::InvalidKey)
+        );
+        assert_eq!(state.handle_key(KeyCode::Enter), Effect::Nothing);
+        assert_eq!(state.popup, None);
+        // 2024-04-01 was a Monday
+        assert_eq!(state.weeks.focus_date(), date!(2024 - 03 - 31));
+        state.apply(Action::ChooseMonth);
+        assert_eq!(state.handle_key(KeyCode::Char('l')), Effect::Nothing);
+        assert_eq!(state.weeks.focus_date(), date!(2024 - 12 - 01));
+    }
+
+    #[test]
+    fn test_find_month() {
+        let mut state = state();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 12));
+        Calendar::<Highlighter>::new().render(buf.area, &mut buf, &mut state.weeks);
+        state.apply(Action::FindMonth);
+        assert_eq!(state.popup, Some(Popup::FindMonth { year: 2024 }));
+        for c in "smar".chars() {
+            state.handle_key(KeyCode::Char(c));
+        }
+        assert_eq!(
+            state.handle_key(KeyCode::Enter),
+            Effect::Alert(Alert::FailedJump)
+        );
+        state.input.clear();
+        for c in "mar 27".chars() {
+            assert_eq!(state.handle_key(KeyCode::Char(c)), Effect::Nothing);
+        }
+        assert_eq!(state.handle_key(KeyCode::Enter), Effect::Nothing);
+        assert_eq!(state.popup, None);
+        // 2027-03-01 was a Monday
+        assert_eq!(state.weeks.focus_date(), date!(2027 - 02 - 28));
+    }
+
+    #[test]
+    fn test_repeat_jump() {
+        let mut state = state();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 12));
+        Calendar::<Highlighter>::new().render(buf.area, &mut buf, &mut state.weeks);
+        assert_eq!(
+            state.apply(Action::RepeatJump),
+            Effect::Alert(Alert::FailedJump)
+        );
+        state.apply(Action::NextFullMoon);
+        let first = state.weeks.focus_date();
+        assert_eq!(state.apply(Action::RepeatJump), Effect::Nothing);
+        assert!(state.weeks.focus_date() > first);
+        assert_eq!(state.apply(Action::ReverseJump), Effect::Nothing);
+        assert_eq!(state.weeks.focus_date(), first);
+        state.apply(Action::ChooseMonth);
+        state.handle_key(KeyCode::Char('l'));
+        assert_eq!(state.apply(Action::RepeatJump), Effect::Nothing);
+        // 2025-01-01 was a Wednesday
+        assert_eq!(state.weeks.focus_date(), date!(2024 - 12 - 29));
+        assert_eq!(state.apply(Action::ReverseJump), Effect::Nothing);
+        assert_eq!(state.weeks.focus_date(), date!(2024 - 12 - 01));
+    }
+
+    #[test]
+    fn test_chords() {
+        let mut state = state();
+        state.keymap.extend(&[
+            "g m=choose-month".parse().unwrap(),
+            "g 0=today".parse().unwrap(),
+        ]);
+        assert_eq!(state.handle_key(KeyCode::Char('g')), Effect::Nothing);
+        assert_eq!(state.chord_prefix, Some(KeyCode::Char('g')));
+        assert_eq!(
+            state.handle_key(KeyCode::Char('x')),
+            Effect::Alert(Alert::InvalidKey)
+        );
+        assert_eq!(state.chord_prefix, None);
+        state.handle_key(KeyCode::Char('g'));
+        assert_eq!(state.handle_key(KeyCode::Esc), Effect::Nothing);
+        assert!(!state.quitting);
+        state.handle_key(KeyCode::Char('g'));
+        assert_eq!(state.handle_key(KeyCode::Char('m')), Effect::Nothing);
+        assert!(matches!(state.popup, Some(Popup::Months { .. })));
+    }
+
+    #[test]
+    fn test_record_macro() {
+        let mut state = state();
+        assert_eq!(state.handle_key(KeyCode::Char('@')), Effect::Nothing);
+        assert_eq!(
+            state.handle_key(KeyCode::Char('a')),
+            Effect::Alert(Alert::EmptyRegister)
+        );
+        for key in ['Q', 'a', 'f', 'y', '1', '9', '6', '9'] {
+            assert_eq!(state.handle_key(KeyCode::Char(key)), Effect::Nothing);
+        }
+        assert_eq!(state.handle_key(KeyCode::Enter), Effect::Nothing);
+        assert_eq!(state.handle_key(KeyCode::Char('Q')), Effect::Nothing);
+        assert_eq!(state.recording, None);
+        assert_eq!(
+            state.macros.get(&'a').map(Vec::as_slice),
+            Some(
+                &[
+                    KeyCode::Char('f'),
+                    KeyCode::Char('y'),
+                    KeyCode::Char('1'),
+                    KeyCode::Char('9'),
+                    KeyCode::Char('6'),
+                    KeyCode::Char('9'),
+                    KeyCode::Enter,
+                ][..]
+            )
+        );
+        assert_eq!(state.handle_key(KeyCode::Char('@')), Effect::Nothing);
+        assert_eq!(state.handle_key(KeyCode::Char('a')), Effect::Play('a'));
+        state.replaying = true;
+        assert_eq!(
+            state.handle_key(KeyCode::Char('@')),
+            Effect::Alert(Alert::FailedJump)
+        );
+    }
+
+    #[test]
+    fn test_advance_month() {
+        let mut state = state();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 12));
+        Calendar::<Highlighter>::new().render(buf.area, &mut buf, &mut state.weeks);
+        for focus in [
+            date!(2024 - 03 - 31),
+            date!(2024 - 04 - 28),
+            date!(2024 - 05 - 26),
+            date!(2024 - 06 - 30),
+        ] {
+            state.advance_month();
+            assert_eq!(state.weeks.focus_date(), focus);
+        }
+    }
+
+    #[test]
+    fn test_animate_phases() {
+        let mut state = state();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 12));
+        Calendar::<Highlighter>::new().render(buf.area, &mut buf, &mut state.weeks);
+        assert_eq!(state.apply(Action::AnimatePhases), Effect::Nothing);
+        assert_eq!(
+            state.popup,
+            Some(Popup::Phases {
+                date: date!(2024 - 03 - 01)
+            })
+        );
+        for _ in 0..40 {
+            state.step_animation(true);
+        }
+        assert_eq!(
+            state.popup,
+            Some(Popup::Phases {
+                date: date!(2024 - 03 - 31)
+            })
+        );
+        assert_eq!(
+            state.handle_key(KeyCode::Right),
+            Effect::Alert(Alert::FailedJump)
+        );
+        assert_eq!(state.handle_key(KeyCode::Left), Effect::Nothing);
+        assert_eq!(
+            state.popup,
+            Some(Popup::Phases {
+                date: date!(2024 - 03 - 30)
+            })
+        );
+        state.handle_key(KeyCode::Char('p'));
+        assert_eq!(state.popup, None);
+    }
+
+    #[test]
+    fn test_copy_table() {
+        let mut state = state();
+        assert_eq!(
+            state.apply(Action::CopyTable),
+            Effect::Alert(Alert::FailedJump)
+        );
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 6));
+        Calendar::<Highlighter>::new().render(buf.area, &mut buf, &mut state.weeks);
+        assert_eq!(
+            state.weeks.shown_dates(),
+            Some((date!(2024 - 03 - 10), date!(2024 - 03 - 23)))
+        );
+        assert_eq!(state.apply(Action::CopyTable), Effect::CopyTable);
+    }
+
+    #[test]
+    fn test_prepare() {
+        let mut terminal = Terminal::new(TestBackend::new(40, 7)).unwrap();
+        let mut weeks = state().weeks;
+        let mut highlighter = *weeks.styler();
+        highlighter.settings.dashboard = true;
+        weeks.set_styler(highlighter);
+        let app = App::new(&mut terminal, weeks).prepare().unwrap();
+        // The footer takes one line, leaving room for two weeks
+        assert_eq!(
+            app.state.weeks.shown_dates(),
+            Some((date!(2024 - 03 - 10), date!(2024 - 03 - 23)))
+        );
+        assert_eq!(
+            buffer_text(app.terminal.backend().buffer()).trim(),
+            "",
+            "nothing should be drawn"
+        );
+    }
+
+    #[test]
+    fn test_animation_frame_rate() {
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        let mut app = App::new(&mut terminal, state().weeks);
+        let start = Instant::now();
+        app.advance_animation(start);
+        app.state.apply(Action::AnimatePhases);
+        // Time out every 100ms, as when reading commands, for one second
+        for i in 1..=10 {
+            app.advance_animation(start + COMMAND_INTERVAL * i);
+        }
+        // One step at each of 300ms, 600ms, and 900ms
+        assert_eq!(
+            app.state.popup,
+            Some(Popup::Phases {
+                date: date!(2024 - 03 - 04)
+            })
+        );
+    }
+
+    #[test]
+    fn test_info_in_list_view() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let mut app = App::new(&mut terminal, state().weeks).view(View::List);
+        app.state.apply(Action::ScrollDown);
+        app.state.apply(Action::Info);
+        app.draw().unwrap();
+        let text = buffer_text(app.terminal.backend().buffer());
+        assert!(text.contains("Date:           2024-03-11 (Monday)"));
+    }
+
+    #[test]
+    fn test_jump_command_in_list_view() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let mut app = App::new(&mut terminal, state().weeks).view(View::List);
+        app.run_command(ControlCommand::Jump(date!(2025 - 06 - 11)))
+            .unwrap();
+        assert_eq!(app.state.list, Some(date!(2025 - 06 - 11)));
+    }
+
+    #[test]
+    fn test_set_today() {
+        let mut state = state();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 12));
+        Calendar::<Highlighter>::new().render(buf.area, &mut buf, &mut state.weeks);
+        state.apply(Action::PageDown);
+        let focus = state.weeks.focus_date();
+        state.set_today(date!(2024 - 03 - 10));
+        assert_eq!(state.weeks.focus_date(), focus);
+        state.set_today(date!(2024 - 03 - 11));
+        assert_eq!(state.weeks.today(), date!(2024 - 03 - 11));
+        assert_eq!(state.weeks.focus_date(), date!(2024 - 03 - 10));
     }
 
-    fn reset(&mut self) {
-        self.weeks.jump_to_today();
+    #[test]
+    fn test_list_view() {
+        let mut state = state();
+        let mut buf = Buffer::empty(Rect::new(0, 0, 40, 12));
+        Calendar::<Highlighter>::new().render(buf.area, &mut buf, &mut state.weeks);
+        assert_eq!(state.apply(Action::ListView), Effect::Nothing);
+        assert_eq!(state.list, Some(date!(2024 - 03 - 10)));
+        assert_eq!(state.apply(Action::ScrollDown), Effect::Nothing);
+        assert_eq!(state.list, Some(date!(2024 - 03 - 11)));
+        state.apply(Action::PageUp);
+        assert_eq!(state.list, Some(date!(2024 - 03 - 04)));
+        state.apply(Action::NextFullMoon);
+        let full = state.list.unwrap();
+        assert!(full > date!(2024 - 03 - 04));
+        assert_eq!(
+            state.weeks.styler().phoon().phase(full),
+            Phase::Full,
+            "the full moon itself should be selected"
+        );
+        state.apply(Action::Today);
+        assert_eq!(state.list, Some(date!(2024 - 03 - 10)));
+        state.apply(Action::ListView);
+        assert_eq!(state.list, None);
     }
 
-    fn quit(&mut self) {
-        self.quitting = true;
+    #[test]
+    fn test_no_marked_dates() {
+        let mut state = state();
+        assert_eq!(
+            state.apply(Action::NextDate),
+            Effect::Alert(Alert::FailedJump)
+        );
     }
 
-    fn beep(&mut self) -> io::Result<()> {
-        execute!(self.terminal.backend_mut(), Print("\x07"))
+    #[test]
+    fn test_scroll_glides() {
+        let mut state = state();
+        assert_eq!(state.apply(Action::ScrollDown), Effect::Glide(1));
+        assert_eq!(state.apply(Action::ScrollUp), Effect::Glide(-1));
+        assert_eq!(state.apply(Action::PageDown), Effect::Nothing);
     }
 }
@@ -0,0 +1,278 @@
+//! Low-precision positional astronomy for the Moon, good to within a few
+//! minutes for rise & set times
+use std::num::ParseFloatError;
+use std::str::FromStr;
+use thiserror::Error;
+use time::{Date, Duration, Time, UtcOffset};
+
+/// Julian date of the J2000.0 epoch
+const J2000: f64 = 2_451_545.0;
+
+const MINUTES_PER_DAY: u16 = 1440;
+
+/// Interval in minutes at which the Moon's altitude is sampled when searching
+/// for rise & set times
+const SAMPLE_STEP: u16 = 10;
+
+/// Mean obliquity of the ecliptic for the current epoch, in degrees
+const OBLIQUITY: f64 = 23.44;
+
+/// Equatorial radius of the Earth in kilometers
+const EARTH_RADIUS: f64 = 6378.14;
+
+/// A point on the Earth's surface, with coordinates in degrees.  North
+/// latitudes and east longitudes are positive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Location {
+    pub(crate) latitude: f64,
+    pub(crate) longitude: f64,
+}
+
+impl FromStr for Location {
+    type Err = LocationError;
+
+    /// Parse a location of the form `LAT,LON`
+    fn from_str(s: &str) -> Result<Location, LocationError> {
+        let (lat, lon) = s.split_once(',').ok_or(LocationError::Format)?;
+        let latitude = lat.trim().parse::<f64>()?;
+        let longitude = lon.trim().parse::<f64>()?;
+        if !(-90.0..=90.0).contains(&latitude) {
+            return Err(LocationError::Latitude);
+        }
+        if !(-180.0..=180.0).contains(&longitude) {
+            return Err(LocationError::Longitude);
+        }
+        Ok(Location {
+            latitude,
+            longitude,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub(crate) enum LocationError {
+    #[error("location must be of the form LAT,LON")]
+    Format,
+    #[error("invalid coordinate")]
+    Number(#[from] ParseFloatError),
+    #[error("latitude must be between -90 and 90")]
+    Latitude,
+    #[error("longitude must be between -180 and 180")]
+    Longitude,
+}
+
+/// The local times at which the Moon rises and sets on a given date.  Either
+/// may be `None`, as the Moon does not rise or set every calendar day.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct RiseSet {
+    pub(crate) rise: Option<Time>,
+    pub(crate) set: Option<Time>,
+}
+
+/// Compute the times of moonrise & moonset at `loc` on `date`, where `date`
+/// and the returned times are in the timezone given by `offset`
+pub(crate) fn moon_rise_set(date: Date, offset: UtcOffset, loc: Location) -> RiseSet {
+    let jd0 = julian_midnight(date, offset);
+    // Altitude of the Moon's center above the altitude at which its upper
+    // limb touches the horizon, in degrees
+    let altitude = |minute: u16| {
+        let jd = jd0 + f64::from(minute) / f64::from(MINUTES_PER_DAY);
+        let pos = MoonPosition::at(jd);
+        pos.altitude(jd, loc) - pos.horizon()
+    };
+    let mut rs = RiseSet {
+        rise: None,
+        set: None,
+    };
+    let mut prev_minute = 0;
+    let mut prev_below = altitude(0) < 0.0;
+    for minute in (SAMPLE_STEP..=MINUTES_PER_DAY).step_by(SAMPLE_STEP.into()) {
+        let below = altitude(minute) < 0.0;
+        if below != prev_below {
+            let crossing = bisect(&altitude, prev_minute, minute, prev_below);
+            if crossing < MINUTES_PER_DAY {
+                let when = Time::MIDNIGHT + Duration::minutes(crossing.into());
+                if prev_below {
+                    rs.rise.get_or_insert(when);
+                } else {
+                    rs.set.get_or_insert(when);
+                }
+            }
+        }
+        prev_minute = minute;
+        prev_below = below;
+    }
+    rs
+}
+
+/// Returns the Julian date (UT) of the start of `date` in the timezone given
+/// by `offset`
+pub(crate) fn julian_midnight(date: Date, offset: UtcOffset) -> f64 {
+    f64::from(date.to_julian_day()) - 0.5 - f64::from(offset.whole_seconds()) / 86400.0
+}
+
+/// Returns the distance in kilometers between the centers of the Earth and the
+/// Moon at Julian date `jd`
+pub(crate) fn moon_distance(jd: f64) -> f64 {
+    EARTH_RADIUS / MoonPosition::at(jd).parallax.to_radians().sin()
+}
+
+/// Returns the elongation of the Moon from the Sun (the difference between
+/// their ecliptic longitudes) at Julian date `jd`, in degrees in `[0, 360)`.
+/// This is 0 at new moon, 90 at first quarter, 180 at full moon, and 270 at
+/// last quarter.
+pub(crate) fn elongation(jd: f64) -> f64 {
+    (MoonPosition::ecliptic_longitude(jd) - sun_longitude(jd)).rem_euclid(360.0)
+}
+
+/// Returns the Sun's apparent ecliptic longitude at Julian date `jd`, in
+/// degrees, using the low-precision formula from the *Astronomical Almanac*
+fn sun_longitude(jd: f64) -> f64 {
+    let n = jd - J2000;
+    let mean_longitude = 0.985_647_4_f64.mul_add(n, 280.460);
+    let mean_anomaly = 0.985_600_3_f64.mul_add(n, 357.528).to_radians();
+    1.915_f64.mul_add(
+        mean_anomaly.sin(),
+        0.020_f64.mul_add((2.0 * mean_anomaly).sin(), mean_longitude),
+    )
+}
+
+// Given a function `f` that changes sign between `lo` and `hi`, return the
+// first minute after `lo` at which `f` is on the other side of zero
+fn bisect<F: Fn(u16) -> f64>(f: &F, mut lo: u16, mut hi: u16, lo_below: bool) -> u16 {
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if (f(mid) < 0.0) == lo_below {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    hi
+}
+
+/// Geocentric position of the Moon, computed with the low-precision formulae
+/// from the *Astronomical Almanac*
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct MoonPosition {
+    /// Right ascension, in degrees
+    right_ascension: f64,
+    /// Declination, in degrees
+    declination: f64,
+    /// Horizontal parallax, in degrees
+    parallax: f64,
+}
+
+impl MoonPosition {
+    /// Returns the Moon's geocentric ecliptic longitude at Julian date `jd`,
+    /// in degrees
+    fn ecliptic_longitude(jd: f64) -> f64 {
+        let t = (jd - J2000) / 36525.0;
+        481_267.881_f64.mul_add(t, 218.32) + sin_term(6.29, 135.0, 477_198.87, t)
+            - sin_term(1.27, 259.3, -413_335.36, t)
+            + sin_term(0.66, 235.7, 890_534.22, t)
+            + sin_term(0.21, 269.9, 954_397.74, t)
+            - sin_term(0.19, 357.5, 35999.05, t)
+            - sin_term(0.11, 186.5, 966_404.03, t)
+    }
+
+    fn at(jd: f64) -> MoonPosition {
+        let t = (jd - J2000) / 36525.0;
+        let longitude = MoonPosition::ecliptic_longitude(jd);
+        let latitude = sin_term(5.13, 93.3, 483_202.02, t) + sin_term(0.28, 228.2, 960_400.89, t)
+            - sin_term(0.28, 318.3, 6003.15, t)
+            - sin_term(0.17, 217.6, -407_332.21, t);
+        let parallax = 0.9508
+            + cos_term(0.0518, 135.0, 477_198.87, t)
+            + cos_term(0.0095, 259.3, -413_335.36, t)
+            + cos_term(0.0078, 235.7, 890_534.22, t)
+            + cos_term(0.0028, 269.9, 954_397.74, t);
+        let (sin_lon, cos_lon) = longitude.to_radians().sin_cos();
+        let (sin_lat, cos_lat) = latitude.to_radians().sin_cos();
+        let (sin_eps, cos_eps) = OBLIQUITY.to_radians().sin_cos();
+        let x = cos_lat * cos_lon;
+        let y = sin_eps.mul_add(-sin_lat, cos_eps * cos_lat * sin_lon);
+        let z = sin_eps.mul_add(cos_lat * sin_lon, cos_eps * sin_lat);
+        MoonPosition {
+            right_ascension: y.atan2(x).to_degrees(),
+            declination: z.asin().to_degrees(),
+            parallax,
+        }
+    }
+
+    /// Geocentric altitude above the horizon at `loc` at Julian date `jd`, in
+    /// degrees
+    fn altitude(&self, jd: f64, loc: Location) -> f64 {
+        let sidereal = 360.985_647_366_29_f64.mul_add(jd - J2000, 280.460_618_37) + loc.longitude;
+        let hour_angle = (sidereal - self.right_ascension).to_radians();
+        let (sin_phi, cos_phi) = loc.latitude.to_radians().sin_cos();
+        let (sin_dec, cos_dec) = self.declination.to_radians().sin_cos();
+        sin_phi
+            .mul_add(sin_dec, cos_phi * cos_dec * hour_angle.cos())
+            .asin()
+            .to_degrees()
+    }
+
+    /// Geocentric altitude at which the Moon's upper limb appears on the
+    /// horizon, accounting for parallax, refraction, and semidiameter
+    fn horizon(&self) -> f64 {
+        0.7275_f64.mul_add(self.parallax, -0.5667)
+    }
+}
+
+/// Evaluate `amplitude * sin(phase + rate * t)` with angles in degrees
+fn sin_term(amplitude: f64, phase: f64, rate: f64, t: f64) -> f64 {
+    amplitude * rate.mul_add(t, phase).to_radians().sin()
+}
+
+/// Evaluate `amplitude * cos(phase + rate * t)` with angles in degrees
+fn cos_term(amplitude: f64, phase: f64, rate: f64, t: f64) -> f64 {
+    amplitude * rate.mul_add(t, phase).to_radians().cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, offset, time};
+
+    #[test]
+    fn test_parse_location() {
+        let loc = "40.7, -74.0".parse::<Location>().unwrap();
+        assert_eq!(
+            loc,
+            Location {
+                latitude: 40.7,
+                longitude: -74.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_bad_location() {
+        assert_eq!("40.7".parse::<Location>(), Err(LocationError::Format));
+        assert_eq!("91,0".parse::<Location>(), Err(LocationError::Latitude));
+        assert_eq!("0,-181".parse::<Location>(), Err(LocationError::Longitude));
+        assert!(matches!(
+            "north,west".parse::<Location>(),
+            Err(LocationError::Number(_))
+        ));
+    }
+
+    fn within(actual: Option<Time>, expected: Time, minutes: i64) -> bool {
+        actual.is_some_and(|t| (t - expected).abs() <= Duration::minutes(minutes))
+    }
+
+    #[test]
+    fn test_full_moon_rise_set() {
+        // On the day of a full moon, the Moon rises around sunset and sets
+        // around sunrise.  In New York City on 2023-11-27, sunrise was at
+        // 06:58 EST and sunset was at 16:31.
+        let loc = Location {
+            latitude: 40.7128,
+            longitude: -74.0060,
+        };
+        let rs = moon_rise_set(date!(2023 - 11 - 27), offset!(-5), loc);
+        assert!(within(rs.rise, time!(16:31), 30), "rise = {:?}", rs.rise);
+        assert!(within(rs.set, time!(06:58), 30), "set = {:?}", rs.set);
+    }
+}
@@ -0,0 +1,50 @@
+//! Entry points into the calendar's internals for the benchmarks in
+//! `benches/`, compiled only with the `bench` feature.  This module is not
+//! part of the public API and may change at any time.
+use crate::calendar::{DateStyler, WeekWindow};
+use std::num::NonZeroUsize;
+use time::Date;
+
+/// The window of consecutive weeks through which the calendar pager scrolls
+#[derive(Clone, Debug)]
+pub struct WeekPager<S>(WeekWindow<S>);
+
+impl<S: DateStyler> WeekPager<S> {
+    /// Generate `week_qty` weeks around `date`, styling each date with
+    /// `styler`
+    pub fn around_date(date: Date, week_qty: NonZeroUsize, styler: S) -> Self {
+        let mut window = WeekWindow::new(date, styler);
+        window.resize(week_qty);
+        WeekPager(window)
+    }
+
+    /// Scroll forwards one page.  Returns `false` at the end of time.
+    pub fn page_forwards(&mut self) -> bool {
+        self.0.one_page_forwards().is_ok()
+    }
+
+    /// Scroll backwards one page.  Returns `false` at the start of time.
+    pub fn page_backwards(&mut self) -> bool {
+        self.0.one_page_backwards().is_ok()
+    }
+
+    /// Scroll forwards one week.  Returns `false` at the end of time.
+    pub fn week_forwards(&mut self) -> bool {
+        self.0.one_week_forwards().is_ok()
+    }
+
+    /// Scroll backwards one week.  Returns `false` at the start of time.
+    pub fn week_backwards(&mut self) -> bool {
+        self.0.one_week_backwards().is_ok()
+    }
+
+    /// Regenerate the window around `date`
+    pub fn jump_to(&mut self, date: Date) {
+        self.0.jump_to(date);
+    }
+
+    /// Returns the first and last dates in the window
+    pub fn shown_dates(&self) -> Option<(Date, Date)> {
+        self.0.shown_dates()
+    }
+}
@@ -1,11 +1,56 @@
+mod cache;
+mod month;
 mod util;
 mod weeks;
 mod widget;
-pub(crate) use self::weeks::WeekWindow;
-pub(crate) use self::widget::Calendar;
+pub use self::cache::CachedStyler;
+pub use self::month::{MonthCalendar, MonthState};
+pub(crate) use self::weeks::{TodayJump, WeekSplit, WeekStart, WeekWindow};
+pub(crate) use self::widget::{Calendar, CalendarStyles, MonthBorders, WeekSpacing, YearNumbering};
+use crate::moon::LunarPhase;
 use ratatui::style::Style;
 use time::Date;
 
-pub(crate) trait DateStyler {
+/// A source of styles for individual dates in the calendar
+pub trait DateStyler {
+    /// Returns the style with which to display `date`
     fn date_style(&self, date: Date) -> Style;
+
+    /// Returns the phase of the moon on `date` for the sparkline that can be
+    /// drawn under each week.  By default, no phase is known, and the
+    /// sparkline is left blank.
+    fn moon_phase(&self, _date: Date) -> Option<LunarPhase> {
+        None
+    }
+}
+
+/// The unit styler leaves all dates unstyled
+impl DateStyler for () {
+    fn date_style(&self, _date: Date) -> Style {
+        Style::new()
+    }
+}
+
+impl<S: DateStyler> DateStyler for Option<S> {
+    fn date_style(&self, date: Date) -> Style {
+        self.as_ref()
+            .map_or_else(Style::new, |styler| styler.date_style(date))
+    }
+
+    fn moon_phase(&self, date: Date) -> Option<LunarPhase> {
+        self.as_ref().and_then(|styler| styler.moon_phase(date))
+    }
+}
+
+/// A pair of stylers is applied by patching the style from the first with
+/// that from the second.  The phase of the moon is taken from the first that
+/// knows it.
+impl<A: DateStyler, B: DateStyler> DateStyler for (A, B) {
+    fn date_style(&self, date: Date) -> Style {
+        self.0.date_style(date).patch(self.1.date_style(date))
+    }
+
+    fn moon_phase(&self, date: Date) -> Option<LunarPhase> {
+        self.0.moon_phase(date).or_else(|| self.1.moon_phase(date))
+    }
 }
@@ -0,0 +1,162 @@
+use super::DateStyler;
+use crate::moon::LunarPhase;
+use ratatui::style::Style;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use time::Date;
+
+/// The number of dates whose styles a [`CachedStyler`] remembers by default,
+/// enough for a few screenfuls of weeks
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// A `DateStyler` that remembers the styles & moon phases returned by
+/// another `DateStyler` so that they are only computed once per date, for use
+/// with stylers that are expensive to query.  Once `capacity` dates have been
+/// cached, the least recently used date is forgotten to make room for each
+/// new one.
+///
+/// The wrapped styler is assumed to always give the same results for the same
+/// date; if that changes, call [`CachedStyler::clear()`].
+#[derive(Clone, Debug)]
+pub struct CachedStyler<S> {
+    styler: S,
+    capacity: NonZeroUsize,
+    cache: RefCell<Cache>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Cache {
+    entries: HashMap<Date, Entry>,
+    /// Incremented on every lookup so that entries can be ordered by when
+    /// they were last used
+    clock: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    style: Style,
+    phase: Option<LunarPhase>,
+    last_used: u64,
+}
+
+impl<S> CachedStyler<S> {
+    /// Wrap `styler` with a cache of the default size
+    pub fn new(styler: S) -> CachedStyler<S> {
+        let Some(capacity) = NonZeroUsize::new(DEFAULT_CAPACITY) else {
+            unreachable!("DEFAULT_CAPACITY should be nonzero");
+        };
+        CachedStyler::with_capacity(styler, capacity)
+    }
+
+    /// Wrap `styler` with a cache that holds up to `capacity` dates
+    pub fn with_capacity(styler: S, capacity: NonZeroUsize) -> CachedStyler<S> {
+        CachedStyler {
+            styler,
+            capacity,
+            cache: RefCell::new(Cache::default()),
+        }
+    }
+
+    /// Returns a reference to the wrapped styler
+    pub fn get_ref(&self) -> &S {
+        &self.styler
+    }
+
+    /// Returns the wrapped styler
+    pub fn into_inner(self) -> S {
+        self.styler
+    }
+
+    /// Forget all cached results
+    pub fn clear(&self) {
+        self.cache.borrow_mut().entries.clear();
+    }
+}
+
+impl<S: DateStyler> CachedStyler<S> {
+    fn lookup(&self, date: Date) -> Entry {
+        let mut cache = self.cache.borrow_mut();
+        cache.clock += 1;
+        let now = cache.clock;
+        if let Some(entry) = cache.entries.get_mut(&date) {
+            entry.last_used = now;
+            return *entry;
+        }
+        if cache.entries.len() >= self.capacity.get() {
+            let oldest = cache
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(&d, _)| d);
+            if let Some(d) = oldest {
+                cache.entries.remove(&d);
+            }
+        }
+        let entry = Entry {
+            style: self.styler.date_style(date),
+            phase: self.styler.moon_phase(date),
+            last_used: now,
+        };
+        cache.entries.insert(date, entry);
+        entry
+    }
+}
+
+impl<S: DateStyler> DateStyler for CachedStyler<S> {
+    fn date_style(&self, date: Date) -> Style {
+        self.lookup(date).style
+    }
+
+    fn moon_phase(&self, date: Date) -> Option<LunarPhase> {
+        self.lookup(date).phase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Stylize;
+    use std::cell::Cell;
+    use time::macros::date;
+
+    /// A styler that counts how many times it's been queried
+    #[derive(Debug, Default)]
+    struct Counter(Cell<usize>);
+
+    impl DateStyler for Counter {
+        fn date_style(&self, date: Date) -> Style {
+            self.0.set(self.0.get() + 1);
+            if date.day() == 13 {
+                Style::new().red()
+            } else {
+                Style::new()
+            }
+        }
+    }
+
+    #[test]
+    fn test_cached_styler() {
+        let styler = CachedStyler::with_capacity(Counter::default(), NonZeroUsize::new(2).unwrap());
+        let d1 = date!(2024 - 09 - 13);
+        let d2 = date!(2024 - 09 - 14);
+        let d3 = date!(2024 - 09 - 15);
+        assert_eq!(styler.date_style(d1), Style::new().red());
+        assert_eq!(styler.moon_phase(d1), None);
+        assert_eq!(styler.date_style(d1), Style::new().red());
+        assert_eq!(styler.get_ref().0.get(), 1);
+        assert_eq!(styler.date_style(d2), Style::new());
+        assert_eq!(styler.get_ref().0.get(), 2);
+        // d1 was used more recently than d2, so d2 is evicted
+        styler.date_style(d1);
+        styler.date_style(d3);
+        assert_eq!(styler.get_ref().0.get(), 3);
+        styler.date_style(d1);
+        assert_eq!(styler.get_ref().0.get(), 3);
+        styler.date_style(d2);
+        assert_eq!(styler.get_ref().0.get(), 4);
+        styler.clear();
+        styler.date_style(d1);
+        assert_eq!(styler.into_inner().0.get(), 5);
+    }
+}
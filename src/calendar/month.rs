@@ -0,0 +1,223 @@
+use super::util::WeekdayExt;
+use super::DateStyler;
+use ratatui::{buffer::Buffer, layout::Rect, style::Style, widgets::StatefulWidget};
+use std::marker::PhantomData;
+use time::{Date, Month, Weekday::Saturday};
+
+static HEADER: &str = "Su Mo Tu We Th Fr Sa";
+
+/// Width of the calendar in columns
+const WIDTH: u16 = 20;
+
+/// Number of lines taken up by the title and the header
+const HEADER_LINES: u16 = 2;
+
+/// Number of columns per day of week
+const DAY_WIDTH: u16 = 3;
+
+/// A compact calendar of a single month, in the style of cal(1), for use in
+/// places that don't need the full scrollable pager.  The month to show and
+/// the styler for its dates are held in a [`MonthState`].
+///
+/// The calendar takes up 20 columns and at most 8 lines; anything that
+/// doesn't fit in the area it's rendered in is cut off.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MonthCalendar<S> {
+    title: Style,
+    header: Style,
+    today: Style,
+    _data: PhantomData<S>,
+}
+
+impl<S> MonthCalendar<S> {
+    /// Create a calendar with all parts unstyled
+    pub fn new() -> MonthCalendar<S> {
+        MonthCalendar {
+            title: Style::new(),
+            header: Style::new(),
+            today: Style::new(),
+            _data: PhantomData,
+        }
+    }
+
+    /// Set the style for the month and year at the top
+    pub fn title_style(mut self, style: Style) -> MonthCalendar<S> {
+        self.title = style;
+        self
+    }
+
+    /// Set the style for the names of the days of the week
+    pub fn header_style(mut self, style: Style) -> MonthCalendar<S> {
+        self.header = style;
+        self
+    }
+
+    /// Set the style to patch over today's date
+    pub fn today_style(mut self, style: Style) -> MonthCalendar<S> {
+        self.today = style;
+        self
+    }
+}
+
+impl<S> Default for MonthCalendar<S> {
+    fn default() -> MonthCalendar<S> {
+        MonthCalendar::new()
+    }
+}
+
+impl<S: DateStyler> StatefulWidget for MonthCalendar<S> {
+    type State = MonthState<S>;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let mut put = |y: u16, x: u16, s: &str, style: Style| {
+            if y < area.height && x < area.width {
+                buf.set_stringn(
+                    area.x + x,
+                    area.y + y,
+                    s,
+                    usize::from(area.width - x),
+                    style,
+                );
+            }
+        };
+        let title = format!("{} {}", state.month, state.year);
+        let title_width = u16::try_from(title.len()).unwrap_or(u16::MAX);
+        put(0, WIDTH.saturating_sub(title_width) / 2, &title, self.title);
+        put(1, 0, HEADER, self.header);
+        // Months outside of `time`'s range are drawn with no dates
+        let Ok(mut date) = Date::from_calendar_date(state.year, state.month, 1) else {
+            return;
+        };
+        let mut y = HEADER_LINES;
+        loop {
+            let mut style = state.styler.date_style(date);
+            if state.today == Some(date) {
+                style = style.patch(self.today);
+            }
+            let weekday = date.weekday();
+            put(
+                y,
+                DAY_WIDTH * weekday.index0(),
+                &format!("{:>2}", date.day()),
+                style,
+            );
+            if weekday == Saturday {
+                y += 1;
+            }
+            match date.next_day() {
+                Some(d) if d.month() == state.month => date = d,
+                _ => break,
+            }
+        }
+    }
+}
+
+/// The month shown by a [`MonthCalendar`] and the styler for its dates
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MonthState<S> {
+    year: i32,
+    month: Month,
+    today: Option<Date>,
+    styler: S,
+}
+
+impl<S> MonthState<S> {
+    pub fn new(year: i32, month: Month, styler: S) -> MonthState<S> {
+        MonthState {
+            year,
+            month,
+            today: None,
+            styler,
+        }
+    }
+
+    /// Highlight `today` with the calendar's today style if it falls within
+    /// the month being shown
+    pub fn today(mut self, today: Date) -> MonthState<S> {
+        self.today = Some(today);
+        self
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> Month {
+        self.month
+    }
+
+    pub fn styler(&self) -> &S {
+        &self.styler
+    }
+
+    /// Switch to the following month
+    pub fn next_month(&mut self) {
+        if self.month == Month::December {
+            self.year = self.year.saturating_add(1);
+        }
+        self.month = self.month.next();
+    }
+
+    /// Switch to the preceding month
+    pub fn prev_month(&mut self) {
+        if self.month == Month::January {
+            self.year = self.year.saturating_sub(1);
+        }
+        self.month = self.month.previous();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dump::buffer_text;
+    use ratatui::style::Modifier;
+    use time::macros::date;
+
+    struct Weekends;
+
+    impl DateStyler for Weekends {
+        fn date_style(&self, date: Date) -> Style {
+            if date.weekday().index0() % 6 == 0 {
+                Style::new().add_modifier(Modifier::BOLD)
+            } else {
+                Style::new()
+            }
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 22, 9));
+        let mut state = MonthState::new(2024, Month::March, Weekends).today(date!(2024 - 03 - 14));
+        let cal = MonthCalendar::new().today_style(Style::new().add_modifier(Modifier::REVERSED));
+        cal.render(buf.area, &mut buf, &mut state);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "     March 2024\n",
+                "Su Mo Tu We Th Fr Sa\n",
+                "                1  2\n",
+                " 3  4  5  6  7  8  9\n",
+                "10 11 12 13 14 15 16\n",
+                "17 18 19 20 21 22 23\n",
+                "24 25 26 27 28 29 30\n",
+                "31\n",
+                "\n",
+            )
+        );
+        assert!(buf.get(18, 2).modifier.contains(Modifier::BOLD));
+        assert!(!buf.get(15, 2).modifier.contains(Modifier::BOLD));
+        assert!(buf.get(12, 4).modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn test_next_prev_month() {
+        let mut state = MonthState::new(2023, Month::December, ());
+        state.next_month();
+        assert_eq!((state.year(), state.month()), (2024, Month::January));
+        state.prev_month();
+        state.prev_month();
+        assert_eq!((state.year(), state.month()), (2023, Month::November));
+    }
+}
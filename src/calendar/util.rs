@@ -1,26 +1,40 @@
 use super::DateStyler;
-use ratatui::{style::Style, text::Span};
-use std::collections::VecDeque;
-use std::iter::successors;
+use crate::moon::LunarPhase;
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use std::iter::{successors, Chain};
 use std::num::NonZeroUsize;
+use std::slice;
 use time::{Date, Month, Weekday, Weekday::*};
 
 const DAYS_IN_WEEK: usize = 7;
 
 pub(super) trait WeekdayExt {
     fn index0(&self) -> u16;
+
+    /// Returns the zero-based column of the weekday in a week that starts on
+    /// `start`
+    fn column(&self, start: Weekday) -> u16;
 }
 
 impl WeekdayExt for Weekday {
     fn index0(&self) -> u16 {
         self.number_days_from_sunday().into()
     }
+
+    fn column(&self, start: Weekday) -> u16 {
+        (self.index0() + 7 - start.index0()) % 7
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub(super) struct StyledDate {
     pub(crate) date: Date,
     pub(crate) style: Style,
+    /// The phase of the moon, if the styler knows it
+    pub(crate) phase: Option<LunarPhase>,
 }
 
 impl StyledDate {
@@ -43,34 +57,82 @@ impl StyledDate {
         }
     }
 
-    pub(super) fn show(&self, is_today: bool) -> Span<'static> {
-        let s = if is_today {
-            format!("[{:2}]", self.day())
-        } else {
-            format!(" {:2} ", self.day())
-        };
-        Span::styled(s, self.style)
+    /// Returns the character written after the date with
+    /// `Calendar::markers()`: `*` for a full moon and `o` for a new moon
+    pub(super) fn marker(&self) -> Option<char> {
+        match self.phase? {
+            LunarPhase::Full => Some('*'),
+            LunarPhase::New => Some('o'),
+            _ => None,
+        }
+    }
+
+    /// Returns the label written in the right margin for a week containing
+    /// the date with `Calendar::phase_labels()`
+    pub(super) fn phase_label(&self) -> Option<&'static str> {
+        match self.phase? {
+            LunarPhase::Full => Some("Full Moon"),
+            LunarPhase::New => Some("New Moon"),
+            _ => None,
+        }
+    }
+
+    /// Returns the text for the date's cell, with `marker` (if any) in place
+    /// of the trailing space
+    pub(super) fn show(&self, marker: Option<char>) -> Line<'static> {
+        let marker = marker.unwrap_or(' ');
+        Span::styled(format!(" {:2}{marker}", self.day()), self.style).into()
+    }
+
+    /// Returns the text for the date's cell when it is today: the date,
+    /// styled with `today` patched over its own style, surrounded by brackets
+    /// styled with `marker` patched over that
+    pub(super) fn show_today(&self, today: Style, marker: Style) -> Line<'static> {
+        let style = self.style.patch(today);
+        let marker = style.patch(marker);
+        Line::from(vec![
+            Span::styled("[", marker),
+            Span::styled(format!("{:2}", self.day()), style),
+            Span::styled("]", marker),
+        ])
     }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-// Invariant: At least one element of the array is Some
-pub(super) struct Week([Option<StyledDate>; DAYS_IN_WEEK]);
+// Invariant: At least one element of `days` is Some
+pub(super) struct Week {
+    /// The weekday in the first column
+    start: Weekday,
+    days: [Option<StyledDate>; DAYS_IN_WEEK],
+}
 
 impl Week {
-    fn new(date: StyledDate) -> Self {
-        let mut this = Week([None; DAYS_IN_WEEK]);
+    fn new(date: StyledDate, start: Weekday) -> Self {
+        let mut this = Week {
+            start,
+            days: [None; DAYS_IN_WEEK],
+        };
         this.set(date);
         this
     }
 
     fn set(&mut self, date: StyledDate) {
-        let i = usize::from(date.date.weekday().index0());
+        let i = usize::from(date.date.weekday().column(self.start));
         assert!(
             i < DAYS_IN_WEEK,
-            "zero-based index of weekday should be less than number of days in week"
+            "zero-based column of weekday should be less than number of days in week"
         );
-        self.0[i] = Some(date);
+        self.days[i] = Some(date);
+    }
+
+    /// Returns the weekday in the first column
+    pub(super) fn start(&self) -> Weekday {
+        self.start
+    }
+
+    /// Returns the weekday in the last column
+    pub(super) fn end(&self) -> Weekday {
+        self.start.previous()
     }
 
     pub(super) fn enumerate(&self) -> EnumerateWeek<'_> {
@@ -78,15 +140,18 @@ impl Week {
     }
 
     pub(super) fn get(&self, wd: Weekday) -> Option<StyledDate> {
-        self.0.get(usize::from(wd.index0())).copied().flatten()
+        self.days
+            .get(usize::from(wd.column(self.start)))
+            .copied()
+            .flatten()
     }
 
     pub(super) fn has_month_start(&self) -> bool {
-        self.0.iter().flatten().any(|sd| sd.date.day() == 1)
+        self.days.iter().flatten().any(|sd| sd.date.day() == 1)
     }
 
     pub(super) fn first_ym(&self) -> (i32, Month) {
-        self.0
+        self.days
             .iter()
             .flatten()
             .map(|sd| (sd.year(), sd.month()))
@@ -95,11 +160,11 @@ impl Week {
     }
 
     pub(super) fn last_ym(&self) -> (i32, Month) {
-        self.0
+        self.days
             .iter()
             .flatten()
             .map(|sd| (sd.year(), sd.month()))
-            .last()
+            .next_back()
             .expect("Week should contain at least one Some")
     }
 }
@@ -114,7 +179,7 @@ impl<'a> EnumerateWeek<'a> {
     fn new(week: &'a Week) -> Self {
         EnumerateWeek {
             week,
-            next_weekday: Some(Sunday),
+            next_weekday: Some(week.start()),
         }
     }
 }
@@ -125,10 +190,7 @@ impl Iterator for EnumerateWeek<'_> {
     fn next(&mut self) -> Option<(Weekday, StyledDate)> {
         loop {
             let wd = self.next_weekday?;
-            self.next_weekday = match wd.next() {
-                Sunday => None,
-                wd2 => Some(wd2),
-            };
+            self.next_weekday = (wd != self.week.end()).then(|| wd.next());
             if let Some(date) = self.week.get(wd) {
                 return Some((wd, date));
             }
@@ -137,30 +199,65 @@ impl Iterator for EnumerateWeek<'_> {
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub(super) struct WeekFactory<S>(S);
+pub(super) struct WeekFactory<S> {
+    styler: S,
+    week_start: Weekday,
+}
 
 impl<S: DateStyler> WeekFactory<S> {
     pub(super) fn new(styler: S) -> Self {
-        WeekFactory(styler)
+        WeekFactory {
+            styler,
+            week_start: Sunday,
+        }
     }
 
-    pub(super) fn around_date(&self, date: Date, week_qty: NonZeroUsize) -> NonEmptyVecDeque<Week> {
+    pub(super) fn styler(&self) -> &S {
+        &self.styler
+    }
+
+    pub(super) fn set_styler(&mut self, styler: S) {
+        self.styler = styler;
+    }
+
+    pub(super) fn week_start(&self) -> Weekday {
+        self.week_start
+    }
+
+    pub(super) fn set_week_start(&mut self, week_start: Weekday) {
+        self.week_start = week_start;
+    }
+
+    // Recompute the styles & phases of the dates in `week` using the current
+    // styler
+    pub(super) fn restyle(&self, week: &mut Week) {
+        for sd in week.days.iter_mut().flatten() {
+            sd.style = self.styler.date_style(sd.date);
+            sd.phase = self.styler.moon_phase(sd.date);
+        }
+    }
+
+    /// Returns `week_qty` weeks, with the week containing `date` preceded by
+    /// `before` weeks (fewer if that would reach the start of time or leave no
+    /// room for the week itself)
+    pub(super) fn around_date(
+        &self,
+        date: Date,
+        week_qty: NonZeroUsize,
+        before: usize,
+    ) -> RingBuffer<Week> {
         let start_week = self.make(date);
-        let mut weeks = NonEmptyVecDeque::new(start_week, week_qty);
-        for w in self
-            .iter_weeks_before(start_week)
-            .take((week_qty.get() - 1) / 2)
-        {
-            weeks.push_front(w);
+        let mut weeks = RingBuffer::new(start_week, week_qty);
+        if let Some(before) = NonZeroUsize::new(before.min(week_qty.get() - 1)) {
+            weeks.prepend(self.weeks_before(start_week, before));
+        }
+        if let Some(after) = nonzero_sub(week_qty, weeks.len()) {
+            weeks.extend(self.weeks_after(start_week, after));
         }
-        weeks.extend(self.iter_weeks_after(start_week).take(weeks.unfilled()));
-        let unfilled = weeks.unfilled();
-        if unfilled > 0 {
+        if let Some(missing) = nonzero_sub(week_qty, weeks.len()) {
             // We are near the end of time, and so the "after" weeks were
             // short.  Fill towards the past.
-            for w in self.iter_weeks_before(*weeks.front()).take(unfilled) {
-                weeks.push_front(w);
-            }
+            weeks.prepend(self.weeks_before(*weeks.front(), missing));
         }
         weeks
     }
@@ -168,15 +265,16 @@ impl<S: DateStyler> WeekFactory<S> {
     fn style_date(&self, date: Date) -> StyledDate {
         StyledDate {
             date,
-            style: self.0.date_style(date),
+            style: self.styler.date_style(date),
+            phase: self.styler.moon_phase(date),
         }
     }
 
     // Returns the Week containing the given date, which can be at any day of
     // the week
     fn make(&self, date: Date) -> Week {
-        let i = usize::from(date.weekday().index0());
-        let mut week = Week::new(self.style_date(date));
+        let i = usize::from(date.weekday().column(self.week_start));
+        let mut week = Week::new(self.style_date(date), self.week_start);
         for d in iter_days_before(date).take(i) {
             week.set(self.style_date(d));
         }
@@ -187,13 +285,13 @@ impl<S: DateStyler> WeekFactory<S> {
     }
 
     pub(super) fn week_before(&self, week: &Week) -> Option<Week> {
-        week.get(Sunday)
+        week.get(week.start())
             .and_then(|sd| sd.date.previous_day())
             .map(|d| self.make(d))
     }
 
     pub(super) fn week_after(&self, week: &Week) -> Option<Week> {
-        week.get(Saturday)
+        week.get(week.end())
             .and_then(|sd| sd.date.next_day())
             .map(|d| self.make(d))
     }
@@ -206,132 +304,155 @@ impl<S: DateStyler> WeekFactory<S> {
         successors(Some(week), |w| self.week_after(w)).skip(1)
     }
 
-    // Returns `None` if there are no weeks before `week`.  If there are weeks
-    // before `week`, but not `qty` of them, only as many weeks as possible are
-    // returned.
-    pub(super) fn weeks_before(
-        &self,
-        week: Week,
-        qty: NonZeroUsize,
-    ) -> Option<NonEmptyVecDeque<Week>> {
-        let mut iter = self.iter_weeks_before(week);
-        let first_week = iter.next()?;
-        let mut weeks = NonEmptyVecDeque::new(first_week, qty);
-        for w in iter.take(qty.get() - 1) {
-            weeks.push_front(w);
-        }
-        Some(weeks)
+    /// Returns up to `qty` of the weeks immediately before `week`, in
+    /// chronological order.  Fewer weeks are returned if the start of time is
+    /// reached first.
+    pub(super) fn weeks_before(&self, week: Week, qty: NonZeroUsize) -> Vec<Week> {
+        let mut weeks = self
+            .iter_weeks_before(week)
+            .take(qty.get())
+            .collect::<Vec<_>>();
+        weeks.reverse();
+        weeks
     }
 
-    // Returns `None` if there are no weeks after `week`.  If there are weeks
-    // after `week`, but not `qty` of them, only as many weeks as possible are
-    // returned.
-    pub(super) fn weeks_after(
-        &self,
-        week: Week,
-        qty: NonZeroUsize,
-    ) -> Option<NonEmptyVecDeque<Week>> {
-        let mut iter = self.iter_weeks_after(week);
-        let first_week = iter.next()?;
-        let mut weeks = NonEmptyVecDeque::new(first_week, qty);
-        weeks.extend(iter.take(qty.get() - 1));
-        Some(weeks)
+    /// Returns up to `qty` of the weeks immediately after `week`, in
+    /// chronological order.  Fewer weeks are returned if the end of time is
+    /// reached first.
+    pub(super) fn weeks_after(&self, week: Week, qty: NonZeroUsize) -> Vec<Week> {
+        self.iter_weeks_after(week).take(qty.get()).collect()
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub(super) struct NonEmptyVecDeque<T>(VecDeque<T>);
+/// A nonempty ring buffer, used to hold the weeks in a `WeekWindow`.
+/// Scrolling by a week or a page overwrites the elements scrolled out of
+/// view with those scrolled into view in place, so the storage only changes
+/// size when the window itself does.
+#[derive(Clone, Debug)]
+pub(super) struct RingBuffer<T> {
+    items: Vec<T>,
+    /// Index into `items` of the first element
+    head: usize,
+}
 
-impl<T> NonEmptyVecDeque<T> {
+impl<T> RingBuffer<T> {
     pub(super) fn new(init: T, capacity: NonZeroUsize) -> Self {
-        let mut deque = VecDeque::with_capacity(capacity.get());
-        deque.push_front(init);
-        NonEmptyVecDeque(deque)
+        let mut items = Vec::with_capacity(capacity.get());
+        items.push(init);
+        RingBuffer { items, head: 0 }
     }
 
     pub(super) fn len(&self) -> NonZeroUsize {
-        let Some(len) = NonZeroUsize::new(self.0.len()) else {
-            unreachable!("Length of NonEmptyVecDeque cannot be zero");
+        let Some(len) = NonZeroUsize::new(self.items.len()) else {
+            unreachable!("Length of RingBuffer cannot be zero");
         };
         len
     }
 
-    fn unfilled(&self) -> usize {
-        self.0.capacity() - self.0.len()
+    /// Rearrange the elements so that the first one is at the start of
+    /// `items`, as is needed before changing the length
+    fn make_contiguous(&mut self) {
+        self.items.rotate_left(self.head);
+        self.head = 0;
     }
 
-    pub(super) fn push_front(&mut self, value: T) {
-        self.0.push_front(value);
+    pub(super) fn push_back(&mut self, value: T) {
+        self.make_contiguous();
+        self.items.push(value);
     }
 
+    /// Remove the last element, unless it's the only one
+    pub(super) fn pop_back(&mut self) -> Option<T> {
+        if self.items.len() > 1 {
+            self.make_contiguous();
+            self.items.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Insert the elements of `values` before the first element, in order
+    pub(super) fn prepend(&mut self, values: Vec<T>) {
+        self.make_contiguous();
+        self.items.splice(0..0, values);
+    }
+
+    /// Replace the last element with `value`, which becomes the first
     pub(super) fn rotate_push_front(&mut self, value: T) {
-        self.0.pop_back();
-        self.0.push_front(value);
+        self.head = self.head.checked_sub(1).unwrap_or(self.items.len() - 1);
+        self.items[self.head] = value;
     }
 
+    /// Replace the first element with `value`, which becomes the last
     pub(super) fn rotate_push_back(&mut self, value: T) {
-        self.0.pop_front();
-        self.0.push_back(value);
+        self.items[self.head] = value;
+        self.head = (self.head + 1) % self.items.len();
     }
 
-    pub(super) fn append(&mut self, other: &mut NonEmptyVecDeque<T>) {
-        self.0.append(&mut other.0);
+    pub(super) fn front(&self) -> &T {
+        &self.items[self.head]
     }
 
-    pub(super) fn prepend(&mut self, other: NonEmptyVecDeque<T>) {
-        for value in other.0 {
-            self.0.push_front(value);
-        }
+    pub(super) fn back(&self) -> &T {
+        let i = self.head.checked_sub(1).unwrap_or(self.items.len() - 1);
+        &self.items[i]
     }
 
-    pub(super) fn rotate_append(&mut self, other: &mut NonEmptyVecDeque<T>) {
-        self.0.drain(0..(other.len().get()));
-        self.0.append(&mut other.0);
+    pub(super) fn truncate(&mut self, len: NonZeroUsize) {
+        self.make_contiguous();
+        self.items.truncate(len.get());
     }
 
-    pub(super) fn front(&self) -> &T {
-        let Some(t) = self.0.front() else {
-            unreachable!("NonEmptyVecDeque should be nonempty");
-        };
-        t
+    pub(super) fn iter(&self) -> Chain<slice::Iter<'_, T>, slice::Iter<'_, T>> {
+        let (wrapped, first) = self.items.split_at(self.head);
+        first.iter().chain(wrapped)
     }
 
-    pub(super) fn back(&self) -> &T {
-        let Some(t) = self.0.back() else {
-            unreachable!("NonEmptyVecDeque should be nonempty");
-        };
-        t
+    pub(super) fn iter_mut(&mut self) -> Chain<slice::IterMut<'_, T>, slice::IterMut<'_, T>> {
+        let (wrapped, first) = self.items.split_at_mut(self.head);
+        first.iter_mut().chain(wrapped)
     }
+}
 
-    pub(super) fn truncate(&mut self, len: NonZeroUsize) {
-        self.0.truncate(len.get());
+impl<T: PartialEq> PartialEq for RingBuffer<T> {
+    fn eq(&self, other: &RingBuffer<T>) -> bool {
+        self.iter().eq(other.iter())
     }
 }
 
-impl<T> IntoIterator for NonEmptyVecDeque<T> {
-    type Item = T;
-    type IntoIter = std::collections::vec_deque::IntoIter<T>;
+impl<T: Eq> Eq for RingBuffer<T> {}
+
+impl<'a, T> IntoIterator for &'a RingBuffer<T> {
+    type Item = &'a T;
+    type IntoIter = Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        self.iter()
     }
 }
 
-impl<'a, T> IntoIterator for &'a NonEmptyVecDeque<T> {
-    type Item = &'a T;
-    type IntoIter = std::collections::vec_deque::Iter<'a, T>;
+impl<'a, T> IntoIterator for &'a mut RingBuffer<T> {
+    type Item = &'a mut T;
+    type IntoIter = Chain<slice::IterMut<'a, T>, slice::IterMut<'a, T>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.iter()
+        self.iter_mut()
     }
 }
 
-impl<T> Extend<T> for NonEmptyVecDeque<T> {
+impl<T> Extend<T> for RingBuffer<T> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
-        self.0.extend(iter);
+        self.make_contiguous();
+        self.items.extend(iter);
     }
 }
 
+/// Returns `lhs - rhs` if it is positive.  `rhs` must not be greater than
+/// `lhs`.
+pub(super) fn nonzero_sub(lhs: NonZeroUsize, rhs: NonZeroUsize) -> Option<NonZeroUsize> {
+    NonZeroUsize::new(lhs.get() - rhs.get())
+}
+
 fn iter_days_after(date: Date) -> impl Iterator<Item = Date> {
     successors(Some(date), |&d| d.next_day()).skip(1)
 }
@@ -343,6 +464,7 @@ fn iter_days_before(date: Date) -> impl Iterator<Item = Date> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ratatui::style::Stylize;
     use time::macros::date;
 
     struct NullStyler;
@@ -397,4 +519,72 @@ mod tests {
         assert_eq!(iter.next(), Some((Saturday, date!(2023 - 11 - 18))));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn test_make_saturday_start() {
+        let mut factory = WeekFactory::new(NullStyler);
+        factory.set_week_start(Saturday);
+        let week = factory.make(date!(2023 - 11 - 16));
+        let mut iter = week.enumerate().map(|(wd, sd)| (wd, sd.date));
+        assert_eq!(iter.next(), Some((Saturday, date!(2023 - 11 - 11))));
+        assert_eq!(iter.next(), Some((Sunday, date!(2023 - 11 - 12))));
+        assert_eq!(iter.next(), Some((Monday, date!(2023 - 11 - 13))));
+        assert_eq!(iter.next(), Some((Tuesday, date!(2023 - 11 - 14))));
+        assert_eq!(iter.next(), Some((Wednesday, date!(2023 - 11 - 15))));
+        assert_eq!(iter.next(), Some((Thursday, date!(2023 - 11 - 16))));
+        assert_eq!(iter.next(), Some((Friday, date!(2023 - 11 - 17))));
+        assert_eq!(iter.next(), None);
+        let after = factory.week_after(&week).unwrap();
+        assert_eq!(
+            after.get(Saturday).map(|sd| sd.date),
+            Some(date!(2023 - 11 - 18))
+        );
+    }
+
+    #[test]
+    fn test_show_today() {
+        let sd = StyledDate {
+            date: date!(2023 - 11 - 5),
+            style: Style::new().light_yellow(),
+            phase: None,
+        };
+        let line = sd.show_today(Style::new().on_blue(), Style::new().bold());
+        assert_eq!(line.to_string(), "[ 5]");
+        assert_eq!(
+            line.spans[0].style,
+            Style::new().light_yellow().on_blue().bold()
+        );
+        assert_eq!(line.spans[1].style, Style::new().light_yellow().on_blue());
+        assert_eq!(sd.show(None).to_string(), "  5 ");
+        assert_eq!(sd.marker(), None);
+        let full = StyledDate {
+            phase: Some(LunarPhase::Full),
+            ..sd
+        };
+        assert_eq!(full.show(full.marker()).to_string(), "  5*");
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let contents = |ring: &RingBuffer<i32>| ring.iter().copied().collect::<Vec<_>>();
+        let mut ring = RingBuffer::new(3, NonZeroUsize::new(4).unwrap());
+        ring.prepend(vec![1, 2]);
+        ring.extend([4]);
+        assert_eq!(contents(&ring), [1, 2, 3, 4]);
+        ring.rotate_push_back(5);
+        ring.rotate_push_back(6);
+        assert_eq!(contents(&ring), [3, 4, 5, 6]);
+        assert_eq!((*ring.front(), *ring.back()), (3, 6));
+        ring.rotate_push_front(2);
+        assert_eq!(contents(&ring), [2, 3, 4, 5]);
+        assert_eq!((*ring.front(), *ring.back()), (2, 5));
+        ring.push_back(6);
+        assert_eq!(contents(&ring), [2, 3, 4, 5, 6]);
+        ring.rotate_push_back(7);
+        ring.truncate(NonZeroUsize::new(2).unwrap());
+        assert_eq!(contents(&ring), [3, 4]);
+        assert_eq!(ring.pop_back(), Some(4));
+        assert_eq!(ring.pop_back(), None);
+        assert_eq!(ring.len().get(), 1);
+    }
 }
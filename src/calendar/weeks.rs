@@ -1,15 +1,23 @@
 use super::util::*;
 use super::DateStyler;
 use std::cmp::Ordering;
+use std::iter::successors;
 use std::num::NonZeroUsize;
+use std::str::FromStr;
 use thiserror::Error;
-use time::Date;
+use time::{Date, Duration, Weekday};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub(crate) struct WeekWindow<S> {
     pub(super) today: Date,
+    /// Dates to highlight, in the order in which they are cycled through
+    pub(super) marked: Vec<Date>,
+    /// Index into `marked` of the date last jumped to
+    marked_index: usize,
     start_date: Date,
-    weeks: Option<NonEmptyVecDeque<Week>>,
+    split: WeekSplit,
+    today_jump: TodayJump,
+    weeks: Option<RingBuffer<Week>>,
     week_factory: WeekFactory<S>,
 }
 
@@ -18,48 +26,239 @@ impl<S: DateStyler> WeekWindow<S> {
         let week_factory = WeekFactory::new(date_styler);
         WeekWindow {
             today,
+            marked: Vec::new(),
+            marked_index: 0,
             start_date: today,
+            split: WeekSplit::default(),
+            today_jump: TodayJump::default(),
             week_factory,
             weeks: None,
         }
     }
 
+    /// Highlight the given dates and start the calendar at the first one
+    pub(crate) fn marked(mut self, dates: Vec<Date>) -> Self {
+        if let Some(&date) = dates.first() {
+            self.start_date = date;
+        }
+        self.marked = dates;
+        self.marked_index = 0;
+        self
+    }
+
+    /// Set which of the marked dates was last jumped to
+    pub(crate) fn marked_index(mut self, index: usize) -> Self {
+        if index < self.marked.len() {
+            self.marked_index = index;
+        }
+        self
+    }
+
+    /// Position the window around the given date
     pub(crate) fn start_date(mut self, date: Date) -> Self {
         self.start_date = date;
         self
     }
 
-    pub(super) fn ensure_weeks(&mut self, week_qty: NonZeroUsize) -> &NonEmptyVecDeque<Week> {
+    /// Set how many weeks to show before & after the start date and the
+    /// dates jumped to
+    pub(crate) fn split(mut self, split: WeekSplit) -> Self {
+        self.split = split;
+        self
+    }
+
+    /// Set how `jump_to_today()` positions today
+    pub(crate) fn today_jump(mut self, today_jump: TodayJump) -> Self {
+        self.today_jump = today_jump;
+        self
+    }
+
+    /// Set the weekday in the first column of each week
+    pub(crate) fn week_start(mut self, week_start: Weekday) -> Self {
+        self.week_factory.set_week_start(week_start);
+        self
+    }
+
+    pub(crate) fn marked_dates(&self) -> &[Date] {
+        &self.marked
+    }
+
+    pub(crate) fn current_marked_index(&self) -> usize {
+        self.marked_index
+    }
+
+    /// Returns a date such that a window of the same height positioned around
+    /// it with `start_date()` shows the same weeks as this one
+    pub(crate) fn focus_date(&self) -> Date {
+        let Some(weeks) = self.weeks.as_ref() else {
+            return self.start_date;
+        };
+        let week = weeks
+            .into_iter()
+            .nth(self.split.weeks_before(weeks.len()))
+            .unwrap_or_else(|| weeks.front());
+        week.enumerate()
+            .next()
+            .map_or(self.start_date, |(_, sd)| sd.date)
+    }
+
+    pub(crate) fn today(&self) -> Date {
+        self.today
+    }
+
+    /// Returns the first and last dates of the weeks shown when the calendar
+    /// was last drawn, or `None` if it hasn't been drawn yet
+    pub(crate) fn shown_dates(&self) -> Option<(Date, Date)> {
+        let weeks = self.weeks.as_ref()?;
+        let (_, first) = weeks.front().enumerate().next()?;
+        let (_, last) = weeks.back().enumerate().last()?;
+        Some((first.date, last.date))
+    }
+
+    /// Change the date treated as today, e.g., after midnight passes
+    pub(crate) fn set_today(&mut self, today: Date) {
+        self.today = today;
+    }
+
+    pub(crate) fn styler(&self) -> &S {
+        self.week_factory.styler()
+    }
+
+    /// Change the weekday in the first column of each week, rebuilding the
+    /// weeks in the window around the same date
+    pub(crate) fn set_week_start(&mut self, week_start: Weekday) {
+        if week_start != self.week_factory.week_start() {
+            // Aim for the middle of the focused week so that the new week
+            // around it overlaps the old one as much as possible
+            self.start_date = self.focus_date().saturating_add(Duration::days(3));
+            self.week_factory.set_week_start(week_start);
+            self.weeks = None;
+        }
+    }
+
+    /// Replace the date styler and restyle the weeks currently in the window
+    pub(crate) fn set_styler(&mut self, styler: S) {
+        self.week_factory.set_styler(styler);
+        if let Some(weeks) = self.weeks.as_mut() {
+            for week in weeks {
+                self.week_factory.restyle(week);
+            }
+        }
+    }
+
+    pub(super) fn ensure_weeks(&mut self, week_qty: NonZeroUsize) -> &RingBuffer<Week> {
         if let Some(weeks) = self.weeks.as_mut() {
             match weeks.len().cmp(&week_qty) {
                 Ordering::Less => {
-                    if let Some(mut extension) = nonzero_sub(week_qty, weeks.len())
-                        .and_then(|missing| self.week_factory.weeks_after(*weeks.back(), missing))
-                    {
-                        weeks.append(&mut extension);
+                    if let Some(missing) = nonzero_sub(week_qty, weeks.len()) {
+                        weeks.extend(self.week_factory.weeks_after(*weeks.back(), missing));
                     }
                     if let Some(missing) = nonzero_sub(week_qty, weeks.len()) {
                         // The terminal was heightened while at the end of
                         // time, so "scroll" the calendar down by prepending
                         // weeks from before the window.
-                        if let Some(prextension) =
-                            self.week_factory.weeks_before(*weeks.front(), missing)
-                        {
-                            weeks.prepend(prextension);
-                        }
+                        weeks.prepend(self.week_factory.weeks_before(*weeks.front(), missing));
                     }
                 }
                 Ordering::Greater => weeks.truncate(week_qty),
                 Ordering::Equal => (),
             }
         }
-        self.weeks
-            .get_or_insert_with(|| self.week_factory.around_date(self.start_date, week_qty))
+        self.weeks.get_or_insert_with(|| {
+            let before = self.split.weeks_before(week_qty);
+            self.week_factory
+                .around_date(self.start_date, week_qty, before)
+        })
+    }
+
+    /// Make the window hold `week_qty` weeks, generating them if it hasn't
+    /// been drawn yet
+    #[cfg(feature = "bench")]
+    pub(crate) fn resize(&mut self, week_qty: NonZeroUsize) {
+        self.ensure_weeks(week_qty);
+    }
+
+    /// Like `ensure_weeks()`, but then keep adding weeks to the bottom of the
+    /// window for as long as `fits` returns true for the resulting weeks, for
+    /// layouts in which weeks take up varying numbers of lines
+    pub(super) fn fill_weeks<F>(&mut self, min_qty: NonZeroUsize, fits: F) -> &RingBuffer<Week>
+    where
+        F: Fn(&RingBuffer<Week>) -> bool,
+    {
+        self.ensure_weeks(min_qty);
+        if let Some(weeks) = self.weeks.as_mut() {
+            while let Some(week) = self.week_factory.week_after(weeks.back()) {
+                weeks.push_back(week);
+                if !fits(weeks) {
+                    weeks.pop_back();
+                    break;
+                }
+            }
+        }
+        let qty = self.weeks.as_ref().map_or(min_qty, RingBuffer::len);
+        self.ensure_weeks(qty)
     }
 
     pub(crate) fn jump_to_today(&mut self) {
+        let Some(weeks) = self.weeks.as_mut() else {
+            return;
+        };
+        let week_qty = weeks.len();
+        let before = match self.today_jump {
+            TodayJump::Center => self.split.weeks_before(week_qty),
+            TodayJump::Top => 0,
+            TodayJump::Minimal => {
+                let first = weeks.front().enumerate().next().map(|(_, sd)| sd.date);
+                let last = weeks.back().enumerate().last().map(|(_, sd)| sd.date);
+                if first.is_some_and(|d| self.today < d) {
+                    0
+                } else if last.is_some_and(|d| d < self.today) {
+                    week_qty.get() - 1
+                } else {
+                    return;
+                }
+            }
+        };
+        *weeks = self.week_factory.around_date(self.today, week_qty, before);
+    }
+
+    /// Jump to the marked date after (or, if `forwards` is false, before) the
+    /// one last jumped to, wrapping around at the ends.  Returns `false` if
+    /// there are no marked dates.
+    pub(crate) fn cycle_marked(&mut self, forwards: bool) -> bool {
+        let qty = self.marked.len();
+        if qty == 0 {
+            return false;
+        }
+        self.marked_index = if forwards {
+            (self.marked_index + 1) % qty
+        } else {
+            (self.marked_index + qty - 1) % qty
+        };
+        self.jump_to(self.marked[self.marked_index]);
+        true
+    }
+
+    /// Position the window around the marked date last jumped to (or today,
+    /// if there are no marked dates) in the same way as the start date,
+    /// regardless of the `TodayJump` setting
+    pub(crate) fn recenter(&mut self) {
+        let date = self
+            .marked
+            .get(self.marked_index)
+            .copied()
+            .unwrap_or(self.today);
+        self.jump_to(date);
+    }
+
+    /// Position the window around `date` in the same way as the start date.
+    /// If the window hasn't been drawn yet, `date` becomes the start date.
+    pub(crate) fn jump_to(&mut self, date: Date) {
         if let Some(weeks) = self.weeks.as_mut() {
-            *weeks = self.week_factory.around_date(self.today, weeks.len());
+            let before = self.split.weeks_before(weeks.len());
+            *weeks = self.week_factory.around_date(date, weeks.len(), before);
+        } else {
+            self.start_date = date;
         }
     }
 
@@ -91,45 +290,420 @@ impl<S: DateStyler> WeekWindow<S> {
         let Some(weeks) = self.weeks.as_mut() else {
             return Ok(());
         };
-        let week_qty = weeks.len();
-        if let Some(mut page) = self.week_factory.weeks_after(*weeks.back(), week_qty) {
-            if page.len() == week_qty {
-                *weeks = page;
-            } else {
-                assert!(
-                    page.len() < week_qty,
-                    "week_after() should not return more than week_qty items"
-                );
-                weeks.rotate_append(&mut page);
-            }
-            Ok(())
-        } else {
-            Err(OutOfTimeError)
+        let page = self.week_factory.weeks_after(*weeks.back(), weeks.len());
+        if page.is_empty() {
+            return Err(OutOfTimeError);
+        }
+        for w in page {
+            weeks.rotate_push_back(w);
         }
+        Ok(())
     }
 
     pub(crate) fn one_page_backwards(&mut self) -> Result<(), OutOfTimeError> {
         let Some(weeks) = self.weeks.as_mut() else {
             return Ok(());
         };
-        let week_qty = weeks.len();
-        if let Some(mut page) = self.week_factory.weeks_before(*weeks.front(), week_qty) {
-            if let Some(len) = nonzero_sub(week_qty, page.len()) {
-                weeks.truncate(len);
-                page.append(weeks);
+        let page = self.week_factory.weeks_before(*weeks.front(), weeks.len());
+        if page.is_empty() {
+            return Err(OutOfTimeError);
+        }
+        for w in page.into_iter().rev() {
+            weeks.rotate_push_front(w);
+        }
+        Ok(())
+    }
+}
+
+/// How to divide the rows of the calendar between the weeks before & after
+/// the week containing a given date.  If only one side is given, it gets that
+/// many weeks (if they fit) and the other side gets the rest; if both are
+/// given, the weeks are divided between the sides in proportion to them; if
+/// neither is given, the date is centered.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct WeekSplit {
+    pub(crate) before: Option<usize>,
+    pub(crate) after: Option<usize>,
+}
+
+impl WeekSplit {
+    /// Returns the number of weeks to show before the week containing the
+    /// date when showing `week_qty` weeks in total
+    pub(crate) fn weeks_before(&self, week_qty: NonZeroUsize) -> usize {
+        let others = week_qty.get() - 1;
+        match (self.before, self.after) {
+            (Some(before), None) => before.min(others),
+            (None, Some(after)) => others - after.min(others),
+            (Some(before), Some(after)) => {
+                // Clamp first so that the arithmetic can't overflow
+                let (before, after) = (before.min(others), after.min(others));
+                match before + after {
+                    0 => others / 2,
+                    // Divide proportionally, rounding to nearest
+                    total => (others * before * 2 + total) / (total * 2),
+                }
             }
-            *weeks = page;
-            Ok(())
-        } else {
-            Err(OutOfTimeError)
+            (None, None) => others / 2,
+        }
+    }
+}
+
+/// Where `WeekWindow::jump_to_today()` positions today
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) enum TodayJump {
+    /// Position today as for the starting date, which is centered unless
+    /// specified otherwise by the `WeekSplit`
+    #[default]
+    Center,
+    /// Put today in the top row
+    Top,
+    /// Scroll only as far as needed to bring today into view
+    Minimal,
+}
+
+impl FromStr for TodayJump {
+    type Err = ParseTodayJumpError;
+
+    fn from_str(s: &str) -> Result<TodayJump, ParseTodayJumpError> {
+        match s.to_ascii_lowercase().as_str() {
+            "center" | "centre" => Ok(TodayJump::Center),
+            "top" => Ok(TodayJump::Top),
+            "minimal" => Ok(TodayJump::Minimal),
+            _ => Err(ParseTodayJumpError),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("today jump must be \"center\", \"top\", or \"minimal\"")]
+pub(crate) struct ParseTodayJumpError;
+
+/// The weekday in the first column of the calendar
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct WeekStart(pub(crate) Weekday);
+
+impl WeekStart {
+    /// The weekday's name in lowercase, as written to the configuration file
+    pub(crate) fn name(self) -> &'static str {
+        match self.0 {
+            Weekday::Monday => "monday",
+            Weekday::Tuesday => "tuesday",
+            Weekday::Wednesday => "wednesday",
+            Weekday::Thursday => "thursday",
+            Weekday::Friday => "friday",
+            Weekday::Saturday => "saturday",
+            Weekday::Sunday => "sunday",
         }
     }
 }
 
+impl Default for WeekStart {
+    fn default() -> WeekStart {
+        WeekStart(Weekday::Sunday)
+    }
+}
+
+impl FromStr for WeekStart {
+    type Err = ParseWeekStartError;
+
+    /// Parses a weekday's full name or its two- or three-letter abbreviation,
+    /// ignoring case
+    fn from_str(s: &str) -> Result<WeekStart, ParseWeekStartError> {
+        let s = s.to_ascii_lowercase();
+        successors(Some(Weekday::Sunday), |wd| Some(wd.next()))
+            .take(7)
+            .find(|wd| {
+                let name = wd.to_string().to_ascii_lowercase();
+                s == name || (matches!(s.len(), 2 | 3) && name.starts_with(&s))
+            })
+            .map(WeekStart)
+            .ok_or(ParseWeekStartError)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("week start must be the name of a day of the week")]
+pub(crate) struct ParseWeekStartError;
+
 #[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
 #[error("reached the end of time")]
 pub(crate) struct OutOfTimeError;
 
-fn nonzero_sub(lhs: NonZeroUsize, rhs: NonZeroUsize) -> Option<NonZeroUsize> {
-    NonZeroUsize::new(lhs.get() - rhs.get())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Style;
+    use time::macros::date;
+
+    pub(super) struct NullStyler;
+
+    impl DateStyler for NullStyler {
+        fn date_style(&self, _date: Date) -> Style {
+            Style::new()
+        }
+    }
+
+    fn shows(window: &mut WeekWindow<NullStyler>, date: Date) -> bool {
+        let weeks = window.ensure_weeks(NonZeroUsize::new(5).unwrap());
+        weeks
+            .into_iter()
+            .any(|w| w.enumerate().any(|(_, sd)| sd.date == date))
+    }
+
+    #[test]
+    fn test_week_split() {
+        let qty = NonZeroUsize::new(11).unwrap();
+        let split = |before, after| WeekSplit { before, after }.weeks_before(qty);
+        assert_eq!(split(None, None), 5);
+        assert_eq!(split(Some(2), None), 2);
+        assert_eq!(split(Some(20), None), 10);
+        assert_eq!(split(None, Some(1)), 9);
+        assert_eq!(split(None, Some(20)), 0);
+        assert_eq!(split(Some(1), Some(3)), 3);
+        assert_eq!(split(Some(0), Some(0)), 5);
+        assert_eq!(
+            split(Some(1), None),
+            WeekSplit::default().weeks_before(NonZeroUsize::new(3).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_jump_to_today_minimal() {
+        let today = date!(2024 - 06 - 12);
+        let mut window = WeekWindow::new(today, NullStyler).today_jump(TodayJump::Minimal);
+        assert!(shows(&mut window, today));
+        // Scroll so that today is just off the bottom
+        for _ in 0..3 {
+            window.one_week_backwards().unwrap();
+        }
+        assert!(!shows(&mut window, today));
+        window.jump_to_today();
+        let weeks = window.ensure_weeks(NonZeroUsize::new(5).unwrap());
+        assert!(weeks.back().enumerate().any(|(_, sd)| sd.date == today));
+        // Already visible, so nothing changes
+        window.one_week_forwards().unwrap();
+        let before = window.focus_date();
+        window.jump_to_today();
+        assert_eq!(window.focus_date(), before);
+    }
+
+    #[test]
+    fn test_cycle_marked() {
+        let mut window = WeekWindow::new(date!(2024 - 01 - 15), NullStyler).marked(vec![
+            date!(2024 - 03 - 25),
+            date!(2024 - 06 - 22),
+            date!(2024 - 09 - 18),
+        ]);
+        assert!(shows(&mut window, date!(2024 - 03 - 25)));
+        assert!(window.cycle_marked(true));
+        assert!(shows(&mut window, date!(2024 - 06 - 22)));
+        assert!(window.cycle_marked(false));
+        assert!(window.cycle_marked(false));
+        assert!(shows(&mut window, date!(2024 - 09 - 18)));
+        assert!(!WeekWindow::new(date!(2024 - 01 - 15), NullStyler).cycle_marked(true));
+    }
+
+    #[test]
+    fn test_recenter() {
+        let today = date!(2024 - 06 - 12);
+        let mut window = WeekWindow::new(today, NullStyler).today_jump(TodayJump::Top);
+        assert!(shows(&mut window, today));
+        let start = window.focus_date();
+        for _ in 0..10 {
+            window.one_week_forwards().unwrap();
+        }
+        window.recenter();
+        assert_eq!(window.focus_date(), start);
+
+        let mut window = WeekWindow::new(today, NullStyler)
+            .marked(vec![date!(2024 - 03 - 25), date!(2024 - 09 - 18)])
+            .marked_index(1)
+            .start_date(date!(2024 - 09 - 18));
+        assert!(shows(&mut window, date!(2024 - 09 - 18)));
+        let start = window.focus_date();
+        window.one_page_backwards().unwrap();
+        window.recenter();
+        assert_eq!(window.focus_date(), start);
+        assert_eq!(window.current_marked_index(), 1);
+    }
+
+    #[test]
+    fn test_parse_week_start() {
+        for (s, wd) in [
+            ("sunday", Weekday::Sunday),
+            ("Mon", Weekday::Monday),
+            ("TU", Weekday::Tuesday),
+            ("sat", Weekday::Saturday),
+        ] {
+            assert_eq!(s.parse::<WeekStart>(), Ok(WeekStart(wd)));
+        }
+        assert!("s".parse::<WeekStart>().is_err());
+        assert!("thurs".parse::<WeekStart>().is_err());
+        assert!("weekend".parse::<WeekStart>().is_err());
+    }
+
+    #[test]
+    fn test_set_week_start() {
+        let today = date!(2024 - 06 - 12);
+        let mut window = WeekWindow::new(today, NullStyler);
+        assert!(shows(&mut window, today));
+        assert_eq!(window.focus_date(), date!(2024 - 06 - 09));
+        window.set_week_start(Weekday::Monday);
+        assert!(shows(&mut window, today));
+        assert_eq!(window.focus_date(), date!(2024 - 06 - 10));
+        window.set_week_start(Weekday::Saturday);
+        assert!(shows(&mut window, today));
+        assert_eq!(window.focus_date(), date!(2024 - 06 - 08));
+    }
+
+    #[test]
+    fn test_fill_weeks() {
+        let mut window = WeekWindow::new(date!(2024 - 06 - 12), NullStyler);
+        let min = NonZeroUsize::new(3).unwrap();
+        let front = *window.ensure_weeks(min).front();
+        let weeks = window.fill_weeks(min, |weeks| weeks.len().get() <= 5);
+        assert_eq!(weeks.len().get(), 5);
+        assert_eq!(*weeks.front(), front);
+        let weeks = window.fill_weeks(min, |weeks| weeks.len().get() <= 4);
+        assert_eq!(weeks.len().get(), 4);
+        assert_eq!(*weeks.front(), front);
+    }
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::tests::NullStyler;
+    use super::*;
+    use proptest::prelude::*;
+
+    #[derive(Clone, Copy, Debug)]
+    enum Op {
+        ScrollDown,
+        ScrollUp,
+        PageDown,
+        PageUp,
+        Today,
+        NextMarked,
+        Resize(usize),
+    }
+
+    fn arb_op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            Just(Op::ScrollDown),
+            Just(Op::ScrollUp),
+            Just(Op::PageDown),
+            Just(Op::PageUp),
+            Just(Op::Today),
+            Just(Op::NextMarked),
+            (1usize..40).prop_map(Op::Resize),
+        ]
+    }
+
+    /// Dates anywhere in time, with extra weight on those near the ends
+    fn arb_date() -> impl Strategy<Value = Date> {
+        let min = Date::MIN.to_julian_day();
+        let max = Date::MAX.to_julian_day();
+        prop_oneof![min..=max, min..=(min + 400), (max - 400)..=max,]
+            .prop_map(|jd| Date::from_julian_day(jd).unwrap())
+    }
+
+    fn arb_split() -> impl Strategy<Value = WeekSplit> {
+        (
+            proptest::option::of(0usize..50),
+            proptest::option::of(0usize..50),
+        )
+            .prop_map(|(before, after)| WeekSplit { before, after })
+    }
+
+    fn arb_today_jump() -> impl Strategy<Value = TodayJump> {
+        prop_oneof![
+            Just(TodayJump::Center),
+            Just(TodayJump::Top),
+            Just(TodayJump::Minimal),
+        ]
+    }
+
+    fn arb_weekday() -> impl Strategy<Value = Weekday> {
+        (0u8..7).prop_map(|n| Weekday::Sunday.nth_next(n))
+    }
+
+    /// Check that the window has `week_qty` weeks, that its dates are
+    /// contiguous, and that only a week at either end of time is partial
+    fn check_window(window: &mut WeekWindow<NullStyler>, week_qty: NonZeroUsize) {
+        let weeks = window.ensure_weeks(week_qty);
+        assert_eq!(weeks.len(), week_qty, "window should have requested length");
+        let dates = weeks
+            .into_iter()
+            .flat_map(|w| w.enumerate().map(|(_, sd)| sd.date))
+            .collect::<Vec<_>>();
+        for (&d1, &d2) in dates.iter().zip(dates.iter().skip(1)) {
+            assert_eq!(
+                d1.next_day(),
+                Some(d2),
+                "dates in window should be contiguous"
+            );
+        }
+        let first = dates.first().copied();
+        let last = dates.last().copied();
+        let week_start = window.week_factory.week_start();
+        assert!(
+            first.is_some_and(|d| d.weekday() == week_start || d == Date::MIN),
+            "window should start on the first weekday or at the start of time"
+        );
+        assert!(
+            last.is_some_and(|d| d.weekday() == week_start.previous() || d == Date::MAX),
+            "window should end on the last weekday or at the end of time"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn window_invariants(
+            today in arb_date(),
+            start in arb_date(),
+            marked in proptest::collection::vec(arb_date(), 0..4),
+            split in arb_split(),
+            today_jump in arb_today_jump(),
+            week_start in arb_weekday(),
+            height in 1usize..40,
+            ops in proptest::collection::vec(arb_op(), 0..50),
+        ) {
+            let mut week_qty = NonZeroUsize::new(height).unwrap();
+            let mut window = WeekWindow::new(today, NullStyler)
+                .marked(marked)
+                .start_date(start)
+                .split(split)
+                .today_jump(today_jump)
+                .week_start(week_start);
+            check_window(&mut window, week_qty);
+            for op in ops {
+                match op {
+                    Op::ScrollDown => {
+                        if window.one_week_forwards().is_err() {
+                            let weeks = window.ensure_weeks(week_qty);
+                            prop_assert!(weeks.back().enumerate().any(|(_, sd)| sd.date == Date::MAX));
+                        }
+                    }
+                    Op::ScrollUp => {
+                        if window.one_week_backwards().is_err() {
+                            let weeks = window.ensure_weeks(week_qty);
+                            prop_assert!(weeks.front().enumerate().any(|(_, sd)| sd.date == Date::MIN));
+                        }
+                    }
+                    Op::PageDown => {
+                        let _ = window.one_page_forwards();
+                    }
+                    Op::PageUp => {
+                        let _ = window.one_page_backwards();
+                    }
+                    Op::Today => window.jump_to_today(),
+                    Op::NextMarked => {
+                        let _ = window.cycle_marked(true);
+                    }
+                    Op::Resize(h) => week_qty = NonZeroUsize::new(h).unwrap(),
+                }
+                check_window(&mut window, week_qty);
+            }
+        }
+    }
 }
@@ -1,23 +1,25 @@
-use super::util::WeekdayExt;
+use super::util::{RingBuffer, Week, WeekdayExt};
 use super::weeks::WeekWindow;
 use super::DateStyler;
-use ratatui::{prelude::*, widgets::*};
+use ratatui::{layout::Flex, prelude::*, widgets::*};
+use std::iter::successors;
 use std::marker::PhantomData;
 use std::num::NonZeroUsize;
+use std::str::FromStr;
+use thiserror::Error;
 use time::{
     Date,
-    Month::{self, January},
-    Weekday::{self, Saturday},
+    Month::{self, December, January},
+    Weekday,
 };
 
-static HEADER: &str = " Su     Mo     Tu     We     Th     Fr     Sa ";
-
 /// Width of the calendar in columns, not counting the year and months in the
 /// margins
 const MAIN_WIDTH: u16 = 46;
 
 /// Number of columns on the left side of the calendar, used as the margin in
-/// which the year is written
+/// which the year is written.  The margin is widened when showing a year too
+/// long to fit, which can only happen with the `large-dates` feature.
 const LEFT_MARGIN: u16 = 6;
 
 const LONGEST_MONTH_NAME_LEN: u16 = 9; // September
@@ -30,12 +32,15 @@ const MONTH_GUTTER: u16 = 2;
 /// which the month is written
 const RIGHT_MARGIN: u16 = LONGEST_MONTH_NAME_LEN + MONTH_GUTTER;
 
-const TOTAL_WIDTH: u16 = LEFT_MARGIN + MAIN_WIDTH + RIGHT_MARGIN;
+/// Number of columns to the left of the calendar when the terminal is too
+/// narrow for the left margin, just enough for the left edge of the grid
+const NARROW_MARGIN: u16 = DAY_WIDTH - VBAR_OFFSET;
 
 /// Number of lines taken up by the header and its rule
 const HEADER_LINES: u16 = 2;
 
-/// Number of lines taken up by each week of the calendar
+/// Number of lines taken up by each week of the calendar when using
+/// `WeekSpacing::Uniform`
 const WEEK_LINES: u16 = 2;
 
 /// When inserting a vertical bar-like character between consecutive days in
@@ -46,163 +51,789 @@ const VBAR_OFFSET: u16 = 5;
 /// Number of columns per day of week
 const DAY_WIDTH: u16 = 7;
 
-const ACS_HLINE: char = '─';
-const ACS_VLINE: char = '│';
-const ACS_TTEE: char = '┬';
-const ACS_ULCORNER: char = '┌';
-const ACS_LRCORNER: char = '┘';
+/// The bars drawn in the sparkline for each number of quarters of the moon
+/// that are lit
+const UNICODE_SPARKS: [char; 5] = ['▁', '▂', '▄', '▆', '█'];
+
+/// For terminals that can't display block characters
+const ASCII_SPARKS: [char; 5] = ['_', '.', '-', '=', '#'];
+
+/// The characters used to draw the rule below the header and the lines
+/// between months
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct LineChars {
+    hline: char,
+    vline: char,
+    ttee: char,
+    ltee: char,
+    rtee: char,
+    cross: char,
+    ulcorner: char,
+    urcorner: char,
+    lrcorner: char,
+}
+
+const UNICODE_LINES: LineChars = LineChars {
+    hline: '─',
+    vline: '│',
+    ttee: '┬',
+    ltee: '├',
+    rtee: '┤',
+    cross: '┼',
+    ulcorner: '┌',
+    urcorner: '┐',
+    lrcorner: '┘',
+};
+
+/// For terminals that can't display box-drawing characters
+const ASCII_LINES: LineChars = LineChars {
+    hline: '-',
+    vline: '|',
+    ttee: '+',
+    ltee: '+',
+    rtee: '+',
+    cross: '+',
+    ulcorner: '+',
+    urcorner: '+',
+    lrcorner: '+',
+};
+
+/// Styles for the parts of the calendar other than the dates themselves
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct CalendarStyles {
+    /// The names of the days of the week at the top
+    pub(crate) header: Style,
+    pub(crate) year: Style,
+    pub(crate) month: Style,
+    /// The rule below the header and the lines between months
+    pub(crate) border: Style,
+    /// The lines between December and January, in place of `border`
+    pub(crate) year_border: Style,
+    /// The dates marked in the `WeekWindow`, patched over their styles
+    pub(crate) marked: Style,
+    /// Today's date, patched over the date's style
+    pub(crate) today: Style,
+    /// The brackets around today's date, patched over `today`
+    pub(crate) today_marker: Style,
+}
+
+/// Where to put blank lines between weeks
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) enum WeekSpacing {
+    /// Between every pair of weeks
+    #[default]
+    Uniform,
+    /// Only where needed to draw the border between two months, so that each
+    /// month forms a block and more weeks fit on the screen
+    Months,
+}
+
+impl WeekSpacing {
+    /// Returns whether a blank line goes between the consecutive weeks
+    /// `above` and `below`
+    fn gap_between(self, above: &Week, below: &Week) -> bool {
+        match self {
+            WeekSpacing::Uniform => true,
+            WeekSpacing::Months => below.has_month_start() || above.first_ym() != above.last_ym(),
+        }
+    }
+
+    /// Returns the line on which the dates of each week are drawn, given the
+    /// number of extra lines drawn directly below each week's dates
+    fn week_rows(self, weeks: &RingBuffer<Week>, extra: u16) -> Vec<u16> {
+        let mut rows = Vec::with_capacity(weeks.len().get());
+        let mut y = HEADER_LINES;
+        let mut prev = None;
+        for week in weeks {
+            if let Some(prev) = prev {
+                let lines = if self.gap_between(prev, week) {
+                    WEEK_LINES
+                } else {
+                    1
+                };
+                y = y.saturating_add(lines + extra);
+            }
+            rows.push(y);
+            prev = Some(week);
+        }
+        rows
+    }
+}
+
+impl FromStr for WeekSpacing {
+    type Err = ParseWeekSpacingError;
+
+    fn from_str(s: &str) -> Result<WeekSpacing, ParseWeekSpacingError> {
+        match s.to_ascii_lowercase().as_str() {
+            "uniform" => Ok(WeekSpacing::Uniform),
+            "months" => Ok(WeekSpacing::Months),
+            _ => Err(ParseWeekSpacingError),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("spacing must be \"uniform\" or \"months\"")]
+pub(crate) struct ParseWeekSpacingError;
+
+/// Which borders between months to draw
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) enum MonthBorders {
+    /// Between every pair of months
+    #[default]
+    All,
+    /// Only between December and January
+    Years,
+    /// None at all
+    None,
+}
+
+impl MonthBorders {
+    pub(crate) const ALL: [MonthBorders; 3] =
+        [MonthBorders::All, MonthBorders::Years, MonthBorders::None];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            MonthBorders::All => "all",
+            MonthBorders::Years => "years",
+            MonthBorders::None => "none",
+        }
+    }
+
+    /// Returns the next setting after this one, wrapping around
+    pub(crate) fn next(self) -> MonthBorders {
+        match self {
+            MonthBorders::All => MonthBorders::Years,
+            MonthBorders::Years => MonthBorders::None,
+            MonthBorders::None => MonthBorders::All,
+        }
+    }
+
+    /// Returns whether to draw the border after a month, given whether that
+    /// month is December
+    fn draws(self, year_end: bool) -> bool {
+        match self {
+            MonthBorders::All => true,
+            MonthBorders::Years => year_end,
+            MonthBorders::None => false,
+        }
+    }
+}
+
+impl FromStr for MonthBorders {
+    type Err = ParseMonthBordersError;
+
+    fn from_str(s: &str) -> Result<MonthBorders, ParseMonthBordersError> {
+        MonthBorders::ALL
+            .into_iter()
+            .find(|b| b.name().eq_ignore_ascii_case(s))
+            .ok_or(ParseMonthBordersError)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("month borders must be \"all\", \"years\", or \"none\"")]
+pub(crate) struct ParseMonthBordersError;
+
+/// How to number the years before 1 CE
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum YearNumbering {
+    /// Count them backwards as 1 BCE, 2 BCE, etc.
+    #[default]
+    Era,
+    /// Number them 0, -1, -2, etc., continuing on from 1 CE
+    Astronomical,
+}
+
+impl YearNumbering {
+    /// Returns the number to show for `year` (given in astronomical
+    /// numbering) and the era to show after it, if any
+    pub(crate) fn split(self, year: i32) -> (i32, Option<&'static str>) {
+        match self {
+            YearNumbering::Era if year < 1 => (1 - year, Some("BCE")),
+            _ => (year, None),
+        }
+    }
+
+    /// Returns the number of columns that `year` may take up in the left
+    /// margin
+    fn label_width(self, year: i32) -> u16 {
+        let (number, _) = self.split(year);
+        let len = number.to_string().len().max(year.to_string().len());
+        u16::try_from(len).unwrap_or(u16::MAX)
+    }
+}
+
+impl FromStr for YearNumbering {
+    type Err = ParseYearNumberingError;
+
+    fn from_str(s: &str) -> Result<YearNumbering, ParseYearNumberingError> {
+        match s.to_ascii_lowercase().as_str() {
+            "era" => Ok(YearNumbering::Era),
+            "astronomical" => Ok(YearNumbering::Astronomical),
+            _ => Err(ParseYearNumberingError),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("year numbering must be \"era\" or \"astronomical\"")]
+pub(crate) struct ParseYearNumberingError;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(crate) struct Calendar<S> {
+    styles: CalendarStyles,
+    lines: LineChars,
+    spacing: WeekSpacing,
+    grid: bool,
+    borders: MonthBorders,
+    week_qty: Option<NonZeroUsize>,
+    month_labels: bool,
+    sparkline: bool,
+    markers: bool,
+    phase_labels: bool,
+    year_numbering: YearNumbering,
+    offset: i16,
     _data: PhantomData<S>,
 }
 
 impl<S> Calendar<S> {
     pub(crate) fn new() -> Calendar<S> {
-        Calendar { _data: PhantomData }
+        Calendar {
+            styles: CalendarStyles::default(),
+            lines: UNICODE_LINES,
+            spacing: WeekSpacing::Uniform,
+            grid: false,
+            borders: MonthBorders::All,
+            week_qty: None,
+            month_labels: false,
+            sparkline: false,
+            markers: false,
+            phase_labels: false,
+            year_numbering: YearNumbering::default(),
+            offset: 0,
+            _data: PhantomData,
+        }
+    }
+
+    pub(crate) fn styles(mut self, styles: CalendarStyles) -> Calendar<S> {
+        self.styles = styles;
+        self
+    }
+
+    /// Draw lines with ASCII characters instead of box-drawing characters
+    pub(crate) fn ascii(mut self, ascii: bool) -> Calendar<S> {
+        self.lines = if ascii { ASCII_LINES } else { UNICODE_LINES };
+        self
+    }
+
+    pub(crate) fn spacing(mut self, spacing: WeekSpacing) -> Calendar<S> {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Draw lines around every day instead of only between months.  As the
+    /// lines between weeks are needed, this implies `WeekSpacing::Uniform`.
+    pub(crate) fn grid(mut self, grid: bool) -> Calendar<S> {
+        self.grid = grid;
+        self
+    }
+
+    /// Show the given number of weeks, centered vertically, instead of as
+    /// many as fit in the area.  If `None`, the area is filled.
+    pub(crate) fn weeks(mut self, week_qty: Option<NonZeroUsize>) -> Calendar<S> {
+        self.week_qty = week_qty;
+        self
     }
 
-    // ceil((lines - HEADER_LINES)/2)
-    fn weeks_for_lines(lines: u16) -> NonZeroUsize {
+    /// Set which borders between months to draw.  The rule below the header
+    /// and the lines drawn by `grid()` are unaffected.
+    pub(crate) fn month_borders(mut self, borders: MonthBorders) -> Calendar<S> {
+        self.borders = borders;
+        self
+    }
+
+    /// Write each month's abbreviated name under its first day, where there's
+    /// room.  This is always done when the area is too narrow for the month
+    /// names in the right margin.
+    pub(crate) fn month_labels(mut self, month_labels: bool) -> Calendar<S> {
+        self.month_labels = month_labels;
+        self
+    }
+
+    /// Draw a line under each week's dates with a bar under each date showing
+    /// how full the moon is, as given by `DateStyler::moon_phase()`
+    pub(crate) fn sparkline(mut self, sparkline: bool) -> Calendar<S> {
+        self.sparkline = sparkline;
+        self
+    }
+
+    /// Write a `*` after each full moon and an `o` after each new moon, as
+    /// given by `DateStyler::moon_phase()`, so that they can be told apart
+    /// without color
+    pub(crate) fn markers(mut self, markers: bool) -> Calendar<S> {
+        self.markers = markers;
+        self
+    }
+
+    /// Write "Full Moon" or "New Moon" in the right margin beside each week
+    /// containing one, in the style of the first such date, unless the
+    /// margin already holds a month name there
+    pub(crate) fn phase_labels(mut self, phase_labels: bool) -> Calendar<S> {
+        self.phase_labels = phase_labels;
+        self
+    }
+
+    /// Set how to show the years before 1 CE in the left margin
+    pub(crate) fn year_numbering(mut self, year_numbering: YearNumbering) -> Calendar<S> {
+        self.year_numbering = year_numbering;
+        self
+    }
+
+    /// Shift the weeks down (or, if negative, up) by the given number of
+    /// lines, for drawing the frames in between two scroll positions.  Weeks
+    /// shifted into the header are cut off.
+    pub(crate) fn scroll_offset(mut self, offset: i16) -> Calendar<S> {
+        self.offset = offset;
+        self
+    }
+
+    /// Number of lines drawn between each week's dates and the line below
+    /// them
+    fn extra_lines(&self) -> u16 {
+        u16::from(self.sparkline)
+    }
+
+    // ceil((lines - HEADER_LINES)/week_lines), where week_lines is the number
+    // of lines per week
+    fn weeks_for_lines(&self, lines: u16) -> NonZeroUsize {
+        let week_lines = WEEK_LINES + self.extra_lines();
         // If there's no room to show any weeks, request one week anyway so
         // that `WeekWindow.weeks` is always nonempty.
-        NonZeroUsize::new((lines.saturating_sub(HEADER_LINES).saturating_add(1) / 2).into())
-            .unwrap_or(NonZeroUsize::MIN)
+        NonZeroUsize::new(
+            (lines
+                .saturating_sub(HEADER_LINES)
+                .saturating_add(week_lines - 1)
+                / week_lines)
+                .into(),
+        )
+        .unwrap_or(NonZeroUsize::MIN)
     }
 }
 
 impl<S: DateStyler> StatefulWidget for Calendar<S> {
     type State = WeekWindow<S>;
 
-    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let left = (area.width.saturating_sub(MAIN_WIDTH) / 2).max(LEFT_MARGIN) - LEFT_MARGIN;
+    fn render(self, mut area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let today = state.today;
+        let marked = state.marked.clone();
+        // When the weeks are shifted up, an extra week may peek in at the
+        // bottom.
+        let lines = area
+            .height
+            .saturating_add(self.offset.min(0).unsigned_abs());
+        let spacing = if self.grid {
+            WeekSpacing::Uniform
+        } else {
+            self.spacing
+        };
+        let extra = self.extra_lines();
+        let weeks = match (self.week_qty, spacing) {
+            (Some(qty), _) => state.ensure_weeks(qty),
+            (None, WeekSpacing::Uniform) => state.ensure_weeks(self.weeks_for_lines(lines)),
+            // Start with as many weeks as fit with uniform spacing and add
+            // more for as long as the last one's dates are on the screen
+            (None, WeekSpacing::Months) => state.fill_weeks(self.weeks_for_lines(lines), |weeks| {
+                spacing
+                    .week_rows(weeks, extra)
+                    .last()
+                    .is_some_and(|&y| y < lines)
+            }),
+        };
+        let rows = spacing.week_rows(weeks, extra);
+        if self.week_qty.is_some() {
+            // Leave room for the line below the last week, where the border
+            // after a month may be drawn
+            let height = rows
+                .last()
+                .map_or(HEADER_LINES, |&y| y.saturating_add(extra + 2));
+            [area] = Layout::vertical([height]).flex(Flex::Center).areas(area);
+        }
+        let mut margin = weeks
+            .into_iter()
+            .flat_map(|w| [w.first_ym().0, w.last_ym().0])
+            .map(|year| self.year_numbering.label_width(year) + 1)
+            .fold(LEFT_MARGIN, u16::max);
+        // Rather than cut off the month names partway through, leave out the
+        // right margin if the terminal is too narrow for it (writing the
+        // months' names under their first days instead), and then the left
+        // margin as well if needed
+        let months_in_margin = area.width >= margin + MAIN_WIDTH + RIGHT_MARGIN;
+        let years_in_margin = area.width >= margin + MAIN_WIDTH;
+        if !years_in_margin {
+            margin = NARROW_MARGIN;
+        }
+        let left = (area.width.saturating_sub(MAIN_WIDTH) / 2).max(margin) - margin;
         // Flex::Center is not applicable here, as we're centering `MAIN_WIDTH`
-        // but getting a Rect for `TOTAL_WIDTH`.
+        // but getting a Rect that also includes the margins.
         let chunks = Layout::horizontal([
             Constraint::Length(left),
-            Constraint::Length(TOTAL_WIDTH.min(area.width)),
+            Constraint::Length((margin + MAIN_WIDTH + RIGHT_MARGIN).min(area.width)),
             Constraint::Min(0),
         ])
         .split(area);
-        let area = chunks[1];
-        let today = state.today;
-        let weeks = state.ensure_weeks(Self::weeks_for_lines(area.height));
-        let mut canvas = BufferCanvas::new(area, buf);
+        let mut canvas = BufferCanvas {
+            area: chunks[1],
+            buf,
+            styles: self.styles,
+            lines: self.lines,
+            week_start: weeks.front().start(),
+            margin,
+            years_in_margin,
+            months_in_margin,
+            rows,
+            extra,
+            offset: self.offset,
+        };
         canvas.draw_header();
+        if self.grid {
+            canvas.draw_grid();
+        }
         let top = *weeks.front();
-        canvas.draw_year(0, top.first_ym().0);
+        canvas.draw_year(0, top.first_ym().0, self.year_numbering);
         canvas.draw_month(0, top.last_ym().1);
         for (i, week) in std::iter::zip(0u16.., weeks) {
+            let mut phase_label = None;
             if week.has_month_start() {
                 let (first_year, first_month) = week.first_ym();
                 let (last_year, last_month) = week.last_ym();
                 canvas.draw_month(i, last_month);
                 if last_month == January {
                     if first_month == January {
-                        canvas.draw_year(i, first_year);
+                        canvas.draw_year(i, first_year, self.year_numbering);
                     } else if usize::from(i + 1) < weeks.len().get() {
-                        canvas.draw_year(i + 1, last_year);
+                        canvas.draw_year(i + 1, last_year, self.year_numbering);
                     }
                 }
             }
-            for (wd, date) in week.enumerate() {
-                let s = date.show(date.date == today);
+            for (wd, mut date) in week.enumerate() {
+                if phase_label.is_none() {
+                    phase_label = date.phase_label().map(|label| (label, date.style));
+                }
+                if marked.contains(&date.date) {
+                    date.style = date.style.patch(self.styles.marked);
+                }
+                let marker = date.marker().filter(|_| self.markers);
+                let s = if date.date == today {
+                    let mut s = date.show_today(self.styles.today, self.styles.today_marker);
+                    // The marker goes after the closing bracket
+                    if let Some(marker) = marker {
+                        s.spans.push(Span::styled(marker.to_string(), date.style));
+                    }
+                    s
+                } else {
+                    date.show(marker)
+                };
                 canvas.draw_day(i, wd, s);
+                if let Some(phase) = date.phase.filter(|_| self.sparkline) {
+                    let sparks = if self.lines == ASCII_LINES {
+                        ASCII_SPARKS
+                    } else {
+                        UNICODE_SPARKS
+                    };
+                    canvas.draw_spark(i, wd, sparks[phase.quarters_lit()]);
+                }
+                if (self.month_labels || !months_in_margin) && !self.grid && date.day() == 1 {
+                    canvas.draw_month_label(i, wd, date.month());
+                }
+                if self.grid {
+                    // The grid already separates every day
+                    continue;
+                }
                 if date.is_last_day_of_month() {
-                    canvas.draw_month_border(i, wd);
-                } else if date.date == Date::MIN {
+                    if !self.borders.draws(date.month() == December) {
+                        continue;
+                    }
+                    let style = if date.month() == December {
+                        self.styles.year_border
+                    } else {
+                        self.styles.border
+                    };
+                    canvas.draw_month_border(i, wd, style);
+                } else if date.date == Date::MIN && self.borders.draws(true) {
                     let weekday_before_time = wd.previous();
-                    // For time::Date's default bounds, `weekday_before_time`
-                    // is actually a Sunday, but we should be ready if the
-                    // bounds change.
-                    if weekday_before_time != Saturday {
-                        canvas.draw_month_border(i, weekday_before_time);
+                    // If the start of time falls in the first column, the
+                    // border goes at the end of the week above instead.
+                    if weekday_before_time != week.end() {
+                        canvas.draw_month_border(i, weekday_before_time, self.styles.border);
                     } else if i > 0 {
-                        canvas.draw_month_border(i - 1, weekday_before_time);
+                        canvas.draw_month_border(i - 1, weekday_before_time, self.styles.border);
                     }
                 }
             }
+            if let Some((label, style)) = phase_label {
+                if self.phase_labels && i > 0 && !week.has_month_start() {
+                    canvas.draw_right_margin(i, label, style);
+                }
+            }
         }
     }
 }
 
+/// Returns the calendar's header, with the weekdays' abbreviations starting at
+/// `start`
+fn header(start: Weekday) -> String {
+    successors(Some(start), |wd| Some(wd.next()))
+        .take(7)
+        .map(|wd| format!(" {} ", &wd.to_string()[..2]))
+        .collect::<Vec<_>>()
+        .join("   ")
+}
+
 #[derive(Debug, Eq, PartialEq)]
 struct BufferCanvas<'a> {
     area: Rect,
     buf: &'a mut Buffer,
+    styles: CalendarStyles,
+    lines: LineChars,
+    /// The weekday in the first column
+    week_start: Weekday,
+    /// Width of the left margin
+    margin: u16,
+    /// Whether to write the years in the left margin
+    years_in_margin: bool,
+    /// Whether to write the months & phase labels in the right margin
+    months_in_margin: bool,
+    /// The line on which the dates of each week are drawn, before shifting by
+    /// `offset`
+    rows: Vec<u16>,
+    /// Number of lines between each week's dates and the line below them
+    extra: u16,
+    offset: i16,
 }
 
-impl<'a> BufferCanvas<'a> {
-    fn new(area: Rect, buf: &'a mut Buffer) -> Self {
-        Self { area, buf }
+impl BufferCanvas<'_> {
+    /// Column of the left edge of the grid drawn by `Calendar::grid()`, which
+    /// lines up with where a vertical bar would go after a day before the
+    /// first column
+    fn grid_left(&self) -> u16 {
+        self.margin + VBAR_OFFSET - DAY_WIDTH
+    }
+
+    /// Column of the right edge of the grid drawn by `Calendar::grid()`
+    fn grid_right(&self) -> u16 {
+        self.grid_left() + DAY_WIDTH * 7
+    }
+
+    /// Returns how many lines below a week's dates the line between it and
+    /// the next week is, past any lines drawn directly below the dates
+    fn below(&self) -> i16 {
+        i16::try_from(self.extra).map_or(i16::MAX, |extra| extra.saturating_add(1))
+    }
+
+    /// Returns the line of the canvas `line` lines below the dates of week
+    /// `week_no`, or `None` if that would be in the header.  The line
+    /// directly above the dates of a week may be the header's rule.
+    fn row(&self, week_no: u16, line: i16) -> Option<u16> {
+        let week_row = *self.rows.get(usize::from(week_no))?;
+        let y = i32::from(week_row) + i32::from(self.offset) + i32::from(line);
+        u16::try_from(y)
+            .ok()
+            .filter(|&y| y >= HEADER_LINES || (line < 0 && y == HEADER_LINES - 1))
     }
 
     fn draw_header(&mut self) {
-        self.mvprint(0, LEFT_MARGIN, HEADER, Some(Style::new().bold()));
-        self.hline(1, LEFT_MARGIN, ACS_HLINE, MAIN_WIDTH);
+        self.mvprint(0, self.margin, header(self.week_start), self.styles.header);
+        self.hline(
+            1,
+            self.margin,
+            self.lines.hline,
+            MAIN_WIDTH,
+            self.styles.border,
+        );
     }
 
-    fn draw_year(&mut self, week_no: u16, year: i32) {
-        self.mvprint(
-            week_no * WEEK_LINES + HEADER_LINES,
-            0,
-            year.to_string(),
-            Some(Style::new().bold()),
+    /// Draw a box around every day, with the header's rule as its top
+    fn draw_grid(&mut self) {
+        let lines = self.lines;
+        self.draw_grid_rule(HEADER_LINES - 1, lines.ulcorner, lines.ttee, lines.urcorner);
+        for week_no in (0u16..).take(self.rows.len()) {
+            for line in 0..self.below() {
+                if let Some(y) = self.row(week_no, line) {
+                    for x in (self.grid_left()..=self.grid_right()).step_by(DAY_WIDTH.into()) {
+                        self.mvaddch(y, x, lines.vline, self.styles.border);
+                    }
+                }
+            }
+            for line in [-1, self.below()] {
+                match self.row(week_no, line) {
+                    Some(y) if y != HEADER_LINES - 1 => {
+                        self.draw_grid_rule(y, lines.ltee, lines.cross, lines.rtee);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// Draw a horizontal line of the grid, using `left`, `middle`, and `right`
+    /// where it meets the vertical lines
+    fn draw_grid_rule(&mut self, y: u16, left: char, middle: char, right: char) {
+        let border = self.styles.border;
+        let (grid_left, grid_right) = (self.grid_left(), self.grid_right());
+        self.hline(
+            y,
+            grid_left,
+            self.lines.hline,
+            grid_right - grid_left,
+            border,
         );
+        self.mvaddch(y, grid_left, left, border);
+        for x in (grid_left + DAY_WIDTH..grid_right).step_by(DAY_WIDTH.into()) {
+            self.mvaddch(y, x, middle, border);
+        }
+        self.mvaddch(y, grid_right, right, border);
+    }
+
+    /// Write `year` in the left margin.  A year's era is written on the line
+    /// below it; if that line is taken by the next week, the era is
+    /// abbreviated after the year if that fits in the margin, and otherwise
+    /// the year is written in astronomical numbering instead.
+    fn draw_year(&mut self, week_no: u16, year: i32, numbering: YearNumbering) {
+        if !self.years_in_margin {
+            return;
+        }
+        let Some(y) = self.row(week_no, 0) else {
+            return;
+        };
+        match numbering.split(year) {
+            (number, Some(era)) => {
+                if let Some(below) = self.free_line_below(week_no) {
+                    self.mvprint(y, 0, number.to_string(), self.styles.year);
+                    self.mvprint(below, 0, era, self.styles.year);
+                } else {
+                    let mut s = format!("{number}{}", &era[..2]);
+                    if s.len() > usize::from(self.margin) {
+                        s = year.to_string();
+                    }
+                    self.mvprint(y, 0, s, self.styles.year);
+                }
+            }
+            (number, None) => self.mvprint(y, 0, number.to_string(), self.styles.year),
+        }
+    }
+
+    /// Returns the line below the dates (and sparkline) of week `week_no`,
+    /// unless the next week is drawn there or it's off the bottom of the
+    /// canvas
+    fn free_line_below(&self, week_no: u16) -> Option<u16> {
+        let y = self
+            .row(week_no, self.below())
+            .filter(|&y| y < self.area.height)?;
+        if let Some(&next) = self.rows.get(usize::from(week_no) + 1) {
+            if next <= self.rows[usize::from(week_no)] + 1 + self.extra {
+                return None;
+            }
+        }
+        Some(y)
     }
 
     fn draw_month(&mut self, week_no: u16, month: Month) {
-        self.mvprint(
-            week_no * WEEK_LINES + HEADER_LINES,
-            LEFT_MARGIN + MAIN_WIDTH + MONTH_GUTTER,
-            month.to_string(),
-            Some(Style::new().bold()),
-        );
+        self.draw_right_margin(week_no, month.to_string(), self.styles.month);
+    }
+
+    /// Write `text` in the right margin beside the dates of week `week_no`
+    fn draw_right_margin<S: AsRef<str>>(&mut self, week_no: u16, text: S, style: Style) {
+        if !self.months_in_margin {
+            return;
+        }
+        if let Some(y) = self.row(week_no, 0) {
+            self.mvprint(y, self.margin + MAIN_WIDTH + MONTH_GUTTER, text, style);
+        }
+    }
+
+    fn draw_day(&mut self, week_no: u16, wd: Weekday, line: Line<'_>) {
+        let Some(y) = self.row(week_no, 0) else {
+            return;
+        };
+        let mut x = self.margin + DAY_WIDTH * wd.column(self.week_start);
+        for span in line.spans {
+            let width = u16::try_from(span.width()).unwrap_or(u16::MAX);
+            self.mvprint(y, x, span.content, span.style);
+            x = x.saturating_add(width);
+        }
+    }
+
+    /// Draw a bar on the line under the given day
+    fn draw_spark(&mut self, week_no: u16, wd: Weekday, spark: char) {
+        if let Some(y) = self.row(week_no, 1) {
+            let x = self.margin + DAY_WIDTH * wd.column(self.week_start) + 1;
+            let s = String::from(spark).repeat(2);
+            self.mvprint(y, x, s, Style::new());
+        }
     }
 
-    fn draw_day(&mut self, week_no: u16, wd: Weekday, s: Span<'_>) {
+    /// Write the abbreviated name of `month` under the given day, the first of
+    /// the month, unless the next week is drawn on the line below
+    fn draw_month_label(&mut self, week_no: u16, wd: Weekday, month: Month) {
+        let Some(y) = self.free_line_below(week_no) else {
+            return;
+        };
+        let name = month.to_string();
         self.mvprint(
-            week_no * WEEK_LINES + HEADER_LINES,
-            LEFT_MARGIN + DAY_WIDTH * wd.index0(),
-            s.content,
-            Some(s.style),
+            y,
+            self.margin + DAY_WIDTH * wd.column(self.week_start) + 1,
+            name.get(..3).unwrap_or(&name),
+            self.styles.month,
         );
     }
 
     // `week_no` and `wd` specify the "coordinates" of the last day of the
-    // month after which the border is drawn
-    fn draw_month_border(&mut self, week_no: u16, wd: Weekday) {
-        let y = week_no * WEEK_LINES + HEADER_LINES;
-        let offset = DAY_WIDTH * wd.index0();
-        let bar_col = LEFT_MARGIN + offset + VBAR_OFFSET;
-        if wd != Saturday {
-            self.mvaddch(y, bar_col, ACS_VLINE);
-            self.mvaddch(
-                y - 1,
-                bar_col,
-                if week_no == 0 { ACS_TTEE } else { ACS_ULCORNER },
-            );
-            if week_no > 0 {
-                if let Some(length) = MAIN_WIDTH.checked_sub(offset + VBAR_OFFSET + 1) {
-                    self.hline(y - 1, bar_col + 1, ACS_HLINE, length);
+    // month after which the border is drawn, and `style` is the style to draw
+    // it in
+    fn draw_month_border(&mut self, week_no: u16, wd: Weekday, style: Style) {
+        let offset = DAY_WIDTH * wd.column(self.week_start);
+        let bar_col = self.margin + offset + VBAR_OFFSET;
+        let lines = self.lines;
+        let below = self.row(week_no, self.below());
+        if wd != self.week_start.previous() {
+            for line in 0..self.below() {
+                if let Some(y) = self.row(week_no, line) {
+                    self.mvaddch(y, bar_col, lines.vline, style);
+                }
+            }
+            if let Some(above) = self.row(week_no, -1) {
+                if above == HEADER_LINES - 1 {
+                    // Join the header's rule
+                    self.mvaddch(above, bar_col, lines.ttee, style);
+                } else {
+                    self.mvaddch(above, bar_col, lines.ulcorner, style);
+                    if let Some(length) = MAIN_WIDTH.checked_sub(offset + VBAR_OFFSET + 1) {
+                        self.hline(above, bar_col + 1, lines.hline, length, style);
+                    }
                 }
             }
-            self.mvaddch(y + 1, bar_col, ACS_LRCORNER);
+            if let Some(below) = below {
+                self.mvaddch(below, bar_col, lines.lrcorner, style);
+            }
+        }
+        if let Some(below) = below {
+            self.hline(below, self.margin, lines.hline, offset + VBAR_OFFSET, style);
         }
-        self.hline(y + 1, LEFT_MARGIN, ACS_HLINE, offset + VBAR_OFFSET);
     }
 
-    fn mvaddch(&mut self, y: u16, x: u16, ch: char) {
+    fn mvaddch(&mut self, y: u16, x: u16, ch: char, style: Style) {
         if y < self.area.height && x < self.area.width {
             self.buf
                 .get_mut(x + self.area.x, y + self.area.y)
-                .set_char(ch);
+                .set_char(ch)
+                .set_style(style);
         }
     }
 
-    fn mvprint<S: AsRef<str>>(&mut self, y: u16, x: u16, s: S, style: Option<Style>) {
+    fn mvprint<S: AsRef<str>>(&mut self, y: u16, x: u16, s: S, style: Style) {
         if y < self.area.height && x < self.area.width {
-            let text = s.as_ref().set_style(style.unwrap_or_default());
+            let text = s.as_ref().set_style(style);
             let width = u16::try_from(text.width()).unwrap_or(u16::MAX);
             // Using a Paragraph lets us truncate text that extends beyond the
             // calendar's area, though we need to be sure that the Rect passed
@@ -220,7 +851,324 @@ impl<'a> BufferCanvas<'a> {
         }
     }
 
-    fn hline(&mut self, y: u16, x: u16, ch: char, length: u16) {
-        self.mvprint(y, x, String::from(ch).repeat(length.into()), None);
+    fn hline(&mut self, y: u16, x: u16, ch: char, length: u16, style: Style) {
+        self.mvprint(y, x, String::from(ch).repeat(length.into()), style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dump::buffer_text;
+    use crate::moon::{MoonPhases, NetHack};
+    use time::macros::date;
+
+    #[test]
+    fn test_week_start() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 70, 7));
+        let mut window = WeekWindow::new(date!(2024 - 03 - 10), ())
+            .start_date(date!(2024 - 03 - 31))
+            .week_start(Weekday::Monday);
+        Calendar::<()>::new().render(buf.area, &mut buf, &mut window);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "             Mo     Tu     We     Th     Fr     Sa     Su\n",
+                "            ──────────────────────────────────────────────\n",
+                "      2024   18     19     20     21     22     23     24   March\n",
+                "\n",
+                "             25     26     27     28     29     30     31\n",
+                "            ───────────────────────────────────────────────\n",
+                "              1      2      3      4      5      6      7   April\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_month_spacing() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 70, 9));
+        let mut window =
+            WeekWindow::new(date!(2024 - 03 - 10), ()).start_date(date!(2024 - 03 - 24));
+        Calendar::<()>::new()
+            .spacing(WeekSpacing::Months)
+            .render(buf.area, &mut buf, &mut window);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "             Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "            ──────────────────────────────────────────────\n",
+                "      2024   17     18     19     20     21     22     23   March\n",
+                "             24     25     26     27     28     29     30\n",
+                "                 ┌────────────────────────────────────────\n",
+                "             31  │   1      2      3      4      5      6   April\n",
+                "            ─────┘\n",
+                "              7      8      9     10     11     12     13\n",
+                "             14     15     16     17     18     19     20\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 70, 6));
+        let mut window =
+            WeekWindow::new(date!(2024 - 03 - 10), ()).start_date(date!(2024 - 03 - 31));
+        Calendar::<()>::new()
+            .grid(true)
+            .spacing(WeekSpacing::Months)
+            .ascii(true)
+            .render(buf.area, &mut buf, &mut window);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "             Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "          +------+------+------+------+------+------+------+\n",
+                "      2024|  31  |   1  |   2  |   3  |   4  |   5  |   6  |April\n",
+                "          +------+------+------+------+------+------+------+\n",
+                "          |   7  |   8  |   9  |  10  |  11  |  12  |  13  |\n",
+                "          +------+------+------+------+------+------+------+\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let phases = MoonPhases {
+            phases: NetHack,
+            full: Style::new(),
+            new: Style::new(),
+        };
+        let mut buf = Buffer::empty(Rect::new(0, 0, 70, 8));
+        let mut window =
+            WeekWindow::new(date!(2024 - 03 - 10), phases).start_date(date!(2024 - 03 - 24));
+        Calendar::new()
+            .sparkline(true)
+            .ascii(true)
+            .render(buf.area, &mut buf, &mut window);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "             Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "            ----------------------------------------------\n",
+                "      2024   24     25     26     27     28     29     30   March\n",
+                "             ##     ##     ##     ==     ==     ==     ==\n",
+                "                 +----------------------------------------\n",
+                "             31  |   1      2      3      4      5      6   April\n",
+                "             --  |  --     --     --     ..     ..     ..\n",
+                "            -----+\n",
+            )
+        );
+        let mut buf = Buffer::empty(Rect::new(0, 0, 70, 8));
+        Calendar::new()
+            .sparkline(true)
+            .grid(true)
+            .render(buf.area, &mut buf, &mut window);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "             Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "          ┌──────┬──────┬──────┬──────┬──────┬──────┬──────┐\n",
+                "      2024│  24  │  25  │  26  │  27  │  28  │  29  │  30  │March\n",
+                "          │  ██  │  ██  │  ██  │  ▆▆  │  ▆▆  │  ▆▆  │  ▆▆  │\n",
+                "          ├──────┼──────┼──────┼──────┼──────┼──────┼──────┤\n",
+                "          │  31  │   1  │   2  │   3  │   4  │   5  │   6  │April\n",
+                "          │  ▄▄  │  ▄▄  │  ▄▄  │  ▄▄  │  ▂▂  │  ▂▂  │  ▂▂  │\n",
+                "          ├──────┼──────┼──────┼──────┼──────┼──────┼──────┤\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_phase_labels() {
+        let phases = MoonPhases {
+            phases: NetHack,
+            full: Style::new(),
+            new: Style::new(),
+        };
+        let mut buf = Buffer::empty(Rect::new(0, 0, 70, 12));
+        let mut window =
+            WeekWindow::new(date!(2024 - 03 - 10), phases).start_date(date!(2024 - 03 - 24));
+        Calendar::new()
+            .phase_labels(true)
+            .ascii(true)
+            .render(buf.area, &mut buf, &mut window);
+        // The new moon on March 10 goes unlabelled, as the month name is
+        // already beside it
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "             Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "            ----------------------------------------------\n",
+                "      2024  [10]    11     12     13     14     15     16   March\n",
+                "\n",
+                "             17     18     19     20     21     22     23\n",
+                "\n",
+                "             24     25     26     27     28     29     30   Full Moon\n",
+                "                 +----------------------------------------\n",
+                "             31  |   1      2      3      4      5      6   April\n",
+                "            -----+\n",
+                "              7      8      9     10     11     12     13   New Moon\n",
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_month_labels() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 54, 6));
+        let mut window =
+            WeekWindow::new(date!(2024 - 03 - 10), ()).start_date(date!(2024 - 03 - 31));
+        Calendar::<()>::new()
+            .month_labels(true)
+            .render(buf.area, &mut buf, &mut window);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "       Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "      ─────┬────────────────────────────────────────\n",
+                "2024   31  │   1      2      3      4      5      6\n",
+                "      ─────┘  Apr\n",
+                "        7      8      9     10     11     12     13\n",
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_narrow() {
+        let render = |width| {
+            let mut buf = Buffer::empty(Rect::new(0, 0, width, 6));
+            let mut window =
+                WeekWindow::new(date!(2024 - 03 - 10), ()).start_date(date!(2024 - 03 - 31));
+            Calendar::<()>::new().render(buf.area, &mut buf, &mut window);
+            buffer_text(&buf)
+        };
+        // Too narrow for the month names
+        assert_eq!(
+            render(58),
+            concat!(
+                "       Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "      ─────┬────────────────────────────────────────\n",
+                "2024   31  │   1      2      3      4      5      6\n",
+                "      ─────┘  Apr\n",
+                "        7      8      9     10     11     12     13\n",
+                "\n",
+            )
+        );
+        // Too narrow for the years as well
+        assert_eq!(
+            render(48),
+            concat!(
+                "   Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "  ─────┬────────────────────────────────────────\n",
+                "   31  │   1      2      3      4      5      6\n",
+                "  ─────┘  Apr\n",
+                "    7      8      9     10     11     12     13\n",
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_fixed_weeks() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 70, 10));
+        let mut window = WeekWindow::new(date!(2024 - 03 - 10), ());
+        Calendar::<()>::new()
+            .weeks(NonZeroUsize::new(2))
+            .render(buf.area, &mut buf, &mut window);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "\n",
+                "\n",
+                "             Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "            ──────────────────────────────────────────────\n",
+                "      2024  [10]    11     12     13     14     15     16   March\n",
+                "\n",
+                "             17     18     19     20     21     22     23\n",
+                "\n",
+                "\n",
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_year_numbering() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 70, 7));
+        let mut window =
+            WeekWindow::new(date!(2024 - 03 - 10), ()).start_date(date!(-0044 - 01 - 07));
+        Calendar::<()>::new().render(buf.area, &mut buf, &mut window);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "             Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "            ──────────────────────────────────────────────\n",
+                "      46     25     26     27     28     29     30     31   December\n",
+                "      BCE   ───────────────────────────────────────────────\n",
+                "      45      1      2      3      4      5      6      7   January\n",
+                "      BCE\n",
+                "              8      9     10     11     12     13     14\n",
+            )
+        );
+        let mut buf = Buffer::empty(Rect::new(0, 0, 70, 6));
+        Calendar::<()>::new()
+            .spacing(WeekSpacing::Months)
+            .render(buf.area, &mut buf, &mut window);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "             Su     Mo     Tu     We     Th     Fr     Sa\n",
+                "            ──────────────────────────────────────────────\n",
+                "      46     25     26     27     28     29     30     31   December\n",
+                "      BCE   ───────────────────────────────────────────────\n",
+                "      45BC    1      2      3      4      5      6      7   January\n",
+                "              8      9     10     11     12     13     14\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_year_border() {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 70, 8));
+        let mut window = WeekWindow::new(date!(2024 - 12 - 31), ());
+        let styles = CalendarStyles {
+            year_border: Style::new().red(),
+            ..CalendarStyles::default()
+        };
+        Calendar::<()>::new()
+            .styles(styles)
+            .render(buf.area, &mut buf, &mut window);
+        let corners = buf
+            .content()
+            .iter()
+            .filter(|cell| cell.symbol() == "┘")
+            .collect::<Vec<_>>();
+        assert_eq!(corners.len(), 1);
+        assert_eq!(corners[0].fg, Color::Red);
+        // The header's rule keeps the ordinary border style
+        assert_eq!(buf.get(10, 1).fg, Color::Reset);
+    }
+
+    #[test]
+    fn test_month_borders() {
+        // Returns the number of lines below the header with a border on them
+        let border_lines = |borders| {
+            let mut buf = Buffer::empty(Rect::new(0, 0, 70, 20));
+            let mut window = WeekWindow::new(date!(2024 - 12 - 15), ());
+            Calendar::<()>::new()
+                .month_borders(borders)
+                .render(buf.area, &mut buf, &mut window);
+            buffer_text(&buf)
+                .lines()
+                .skip(usize::from(HEADER_LINES))
+                .filter(|line| line.contains('─'))
+                .count()
+        };
+        // The ends of November and December
+        assert_eq!(border_lines(MonthBorders::All), 3);
+        assert_eq!(border_lines(MonthBorders::Years), 2);
+        assert_eq!(border_lines(MonthBorders::None), 0);
+        assert_eq!("YEARS".parse::<MonthBorders>(), Ok(MonthBorders::Years));
     }
 }
@@ -0,0 +1,309 @@
+//! Display of dates in calendars other than the Gregorian
+use crate::calendar::YearNumbering;
+use crate::datefmt::DateFormat;
+use crate::julian::JulianDate;
+use icu_calendar::{chinese::Chinese, hebrew::Hebrew, islamic::IslamicCivil, Iso};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use thiserror::Error;
+use time::Date;
+
+/// Years for which the Chinese calendar is shown.  Its astronomical
+/// calculations break down for years far from the present, which are only
+/// reachable with the `large-dates` feature.
+const CHINESE_YEARS: RangeInclusive<i32> = -9999..=9999;
+
+/// English names of the Hebrew months, by month code
+static HEBREW_MONTHS: [(&str, &str); 14] = [
+    ("M01", "Tishrei"),
+    ("M02", "Cheshvan"),
+    ("M03", "Kislev"),
+    ("M04", "Tevet"),
+    ("M05", "Shevat"),
+    ("M05L", "Adar I"),
+    ("M06", "Adar"),
+    ("M06L", "Adar II"),
+    ("M07", "Nisan"),
+    ("M08", "Iyar"),
+    ("M09", "Sivan"),
+    ("M10", "Tammuz"),
+    ("M11", "Av"),
+    ("M12", "Elul"),
+];
+
+/// English names of the Islamic months, in order
+static ISLAMIC_MONTHS: [&str; 12] = [
+    "Muharram",
+    "Safar",
+    "Rabi' al-Awwal",
+    "Rabi' al-Thani",
+    "Jumada al-Ula",
+    "Jumada al-Akhirah",
+    "Rajab",
+    "Sha'ban",
+    "Ramadan",
+    "Shawwal",
+    "Dhu al-Qa'dah",
+    "Dhu al-Hijjah",
+];
+
+/// The animals of the Chinese zodiac, starting with that of the first year of
+/// the sexagenary cycle
+static ZODIAC: [&str; 12] = [
+    "Rat", "Ox", "Tiger", "Rabbit", "Dragon", "Snake", "Horse", "Goat", "Monkey", "Rooster", "Dog",
+    "Pig",
+];
+
+/// The calendars other than the Gregorian in which the info popup shows the
+/// date
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct OtherCalendars {
+    pub(crate) julian: bool,
+    pub(crate) hebrew: bool,
+    pub(crate) islamic: bool,
+    pub(crate) chinese: bool,
+}
+
+impl OtherCalendars {
+    pub(crate) const NONE: OtherCalendars = OtherCalendars {
+        julian: false,
+        hebrew: false,
+        islamic: false,
+        chinese: false,
+    };
+
+    /// Returns a line for the info popup for each selected calendar, with
+    /// Julian dates shown in the given format
+    pub(crate) fn lines(
+        self,
+        date: Date,
+        format: DateFormat,
+        numbering: YearNumbering,
+    ) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.julian {
+            let JulianDate { year, month, day } = JulianDate::from(date);
+            lines.push(format!(
+                "Old style:      {} (Julian calendar)",
+                format.format_ymd(year, month, day, numbering)
+            ));
+        }
+        let Ok(iso) =
+            icu_calendar::Date::try_new_iso_date(date.year(), u8::from(date.month()), date.day())
+        else {
+            return lines;
+        };
+        if self.hebrew {
+            lines.push(format!("Hebrew:         {}", show_hebrew(&iso)));
+        }
+        if self.islamic {
+            lines.push(format!("Islamic:        {} (tabular)", show_islamic(&iso)));
+        }
+        if self.chinese && CHINESE_YEARS.contains(&date.year()) {
+            lines.push(format!("Chinese:        {}", show_chinese(&iso)));
+        }
+        lines
+    }
+}
+
+impl Default for OtherCalendars {
+    fn default() -> OtherCalendars {
+        OtherCalendars {
+            julian: true,
+            ..OtherCalendars::NONE
+        }
+    }
+}
+
+impl FromStr for OtherCalendars {
+    type Err = ParseCalendarsError;
+
+    /// Parse a comma-separated list of calendar names, or `none`
+    fn from_str(s: &str) -> Result<OtherCalendars, ParseCalendarsError> {
+        let mut calendars = OtherCalendars::NONE;
+        if s.trim().eq_ignore_ascii_case("none") {
+            return Ok(calendars);
+        }
+        for name in s.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            match name.to_ascii_lowercase().as_str() {
+                "julian" => calendars.julian = true,
+                "hebrew" => calendars.hebrew = true,
+                "islamic" => calendars.islamic = true,
+                "chinese" => calendars.chinese = true,
+                _ => return Err(ParseCalendarsError(name.to_owned())),
+            }
+        }
+        Ok(calendars)
+    }
+}
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+#[error("unknown calendar {0:?}; must be \"julian\", \"hebrew\", \"islamic\", or \"chinese\"")]
+pub(crate) struct ParseCalendarsError(String);
+
+fn show_hebrew(iso: &icu_calendar::Date<Iso>) -> String {
+    let date = iso.to_calendar(Hebrew);
+    let code = date.month().code;
+    let month = HEBREW_MONTHS
+        .iter()
+        .find(|&&(c, _)| c == code.0.as_str())
+        .map_or(code.0.as_str(), |&(_, name)| name);
+    format!("{} {} {}", date.day_of_month().0, month, date.year().number)
+}
+
+fn show_islamic(iso: &icu_calendar::Date<Iso>) -> String {
+    let date = iso.to_calendar(IslamicCivil);
+    let month = usize::try_from(date.month().ordinal)
+        .ok()
+        .and_then(|i| ISLAMIC_MONTHS.get(i.checked_sub(1)?))
+        .copied()
+        .unwrap_or("?");
+    format!(
+        "{} {} {} AH",
+        date.day_of_month().0,
+        month,
+        date.year().number
+    )
+}
+
+/// Shows the month & day of the Chinese lunisolar calendar along with the
+/// zodiac animal of the year, marking the Lunar New Year
+fn show_chinese(iso: &icu_calendar::Date<Iso>) -> String {
+    let date = iso.to_calendar(Chinese::new_always_calculating());
+    // Month codes are "M01" through "M12", with an "L" appended for leap
+    // months
+    let code = date.month().code;
+    let leap = code.0.as_str().ends_with('L');
+    let month = code
+        .0
+        .as_str()
+        .get(1..3)
+        .and_then(|n| n.parse::<u8>().ok())
+        .unwrap_or_default();
+    let day = date.day_of_month().0;
+    let mut s = format!(
+        "{}month {month}, day {day}",
+        if leap { "leap " } else { "" }
+    );
+    if let Some(animal) = date
+        .year()
+        .cyclic
+        .and_then(|c| ZODIAC.get(usize::from(c.get() - 1) % ZODIAC.len()))
+    {
+        s.push_str(", year of the ");
+        s.push_str(animal);
+    }
+    if month == 1 && !leap && day == 1 {
+        s.push_str(" (Lunar New Year)");
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    #[test]
+    fn test_lines() {
+        let all = OtherCalendars {
+            julian: true,
+            hebrew: true,
+            islamic: true,
+            chinese: true,
+        };
+        assert_eq!(
+            all.lines(
+                date!(2024 - 03 - 10),
+                DateFormat::default(),
+                YearNumbering::Era
+            ),
+            [
+                "Old style:      2024-02-26 (Julian calendar)",
+                "Hebrew:         30 Adar I 5784",
+                "Islamic:        29 Sha'ban 1445 AH (tabular)",
+                "Chinese:        month 2, day 1, year of the Dragon",
+            ]
+        );
+        let chinese = OtherCalendars {
+            chinese: true,
+            ..OtherCalendars::NONE
+        };
+        assert_eq!(
+            chinese.lines(
+                date!(2024 - 02 - 10),
+                DateFormat::default(),
+                YearNumbering::Era
+            ),
+            ["Chinese:        month 1, day 1, year of the Dragon (Lunar New Year)"]
+        );
+        assert_eq!(
+            chinese.lines(
+                date!(2023 - 03 - 22),
+                DateFormat::default(),
+                YearNumbering::Era
+            ),
+            ["Chinese:        leap month 2, day 1, year of the Rabbit"]
+        );
+        let julian = OtherCalendars {
+            julian: true,
+            ..OtherCalendars::NONE
+        };
+        assert_eq!(
+            julian.lines(
+                date!(-0044 - 03 - 13),
+                DateFormat::default(),
+                YearNumbering::Era
+            ),
+            ["Old style:      0045-03-15 BCE (Julian calendar)"]
+        );
+        assert!(OtherCalendars::NONE
+            .lines(
+                date!(2024 - 03 - 10),
+                DateFormat::default(),
+                YearNumbering::Era
+            )
+            .is_empty());
+    }
+
+    #[test]
+    fn test_extremes() {
+        let all = OtherCalendars {
+            julian: true,
+            hebrew: true,
+            islamic: true,
+            chinese: true,
+        };
+        for date in [
+            Date::MIN,
+            Date::MAX,
+            date!(-9999 - 01 - 01),
+            date!(9999 - 12 - 31),
+        ] {
+            let lines = all.lines(date, DateFormat::default(), YearNumbering::Era);
+            if CHINESE_YEARS.contains(&date.year()) {
+                assert_eq!(lines.len(), 4);
+            } else {
+                assert_eq!(lines.len(), 3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            "Hebrew, islamic".parse::<OtherCalendars>(),
+            Ok(OtherCalendars {
+                julian: false,
+                hebrew: true,
+                islamic: true,
+                chinese: false,
+            })
+        );
+        assert_eq!("none".parse::<OtherCalendars>(), Ok(OtherCalendars::NONE));
+        assert_eq!(
+            "julian,mayan".parse::<OtherCalendars>(),
+            Err(ParseCalendarsError("mayan".into()))
+        );
+    }
+}
@@ -0,0 +1,858 @@
+//! The `nhmoon` command-line interface
+use crate::app::{App, Bell, Clipboard};
+use crate::astro::Location;
+use crate::calendar::{MonthBorders, TodayJump, WeekSpacing, WeekStart, WeekWindow, YearNumbering};
+use crate::calendars::OtherCalendars;
+use crate::compare::Comparison;
+use crate::config::{check_config, load_config, ConfigSource};
+use crate::control::{read_fifo, ControlCommand};
+#[cfg(unix)]
+use crate::control::{send_command, CommandSocket};
+use crate::crash::{crash_report, save_crash_report, ActionLog};
+use crate::datefmt::DateFormat;
+use crate::day_list::View;
+use crate::dump::{buffer_text, DumpSpec};
+use crate::keymap::Binding;
+use crate::luck::{Luck, LuckFormat};
+use crate::moon::{Hemisphere, PhaseAlgorithm, Phoon};
+use crate::options::{split_args, Options};
+use crate::script::load_script;
+use crate::session::{is_first_run, load_session, save_session};
+use crate::settings::{Highlighter, Settings};
+use crate::shade::Shading;
+use crate::status::{MoonStatus, StatusFormat};
+use crate::term::{Background, Capabilities, ColorSupport};
+use crate::theme::load_theme;
+use anyhow::{bail, Context};
+use crossterm::{
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use lexopt::{Arg, Parser, ValueExt};
+use ratatui::{backend::TestBackend, prelude::*};
+use std::backtrace::Backtrace;
+use std::io;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use time::{Date, OffsetDateTime};
+
+/// Name of the environment variable from which default command-line options
+/// are read
+const OPTS_VAR: &str = "NHMOON_OPTS";
+
+#[derive(Clone, Debug, PartialEq)]
+enum Command {
+    Run {
+        /// Dates as given on the command line, parsed once the date format
+        /// is known
+        dates: Vec<String>,
+        options: Options,
+    },
+    Luck {
+        date: Option<String>,
+        options: Options,
+        format: LuckFormat,
+    },
+    Status {
+        date: Option<String>,
+        options: Options,
+        format: StatusFormat,
+        /// Show moons as emoji in the plain format
+        glyph: bool,
+        /// Only output anything during a full or new moon
+        only_notable: bool,
+    },
+    Remote {
+        socket: PathBuf,
+        /// The words of the command to send
+        command: Vec<String>,
+        options: Options,
+    },
+    CheckConfig(Options),
+    Help,
+    Version,
+}
+
+impl Command {
+    fn from_parser(mut parser: Parser) -> Result<Command, lexopt::Error> {
+        let mut dates = Vec::new();
+        let mut options = Options::default();
+        let mut check_config = false;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help),
+                Arg::Short('V') | Arg::Long("version") => return Ok(Command::Version),
+                Arg::Long("check-config") => check_config = true,
+                Arg::Long("config") => {
+                    options.config = Some(ConfigSource::Path(PathBuf::from(parser.value()?)));
+                }
+                Arg::Long("no-config") => options.config = Some(ConfigSource::Disabled),
+                Arg::Long("remote") if dates.is_empty() => {
+                    let socket = PathBuf::from(parser.value()?);
+                    return Command::remote_from_parser(parser, options, socket);
+                }
+                Arg::Value(value) => {
+                    if dates.is_empty() && value == "luck" {
+                        return Command::luck_from_parser(parser, options);
+                    }
+                    if dates.is_empty() && value == "status" {
+                        return Command::status_from_parser(parser, options);
+                    }
+                    dates.push(value.string()?);
+                }
+                Arg::Short('l') | Arg::Long("location") => {
+                    options.location = Some(parser.value()?.parse::<Location>()?);
+                }
+                Arg::Long("hemisphere") => {
+                    options.hemisphere = Some(parser.value()?.parse::<Hemisphere>()?);
+                }
+                Arg::Long("phase-algo") => {
+                    options.phase_algorithm = Some(parser.value()?.parse::<PhaseAlgorithm>()?);
+                }
+                Arg::Long("eclipses") => options.eclipses = Some(true),
+                Arg::Long("no-eclipses") => options.eclipses = Some(false),
+                Arg::Long("supermoons") => options.supermoons = Some(true),
+                Arg::Long("no-supermoons") => options.supermoons = Some(false),
+                Arg::Long("community-dates") => options.community_dates = Some(true),
+                Arg::Long("no-community-dates") => options.community_dates = Some(false),
+                Arg::Long("calendars") => {
+                    options.calendars = Some(parser.value()?.parse::<OtherCalendars>()?);
+                }
+                Arg::Long("shade") => {
+                    options.shading = Some(parser.value()?.parse::<Shading>()?);
+                }
+                Arg::Long("weeks-before") => {
+                    options.weeks_before = Some(parser.value()?.parse::<usize>()?);
+                }
+                Arg::Long("weeks-after") => {
+                    options.weeks_after = Some(parser.value()?.parse::<usize>()?);
+                }
+                Arg::Long("weeks") => {
+                    options.weeks = Some(parser.value()?.parse::<NonZeroUsize>()?);
+                }
+                Arg::Long("today-jump") => {
+                    options.today_jump = Some(parser.value()?.parse::<TodayJump>()?);
+                }
+                Arg::Long("date-format") => {
+                    options.date_format = Some(parser.value()?.parse::<DateFormat>()?);
+                }
+                Arg::Long("year-numbering") => {
+                    options.year_numbering = Some(parser.value()?.parse::<YearNumbering>()?);
+                }
+                Arg::Long("week-start" | "first-weekday") => {
+                    options.week_start = Some(parser.value()?.parse::<WeekStart>()?);
+                }
+                Arg::Long("spacing") => {
+                    options.spacing = Some(parser.value()?.parse::<WeekSpacing>()?);
+                }
+                Arg::Long("grid") => options.grid = Some(true),
+                Arg::Long("no-grid") => options.grid = Some(false),
+                Arg::Long("month-borders") => {
+                    options.month_borders = Some(parser.value()?.parse::<MonthBorders>()?);
+                }
+                Arg::Long("month-labels") => options.month_labels = Some(true),
+                Arg::Long("no-month-labels") => options.month_labels = Some(false),
+                Arg::Long("sparkline") => options.sparkline = Some(true),
+                Arg::Long("no-sparkline") => options.sparkline = Some(false),
+                Arg::Long("markers") => options.markers = Some(true),
+                Arg::Long("no-markers") => options.markers = Some(false),
+                Arg::Long("phase-labels") => options.phase_labels = Some(true),
+                Arg::Long("no-phase-labels") => options.phase_labels = Some(false),
+                Arg::Long("game-messages") => options.game_messages = Some(true),
+                Arg::Long("no-game-messages") => options.game_messages = Some(false),
+                Arg::Long("view") => options.view = Some(parser.value()?.parse::<View>()?),
+                Arg::Long("list-view") => options.view = Some(View::List),
+                Arg::Long("resume") => options.resume = Some(true),
+                Arg::Long("no-resume") => options.resume = Some(false),
+                Arg::Long("bell") => options.bell = Some(true),
+                Arg::Long("no-bell") => options.bell = Some(false),
+                Arg::Long("reduced-motion") => options.reduced_motion = Some(true),
+                Arg::Long("no-reduced-motion") => options.reduced_motion = Some(false),
+                Arg::Long("confirm-quit") => options.confirm_quit = Some(true),
+                Arg::Long("no-confirm-quit") => options.confirm_quit = Some(false),
+                Arg::Long("tour") => options.tour = Some(true),
+                Arg::Long("no-tour") => options.tour = Some(false),
+                Arg::Long("dashboard") => options.dashboard = Some(true),
+                Arg::Long("no-dashboard") => options.dashboard = Some(false),
+                Arg::Long("compare-algo") => {
+                    options.compare_algo = Some(parser.value()?.parse::<PhaseAlgorithm>()?);
+                }
+                Arg::Long("compare-theme") => {
+                    options.compare_theme = Some(parser.value()?.string()?);
+                }
+                Arg::Long("auto-scroll") => {
+                    options.auto_scroll = Some(parser.value()?.parse::<NonZeroU64>()?);
+                }
+                Arg::Long("script") => options.script = Some(PathBuf::from(parser.value()?)),
+                Arg::Long("command-fifo") => {
+                    options.command_fifo = Some(PathBuf::from(parser.value()?));
+                }
+                Arg::Long("listen") => options.listen = Some(PathBuf::from(parser.value()?)),
+                // Undocumented; for snapshot testing
+                Arg::Long("dump-buffer") => {
+                    options.dump_buffer = Some(parser.value()?.parse::<DumpSpec>()?);
+                }
+                Arg::Long("theme") => options.theme = Some(parser.value()?.string()?),
+                Arg::Long("color-depth") => {
+                    options.color_depth = Some(parser.value()?.parse::<ColorSupport>()?);
+                }
+                Arg::Long("bind") => options.bindings.push(parser.value()?.parse::<Binding>()?),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        if check_config {
+            Ok(Command::CheckConfig(options))
+        } else {
+            Ok(Command::Run { dates, options })
+        }
+    }
+
+    fn luck_from_parser(
+        mut parser: Parser,
+        mut options: Options,
+    ) -> Result<Command, lexopt::Error> {
+        let mut date = None;
+        let mut format = LuckFormat::default();
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help),
+                Arg::Long("json") => format = LuckFormat::Json,
+                Arg::Long("format") => format = parser.value()?.parse::<LuckFormat>()?,
+                Arg::Long("config") => {
+                    options.config = Some(ConfigSource::Path(PathBuf::from(parser.value()?)));
+                }
+                Arg::Long("no-config") => options.config = Some(ConfigSource::Disabled),
+                Arg::Value(value) if date.is_none() => date = Some(value.string()?),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(Command::Luck {
+            date,
+            options,
+            format,
+        })
+    }
+
+    fn status_from_parser(
+        mut parser: Parser,
+        mut options: Options,
+    ) -> Result<Command, lexopt::Error> {
+        let mut date = None;
+        let mut format = StatusFormat::default();
+        let mut glyph = false;
+        let mut only_notable = false;
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help),
+                Arg::Long("format") => format = parser.value()?.parse::<StatusFormat>()?,
+                Arg::Long("glyph") => glyph = true,
+                Arg::Long("only-notable") => only_notable = true,
+                Arg::Long("config") => {
+                    options.config = Some(ConfigSource::Path(PathBuf::from(parser.value()?)));
+                }
+                Arg::Long("no-config") => options.config = Some(ConfigSource::Disabled),
+                Arg::Short('l') | Arg::Long("location") => {
+                    options.location = Some(parser.value()?.parse::<Location>()?);
+                }
+                Arg::Long("hemisphere") => {
+                    options.hemisphere = Some(parser.value()?.parse::<Hemisphere>()?);
+                }
+                Arg::Long("phase-algo") => {
+                    options.phase_algorithm = Some(parser.value()?.parse::<PhaseAlgorithm>()?);
+                }
+                Arg::Value(value) if date.is_none() => date = Some(value.string()?),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(Command::Status {
+            date,
+            options,
+            format,
+            glyph,
+            only_notable,
+        })
+    }
+
+    fn remote_from_parser(
+        mut parser: Parser,
+        mut options: Options,
+        socket: PathBuf,
+    ) -> Result<Command, lexopt::Error> {
+        let mut command = Vec::new();
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('h') | Arg::Long("help") => return Ok(Command::Help),
+                Arg::Long("config") => {
+                    options.config = Some(ConfigSource::Path(PathBuf::from(parser.value()?)));
+                }
+                Arg::Long("no-config") => options.config = Some(ConfigSource::Disabled),
+                Arg::Value(value) => {
+                    // Take the rest of the arguments as is so that dates like
+                    // -0044-03-15 aren't mistaken for options
+                    command.push(value.string()?);
+                    for value in parser.raw_args()? {
+                        command.push(value.string()?);
+                    }
+                }
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(Command::Remote {
+            socket,
+            command,
+            options,
+        })
+    }
+
+    /// Apply options from the `NHMOON_OPTS` environment variable underneath
+    /// those given on the command line
+    fn with_env_defaults(self) -> anyhow::Result<Command> {
+        let Some(value) = std::env::var_os(OPTS_VAR) else {
+            return Ok(self);
+        };
+        let Ok(value) = value.into_string() else {
+            bail!("{OPTS_VAR} is not valid UTF-8");
+        };
+        let args = split_args(&value).with_context(|| format!("failed to parse {OPTS_VAR}"))?;
+        let env_options = match Command::from_parser(Parser::from_args(args))
+            .with_context(|| format!("failed to parse {OPTS_VAR}"))?
+        {
+            Command::Run { dates, options } if dates.is_empty() => options,
+            _ => bail!("{OPTS_VAR} may only contain options"),
+        };
+        Ok(match self {
+            Command::Run { dates, options } => Command::Run {
+                dates,
+                options: options.or(env_options),
+            },
+            Command::Luck {
+                date,
+                options,
+                format,
+            } => Command::Luck {
+                date,
+                options: options.or(env_options),
+                format,
+            },
+            Command::Status {
+                date,
+                options,
+                format,
+                glyph,
+                only_notable,
+            } => Command::Status {
+                date,
+                options: options.or(env_options),
+                format,
+                glyph,
+                only_notable,
+            },
+            Command::Remote {
+                socket,
+                command,
+                options,
+            } => Command::Remote {
+                socket,
+                command,
+                options: options.or(env_options),
+            },
+            Command::CheckConfig(options) => Command::CheckConfig(options.or(env_options)),
+            cmd => cmd,
+        })
+    }
+
+    fn run(self) -> anyhow::Result<()> {
+        match self {
+            Command::Run { dates, options } => {
+                let opts = options.clone().resolve(load_config)?;
+                let dates = dates
+                    .iter()
+                    .map(|s| parse_date(s, &opts))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                // Dumps are rendered the same regardless of the terminal
+                let (capabilities, background) = if opts.dump_buffer.is_some() {
+                    (Capabilities::FULL, Background::Dark)
+                } else {
+                    (Capabilities::detect(), Background::detect())
+                };
+                let colors = capabilities.with_colors(opts.color_depth);
+                let theme_name = opts.theme(background.default_theme());
+                let theme = colors.adapt_theme(load_theme(theme_name)?);
+                let comparison = if opts.compare_algo.is_some() || opts.compare_theme.is_some() {
+                    let theme = match opts.compare_theme {
+                        Some(ref name) => {
+                            Some((name.clone(), colors.adapt_theme(load_theme(name)?)))
+                        }
+                        None => None,
+                    };
+                    Some(Comparison {
+                        phase_algorithm: opts.compare_algo,
+                        theme,
+                        base_theme: theme_name.to_owned(),
+                    })
+                } else {
+                    None
+                };
+                let session = if dates.is_empty() && opts.resume() {
+                    load_session()?
+                } else {
+                    None
+                };
+                let script = match opts.script {
+                    Some(ref path) => load_script(path)?,
+                    None => Vec::new(),
+                };
+                let now = OffsetDateTime::now_local().context("failed to determine local date")?;
+                let highlighter = Highlighter {
+                    settings: Settings::from(&opts),
+                    theme,
+                    offset: now.offset(),
+                    extra: (),
+                };
+                let mut calpager = WeekWindow::new(now.date(), highlighter)
+                    .split(opts.week_split())
+                    .today_jump(opts.today_jump())
+                    .week_start(opts.week_start().0)
+                    .marked(dates);
+                if let Some(session) = session {
+                    calpager = session.restore(calpager);
+                }
+                if let Some(spec) = opts.dump_buffer {
+                    // Don't show the first-run welcome popup, as whether it
+                    // appears would then depend on the state directory
+                    let mut terminal = Terminal::new(TestBackend::new(spec.width, spec.height))
+                        .context("failed to create Terminal object")?;
+                    let buffers = build_app(&opts, &mut terminal, calpager)
+                        .welcome(opts.tour())
+                        .compare(comparison)
+                        .script(script)
+                        .dump(spec.frames)?;
+                    for (i, buffer) in buffers.iter().enumerate() {
+                        if i > 0 {
+                            println!();
+                        }
+                        print!("{}", buffer_text(buffer));
+                    }
+                    return Ok(());
+                }
+                let welcome = !opts.dashboard() && (opts.tour() || is_first_run());
+                let (sender, receiver) = channel();
+                if let Some(path) = opts.command_fifo.clone() {
+                    read_fifo(path, sender.clone());
+                }
+                #[cfg(unix)]
+                let _socket = opts
+                    .listen
+                    .clone()
+                    .map(|path| CommandSocket::listen(path, sender.clone()))
+                    .transpose()?;
+                #[cfg(not(unix))]
+                if opts.listen.is_some() {
+                    bail!("--listen is only supported on Unix");
+                }
+                let commands =
+                    (opts.command_fifo.is_some() || opts.listen.is_some()).then_some(receiver);
+                let action_log = ActionLog::default();
+                let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))
+                    .context("failed to create Terminal object")?;
+                terminal.hide_cursor().context("failed to hide cursor")?;
+                // Generate the weeks before taking over the screen so that
+                // the calendar appears all at once
+                let app = build_app(&opts, &mut terminal, calpager)
+                    .config(opts.config_source())
+                    .overrides(options)
+                    .action_log(action_log.clone())
+                    .capabilities(capabilities)
+                    .background(background)
+                    .welcome(welcome)
+                    .compare(comparison)
+                    .auto_scroll(opts.auto_scroll())
+                    .script(script)
+                    .commands(commands)
+                    .prepare()
+                    .context("failed to determine terminal size")?;
+                let session = with_terminal(action_log, || Ok(app.run()?))?;
+                // Failing to save the session shouldn't turn an otherwise
+                // successful run into a failure
+                if let Err(e) = save_session(&session) {
+                    eprintln!("nhmoon: warning: {:#}", anyhow::Error::new(e));
+                }
+                Ok(())
+            }
+            Command::Luck {
+                date,
+                options,
+                format,
+            } => {
+                let opts = options.resolve(load_config)?;
+                let date = match date {
+                    Some(s) => parse_date(&s, &opts)?,
+                    None => OffsetDateTime::now_local()
+                        .context("failed to determine local date")?
+                        .date(),
+                };
+                let luck = Luck::for_date(date);
+                print!("{}", format.render(luck));
+                Ok(())
+            }
+            Command::Status {
+                date,
+                options,
+                format,
+                glyph,
+                only_notable,
+            } => {
+                let opts = options.resolve(load_config)?;
+                let now = OffsetDateTime::now_local().context("failed to determine local date")?;
+                let date = match date {
+                    Some(s) => parse_date(&s, &opts)?,
+                    None => now.date(),
+                };
+                let phoon = Phoon {
+                    algorithm: opts.phase_algorithm(),
+                    offset: now.offset(),
+                };
+                let status = MoonStatus::new(&phoon, date, opts.hemisphere());
+                if !only_notable || status.is_notable() {
+                    println!("{}", format.render(status, glyph));
+                }
+                Ok(())
+            }
+            Command::Remote {
+                socket,
+                command,
+                options,
+            } => {
+                let opts = options.resolve(load_config)?;
+                let line = command.join(" ");
+                let Some(cmd) = ControlCommand::parse(&line, opts.date_format())? else {
+                    bail!("no command given");
+                };
+                // Screenshots are written by the running calendar, which may
+                // be in a different directory
+                let cmd = match cmd {
+                    ControlCommand::Screenshot(path) if path.is_relative() => {
+                        let cwd = std::env::current_dir()
+                            .context("failed to determine current directory")?;
+                        ControlCommand::Screenshot(cwd.join(path))
+                    }
+                    cmd => cmd,
+                };
+                send_remote(&socket, &cmd)
+            }
+            Command::CheckConfig(options) => {
+                println!("{}", check_config(&options.config_source())?);
+                Ok(())
+            }
+            Command::Help => {
+                println!("Usage: nhmoon [options] [YYYY-MM-DD ...]");
+                println!("       nhmoon luck [--format text|json|markdown] [--config PATH | --no-config] [YYYY-MM-DD]");
+                println!("       nhmoon status [--format waybar|tmux|plain] [--glyph] [--only-notable] [--config PATH | --no-config] [--phase-algo ALGO] [YYYY-MM-DD]");
+                println!("       nhmoon --remote SOCKET [--config PATH | --no-config] COMMAND ...");
+                println!();
+                println!("Scrollable terminal calendar highlighting NetHack's new & full moons");
+                println!();
+                println!("Any dates given are highlighted, and the calendar starts at the first");
+                println!("one; press TAB to cycle between them.");
+                println!();
+                println!("Commands:");
+                println!("  luck              Print the NetHack luck modifiers in effect on the");
+                println!("                    given date [default: today]");
+                println!();
+                println!("  status            Print a one-line summary of the Moon's phase on the");
+                println!("                    given date [default: today] for use in status bars");
+                println!();
+                println!("Options:");
+                println!("  -l, --location LAT,LON");
+                println!("                    Show moonrise & moonset times for the given");
+                println!("                    coordinates in the info popup");
+                println!();
+                println!("  --hemisphere north|south");
+                println!("                    Depict the Moon as seen from the given hemisphere");
+                println!("                    [default: that of --location, else north]");
+                println!();
+                println!("  --phase-algo nethack|conway|meeus");
+                println!("                    Select the algorithm used to compute moon phases");
+                println!("                    [default: nethack]");
+                println!();
+                println!("  --eclipses        Highlight dates of lunar eclipses");
+                println!();
+                println!("  --supermoons      Highlight supermoons & micromoons");
+                println!();
+                println!("  --community-dates");
+                println!("                    Underline dates of NetHack tournaments & releases");
+                println!();
+                println!("  --calendars LIST  Also show the date in the info popup in each of");
+                println!("                    the comma-separated calendars julian, hebrew,");
+                println!("                    islamic, and chinese, or in none [default: julian]");
+                println!();
+                println!("  --date-format FORMAT");
+                println!("                    Show dates in the info popup as the given");
+                println!("                    arrangement of YYYY, MM, and DD, e.g. DD.MM.YYYY;");
+                println!("                    dates on the command line may also be given so");
+                println!("                    [default: YYYY-MM-DD]");
+                println!();
+                println!("  --year-numbering era|astronomical");
+                println!("                    Show years before 1 CE as 1 BCE, 2 BCE, etc., or as");
+                println!("                    0, -1, etc. [default: era]");
+                println!();
+                println!("  --shade off|weeks|months");
+                println!("                    Tint the background of every other week or month");
+                println!("                    [default: off]");
+                println!();
+                println!("  --weeks-before N  Show N weeks before the starting date on launch");
+                println!();
+                println!("  --weeks-after N   Show N weeks after the starting date on launch");
+                println!("                    [default: center the starting date]");
+                println!();
+                println!("  --weeks N         Always show N weeks, however tall the terminal is");
+                println!();
+                println!("  --today-jump center|top|minimal");
+                println!("                    Where the \"today\" action puts today: where the");
+                println!("                    starting date goes, in the top row, or just far");
+                println!("                    enough to be visible [default: center]");
+                println!();
+                println!("  --week-start DAY  Start each week on the given day of the week");
+                println!("                    [default: per the locale, else sunday]");
+                println!();
+                println!("  --first-weekday DAY");
+                println!("                    Same as --week-start");
+                println!();
+                println!("  --spacing uniform|months");
+                println!("                    Put a blank line between every pair of weeks, or");
+                println!("                    only at the borders between months");
+                println!("                    [default: uniform]");
+                println!();
+                println!("  --grid            Draw lines around every day; implies --spacing");
+                println!("                    uniform");
+                println!();
+                println!("  --month-borders all|years|none");
+                println!("                    Draw the borders between every pair of months,");
+                println!("                    only between years, or not at all [default: all]");
+                println!();
+                println!("  --month-labels    Write each month's abbreviated name under its first");
+                println!("                    day");
+                println!();
+                println!("  --sparkline       Draw a bar under each date showing how full the");
+                println!("                    moon is");
+                println!();
+                println!("  --markers         Write a * after each full moon and an o after each");
+                println!("                    new moon");
+                println!();
+                println!("  --phase-labels    Write \"Full Moon\" or \"New Moon\" in the right");
+                println!("                    margin beside weeks containing one");
+                println!();
+                println!("  --game-messages   Quote NetHack's messages for a full or new moon");
+                println!("                    in the info popup & dashboard footer");
+                println!();
+                println!("  --view week|list  Start with the calendar shown as a grid of weeks or");
+                println!("                    as a list of days, one per line, which works");
+                println!("                    better with screen readers [default: week]");
+                println!();
+                println!("  --list-view       Same as --view list");
+                println!();
+                println!(
+                    "  --resume          If no dates are given, reopen the calendar where the"
+                );
+                println!("                    last session left off");
+                println!();
+                println!("  --no-bell         Don't ring the terminal bell on invalid keys or at");
+                println!("                    the ends of time");
+                println!();
+                println!("  --reduced-motion  Scroll by whole weeks at once instead of gliding,");
+                println!("                    don't animate phases on their own, and show");
+                println!("                    messages instead of ringing the bell");
+                println!();
+                println!("  --confirm-quit    Ask before quitting with q or Escape; Ctrl-C still");
+                println!("                    quits at once");
+                println!();
+                println!("  --tour            Show the welcome popup from the first run again");
+                println!();
+                println!("  --dashboard       Keep today centered as the date changes and show a");
+                println!("                    footer with today's NetHack luck, for leaving the");
+                println!("                    calendar running in a status pane");
+                println!();
+                println!("  --compare-algo ALGO");
+                println!("                    Draw a second copy of the calendar to the right");
+                println!("                    using the given phase algorithm, for comparison");
+                println!();
+                println!("  --compare-theme THEME");
+                println!("                    Draw a second copy of the calendar to the right");
+                println!("                    using the given theme, for comparison");
+                println!();
+                println!("  --auto-scroll SECONDS");
+                println!("                    Advance the calendar by a month every SECONDS");
+                println!("                    seconds, pausing for a minute after each keypress");
+                println!();
+                println!("  --script FILE     Perform the actions listed in the given file, one");
+                println!("                    per line, on startup");
+                println!();
+                println!("  --command-fifo PATH");
+                println!("                    While running, read commands (action names,");
+                println!("                    \"jump DATE\", or \"screenshot FILE\") from the");
+                println!("                    given FIFO, one per line");
+                println!();
+                println!("  --listen SOCKET   While running, accept commands like those for");
+                println!("                    --command-fifo on the given Unix socket, sent");
+                println!("                    with \"nhmoon --remote SOCKET COMMAND ...\"");
+                println!();
+                println!("  --theme NAME|PATH Color the calendar using the given built-in theme");
+                println!("                    (default, light, or mono) or theme file; NAME is");
+                println!("                    first looked up as");
+                println!("                    $XDG_CONFIG_HOME/nhmoon/themes/NAME.toml");
+                println!("                    [default: light if the terminal reports a light");
+                println!("                    background, else default]");
+                println!();
+                println!("  --color-depth 16|256|truecolor");
+                println!("                    Adapt the theme's colors to the given number of");
+                println!("                    colors [default: detected from $COLORTERM & $TERM]");
+                println!();
+                println!(
+                    "  --bind KEY=ACTION Bind the given key to the given action (or \"none\")."
+                );
+                println!("                    KEY may also be a chord of two keys, like \"g m\".");
+                println!("                    May be given multiple times");
+                println!();
+                println!("  --config PATH     Read settings from the given config file [default:");
+                println!("                    $XDG_CONFIG_HOME/nhmoon/config.toml]");
+                println!();
+                println!("  --no-config       Do not read any config file");
+                println!();
+                println!("  --check-config    Validate the config file and exit");
+                println!();
+                println!("  --format FORMAT   Output `luck` information as text, json, or");
+                println!("                    markdown [default: text], or `status` information");
+                println!("                    as waybar, tmux, or plain [default: waybar]");
+                println!();
+                println!("  --glyph           Show moons as emoji in `status --format plain`");
+                println!();
+                println!(
+                    "  --only-notable    Make `status` output nothing except during a full or"
+                );
+                println!("                    new moon");
+                println!();
+                println!("  --json            Same as `--format json` for `luck`");
+                println!();
+                println!("  -h, --help        Display this help message and exit");
+                println!("  -V, --version     Show the program version and exit");
+                println!();
+                println!("Default options can also be set in the {OPTS_VAR} environment variable");
+                println!("and in the config file.  Options on the command line take precedence");
+                println!("over those in {OPTS_VAR}, which take precedence over the config file.");
+                println!("Each on/off option can be turned off again with a \"--no-\" prefix");
+                println!("(e.g., --no-eclipses), and --bell undoes --no-bell.");
+                Ok(())
+            }
+            Command::Version => {
+                println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Parse a date given on the command line, which may be in either
+/// `YYYY-MM-DD` format or the configured date format
+fn parse_date(s: &str, opts: &Options) -> anyhow::Result<Date> {
+    let format = opts.date_format();
+    if let Some(date) = DateFormat::default().parse(s).or_else(|| format.parse(s)) {
+        return Ok(date);
+    }
+    if DateFormat::default().is_out_of_range(s) || format.is_out_of_range(s) {
+        let numbering = opts.year_numbering();
+        bail!(
+            "date {s:?} is outside of the supported range of {} through {}",
+            format.format(Date::MIN, numbering),
+            format.format(Date::MAX, numbering)
+        );
+    }
+    if format == DateFormat::default() {
+        bail!("invalid date {s:?}: expected {format}");
+    }
+    bail!("invalid date {s:?}: expected YYYY-MM-DD or {format}")
+}
+
+/// Create an `App` for `calpager` with the display settings & key bindings in
+/// `opts`.  Settings that only apply to interactive runs are left to the
+/// caller.
+fn build_app<'a, B: Backend + Bell + Clipboard>(
+    opts: &Options,
+    terminal: &'a mut Terminal<B>,
+    calpager: WeekWindow<Highlighter>,
+) -> App<'a, B> {
+    App::new(terminal, calpager)
+        .location(opts.location)
+        .calendars(opts.calendars())
+        .date_format(opts.date_format())
+        .year_numbering(opts.year_numbering())
+        .keymap(opts.keymap())
+        .spacing(opts.spacing())
+        .weeks(opts.weeks)
+        .grid(opts.grid())
+        .month_borders(opts.month_borders())
+        .month_labels(opts.month_labels())
+        .sparkline(opts.sparkline())
+        .markers(opts.markers())
+        .phase_labels(opts.phase_labels())
+        .game_messages(opts.game_messages())
+        .view(opts.view())
+        .feedback(opts.feedback())
+        .reduced_motion(opts.reduced_motion())
+        .confirm_quit(opts.confirm_quit())
+}
+
+#[cfg(unix)]
+fn send_remote(socket: &Path, cmd: &ControlCommand) -> anyhow::Result<()> {
+    send_command(socket, cmd)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send_remote(_socket: &Path, _cmd: &ControlCommand) -> anyhow::Result<()> {
+    bail!("--remote is only supported on Unix")
+}
+
+/// Run the `nhmoon` command with the arguments given to the current process
+pub fn main() -> anyhow::Result<()> {
+    Command::from_parser(Parser::from_env())?
+        .with_env_defaults()?
+        .run()
+}
+
+/// Take over the screen, run `func`, and then restore the screen.  If `func`
+/// panics, the screen is restored and a crash report including the actions
+/// in `log` is saved.
+fn with_terminal<F, T>(log: ActionLog, func: F) -> anyhow::Result<T>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    let mut stream = io::stdout();
+    execute!(stream, EnterAlternateScreen).context("failed to start alternate screen")?;
+    if let Err(e) = enable_raw_mode() {
+        let _ = execute!(stream, LeaveAlternateScreen);
+        return Err(e).context("failed to enable raw terminal mode");
+    }
+
+    let original_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic| {
+        let _ = reset_terminal();
+        original_hook(panic);
+        let backtrace = Backtrace::force_capture();
+        let report = crash_report(&panic.to_string(), &log.recent(), &backtrace);
+        match save_crash_report(&report) {
+            Ok(path) => eprintln!("nhmoon: crash report saved to {}", path.display()),
+            Err(e) => eprintln!("nhmoon: warning: {:#}", anyhow::Error::new(e)),
+        }
+    }));
+
+    let r = func();
+    reset_terminal().context("failed to reset terminal")?;
+    r
+}
+
+fn reset_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen)?;
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! A bundled table of dates of note to the NetHack community
+use crate::calendar::DateStyler;
+use ratatui::style::Style;
+use time::{Date, Month};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Event {
+    /// An event that runs every year between two dates (inclusive), starting
+    /// in `since` and (if set) ending in `until`
+    Annual {
+        name: &'static str,
+        start: (Month, u8),
+        end: (Month, u8),
+        since: i32,
+        until: Option<i32>,
+    },
+    /// The release of a version of NetHack and its anniversaries
+    Release { version: &'static str, date: Date },
+}
+
+static EVENTS: &[Event] = &[
+    Event::Annual {
+        name: "/dev/null/nethack tournament",
+        start: (Month::November, 1),
+        end: (Month::November, 30),
+        since: 1999,
+        until: Some(2016),
+    },
+    Event::Annual {
+        name: "The November NetHack Tournament",
+        start: (Month::November, 1),
+        end: (Month::November, 30),
+        since: 2018,
+        until: None,
+    },
+    Event::Annual {
+        name: "Junethack",
+        start: (Month::June, 1),
+        end: (Month::June, 30),
+        since: 2011,
+        until: None,
+    },
+    Event::Release {
+        version: "1.3d",
+        date: time::macros::date!(1987 - 07 - 28),
+    },
+    Event::Release {
+        version: "3.0.0",
+        date: time::macros::date!(1989 - 07 - 24),
+    },
+    Event::Release {
+        version: "3.4.3",
+        date: time::macros::date!(2003 - 12 - 08),
+    },
+    Event::Release {
+        version: "3.6.0",
+        date: time::macros::date!(2015 - 12 - 07),
+    },
+    Event::Release {
+        version: "3.6.7",
+        date: time::macros::date!(2023 - 02 - 16),
+    },
+];
+
+impl Event {
+    /// If the event takes place on `date`, return a description of it
+    fn on(&self, date: Date) -> Option<String> {
+        match *self {
+            Event::Annual {
+                name,
+                start,
+                end,
+                since,
+                until,
+            } => {
+                let year = date.year();
+                let key = |(month, day): (Month, u8)| (u8::from(month), day);
+                let md = key((date.month(), date.day()));
+                (year >= since
+                    && until.map_or(true, |y| year <= y)
+                    && key(start) <= md
+                    && md <= key(end))
+                .then(|| name.to_owned())
+            }
+            Event::Release {
+                version,
+                date: released,
+            } => {
+                if date == released {
+                    Some(format!("NetHack {version} released"))
+                } else if date.month() == released.month()
+                    && date.day() == released.day()
+                    && date.year() > released.year()
+                {
+                    let years = date.year() - released.year();
+                    Some(format!(
+                        "NetHack {version} release, {}",
+                        ordinal_anniversary(years)
+                    ))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+fn ordinal_anniversary(years: i32) -> String {
+    let suffix = match (years % 10, years % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{years}{suffix} anniversary")
+}
+
+/// Returns descriptions of all community events taking place on `date`
+pub(crate) fn community_events_on(date: Date) -> Vec<String> {
+    EVENTS.iter().filter_map(|ev| ev.on(date)).collect()
+}
+
+/// A `DateStyler` that applies the given style to dates of NetHack community
+/// events
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct CommunityDates(pub(crate) Style);
+
+impl DateStyler for CommunityDates {
+    fn date_style(&self, date: Date) -> Style {
+        if EVENTS.iter().any(|ev| ev.on(date).is_some()) {
+            self.0
+        } else {
+            Style::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    #[test]
+    fn test_community_events_on() {
+        assert_eq!(
+            community_events_on(date!(2010 - 11 - 15)),
+            ["/dev/null/nethack tournament"]
+        );
+        assert_eq!(
+            community_events_on(date!(2017 - 11 - 15)),
+            Vec::<String>::new()
+        );
+        assert_eq!(community_events_on(date!(2023 - 06 - 30)), ["Junethack"]);
+        assert_eq!(
+            community_events_on(date!(2015 - 12 - 07)),
+            ["NetHack 3.6.0 released"]
+        );
+        assert_eq!(
+            community_events_on(date!(2023 - 12 - 08)),
+            ["NetHack 3.4.3 release, 20th anniversary"]
+        );
+        assert_eq!(
+            community_events_on(date!(2014 - 12 - 07)),
+            Vec::<String>::new()
+        );
+    }
+}
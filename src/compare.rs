@@ -0,0 +1,82 @@
+//! Comparison mode: the calendar drawn twice side by side, the copy on the
+//! right with a different phase algorithm and/or theme, for spotting where
+//! they differ
+use crate::moon::PhaseAlgorithm;
+use crate::settings::Highlighter;
+use crate::theme::Theme;
+
+/// What to change in the copy of the calendar drawn on the right
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Comparison {
+    pub(crate) phase_algorithm: Option<PhaseAlgorithm>,
+    /// The name of the theme to use on the right and the theme itself
+    pub(crate) theme: Option<(String, Theme)>,
+    /// The name of the theme used on the left
+    pub(crate) base_theme: String,
+}
+
+impl Comparison {
+    /// Returns the styler for the copy of the calendar on the right, given
+    /// the one used on the left
+    pub(crate) fn apply<X: Clone>(&self, highlighter: &Highlighter<X>) -> Highlighter<X> {
+        let mut highlighter = highlighter.clone();
+        if let Some(algorithm) = self.phase_algorithm {
+            highlighter.settings.phase_algorithm = algorithm;
+        }
+        if let Some((_, theme)) = &self.theme {
+            highlighter.theme = *theme;
+        }
+        highlighter
+    }
+
+    /// Returns the labels to show above the left & right copies of the
+    /// calendar, naming the settings that differ between them
+    pub(crate) fn labels<X>(&self, highlighter: &Highlighter<X>) -> (String, String) {
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        if let Some(algorithm) = self.phase_algorithm {
+            left.push(format!(
+                "phase-algo: {}",
+                highlighter.settings.phase_algorithm.name()
+            ));
+            right.push(format!("phase-algo: {}", algorithm.name()));
+        }
+        if let Some((name, _)) = &self.theme {
+            left.push(format!("theme: {}", self.base_theme));
+            right.push(format!("theme: {name}"));
+        }
+        (left.join(", "), right.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use time::UtcOffset;
+
+    #[test]
+    fn test_comparison() {
+        let left = Highlighter {
+            settings: Settings::default(),
+            theme: Theme::default(),
+            offset: UtcOffset::UTC,
+            extra: (),
+        };
+        let comparison = Comparison {
+            phase_algorithm: Some(PhaseAlgorithm::Meeus),
+            theme: Some((String::from("mono"), Theme::builtin("mono").unwrap())),
+            base_theme: String::from("default"),
+        };
+        let right = comparison.apply(&left);
+        assert_eq!(right.settings.phase_algorithm, PhaseAlgorithm::Meeus);
+        assert_eq!(right.theme, Theme::builtin("mono").unwrap());
+        assert_eq!(
+            comparison.labels(&left),
+            (
+                String::from("phase-algo: nethack, theme: default"),
+                String::from("phase-algo: meeus, theme: mono")
+            )
+        );
+    }
+}
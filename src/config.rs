@@ -0,0 +1,585 @@
+//! Loading of settings from the configuration file
+use crate::dirs;
+use crate::feedback::Alert;
+use crate::keymap::Binding;
+use crate::options::Options;
+use crate::theme::{load_theme, ThemeError};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+use thiserror::Error;
+use toml::{Spanned, Value};
+
+/// Which configuration file to read settings from
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) enum ConfigSource {
+    /// The default configuration file, if it exists
+    #[default]
+    Default,
+    /// A file given by the user, which must exist
+    Path(PathBuf),
+    /// No configuration file (`--no-config`)
+    Disabled,
+}
+
+impl ConfigSource {
+    /// Returns the path to the configuration file (if any) and whether it is
+    /// required to exist
+    fn path(&self) -> Option<(PathBuf, bool)> {
+        match self {
+            ConfigSource::Default => dirs::config_file().map(|p| (p, false)),
+            ConfigSource::Path(p) => Some((p.clone(), true)),
+            ConfigSource::Disabled => None,
+        }
+    }
+}
+
+/// Load settings from the configuration file given by `source`.  It is not an
+/// error for the default configuration file to not exist.
+pub(crate) fn load_config(source: &ConfigSource) -> Result<Options, ConfigError> {
+    let Some((path, required)) = source.path() else {
+        return Ok(Options::default());
+    };
+    let src = match fs::read_to_string(&path) {
+        Ok(src) => src,
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !required => return Ok(Options::default()),
+        Err(source) => return Err(ConfigError::Read { path, source }),
+    };
+    parse_config(&src).map_err(|errors| ConfigError::Invalid { path, errors })
+}
+
+/// Validate the configuration file given by `source`, returning a summary of
+/// the result on success
+pub(crate) fn check_config(source: &ConfigSource) -> Result<String, ConfigError> {
+    let Some((path, required)) = source.path() else {
+        return Ok(String::from("No config file in use"));
+    };
+    if !required && !path.exists() {
+        return Ok(format!("{}: not found; using defaults", path.display()));
+    }
+    let opts = load_config(source)?;
+    if let Some(name) = &opts.theme {
+        load_theme(name)?;
+    }
+    Ok(format!(
+        "{}: OK ({} setting{}, {} key binding{})",
+        path.display(),
+        opts.count_settings(),
+        if opts.count_settings() == 1 { "" } else { "s" },
+        opts.bindings.len(),
+        if opts.bindings.len() == 1 { "" } else { "s" },
+    ))
+}
+
+/// Set the given top-level keys in the configuration file given by `source`,
+/// creating the file if necessary.  Only the existing values of the keys are
+/// replaced, so comments and formatting are left as-is.  Returns the path to
+/// the file.
+pub(crate) fn save_config(
+    source: &ConfigSource,
+    values: &[(&str, Value)],
+) -> Result<PathBuf, ConfigError> {
+    let path = match source {
+        ConfigSource::Disabled => return Err(ConfigError::Disabled),
+        source => source.path().ok_or(ConfigError::NoPath)?.0,
+    };
+    let src = match fs::read_to_string(&path) {
+        Ok(src) => src,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(source) => return Err(ConfigError::Read { path, source }),
+    };
+    let updated = update_config(&src, values).map_err(|e| ConfigError::Invalid {
+        path: path.clone(),
+        errors: vec![e],
+    })?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|source| ConfigError::Write {
+            path: path.clone(),
+            source,
+        })?;
+    }
+    match fs::write(&path, updated) {
+        Ok(()) => Ok(path),
+        Err(source) => Err(ConfigError::Write { path, source }),
+    }
+}
+
+fn update_config(src: &str, values: &[(&str, Value)]) -> Result<String, ParseError> {
+    let mut src = src.to_owned();
+    if !src.is_empty() && !src.ends_with('\n') {
+        src.push('\n');
+    }
+    let table = toml::from_str::<BTreeMap<String, Spanned<Value>>>(&src)
+        .map_err(|e| ParseError::from_toml(&src, &e))?;
+    // The span of the value of each top-level `key = value` pair, which may
+    // cover several lines
+    let spans = table
+        .iter()
+        .filter(|(_, value)| !value.get_ref().is_table())
+        .map(|(key, value)| (key.as_str(), value.span()))
+        .collect::<BTreeMap<_, _>>();
+    // New keys are inserted after the line on which the last top-level value
+    // ends, or else before the first table header, so that they remain
+    // top-level
+    let insert_at = if let Some(end) = spans.values().map(|span| span.end).max() {
+        src[end..].find('\n').map_or(src.len(), |i| end + i + 1)
+    } else {
+        let mut offset = 0;
+        for line in src.split_inclusive('\n') {
+            if line.trim_start().starts_with('[') {
+                break;
+            }
+            offset += line.len();
+        }
+        offset
+    };
+    let mut edits = Vec::new();
+    let mut added = String::new();
+    for (key, value) in values {
+        if let Some(span) = spans.get(key) {
+            edits.push((span.clone(), value.to_string()));
+        } else {
+            let _ = writeln!(added, "{key} = {value}");
+        }
+    }
+    edits.push((insert_at..insert_at, added));
+    edits.sort_by_key(|(span, _)| span.start);
+    let mut out = String::with_capacity(src.len());
+    let mut pos = 0;
+    for (span, text) in edits {
+        out.push_str(&src[pos..span.start]);
+        out.push_str(&text);
+        pos = span.end;
+    }
+    out.push_str(&src[pos..]);
+    Ok(out)
+}
+
+/// Detects changes to the configuration file by checking its modification
+/// time and, on Unix, by listening for `SIGHUP`
+#[derive(Clone, Debug)]
+pub(crate) struct ConfigWatcher {
+    source: ConfigSource,
+    mtime: Option<SystemTime>,
+    hangup: Arc<AtomicBool>,
+}
+
+impl ConfigWatcher {
+    pub(crate) fn new(source: ConfigSource) -> ConfigWatcher {
+        let hangup = Arc::new(AtomicBool::new(false));
+        #[cfg(unix)]
+        {
+            // If the handler can't be installed, we still have mtime checks
+            let _ = signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&hangup));
+        }
+        let mut watcher = ConfigWatcher {
+            source,
+            mtime: None,
+            hangup,
+        };
+        watcher.mark_seen();
+        watcher
+    }
+
+    /// Record the current state of the file as already loaded, e.g., after
+    /// writing to it
+    pub(crate) fn mark_seen(&mut self) {
+        self.mtime = self.current_mtime();
+    }
+
+    /// If the file has been created, modified, or deleted since it was last
+    /// seen, or if `SIGHUP` was received, reload & return its settings
+    pub(crate) fn poll(&mut self) -> Option<Result<Options, ConfigError>> {
+        if self.source == ConfigSource::Disabled {
+            return None;
+        }
+        let mtime = self.current_mtime();
+        let hangup = self.hangup.swap(false, Ordering::Relaxed);
+        if mtime == self.mtime && !hangup {
+            return None;
+        }
+        self.mtime = mtime;
+        Some(load_config(&self.source))
+    }
+
+    fn current_mtime(&self) -> Option<SystemTime> {
+        let (path, _) = self.source.path()?;
+        fs::metadata(path).and_then(|md| md.modified()).ok()
+    }
+}
+
+/// The contents of a configuration file.  Keys are the same as the names of
+/// the corresponding long command-line options.  Settings parsed with
+/// `FromStr` are kept as spanned strings so that errors can give their lines.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+    location: Option<Spanned<String>>,
+    hemisphere: Option<Spanned<String>>,
+    phase_algo: Option<Spanned<String>>,
+    eclipses: Option<bool>,
+    supermoons: Option<bool>,
+    community_dates: Option<bool>,
+    calendars: Option<Spanned<String>>,
+    date_format: Option<Spanned<String>>,
+    year_numbering: Option<Spanned<String>>,
+    shade: Option<Spanned<String>>,
+    weeks_before: Option<usize>,
+    weeks_after: Option<usize>,
+    weeks: Option<NonZeroUsize>,
+    today_jump: Option<Spanned<String>>,
+    #[serde(alias = "first-weekday")]
+    week_start: Option<Spanned<String>>,
+    spacing: Option<Spanned<String>>,
+    month_borders: Option<Spanned<String>>,
+    grid: Option<bool>,
+    month_labels: Option<bool>,
+    sparkline: Option<bool>,
+    markers: Option<bool>,
+    phase_labels: Option<bool>,
+    game_messages: Option<bool>,
+    view: Option<Spanned<String>>,
+    resume: Option<bool>,
+    bell: Option<bool>,
+    reduced_motion: Option<bool>,
+    confirm_quit: Option<bool>,
+    theme: Option<String>,
+    color_depth: Option<Spanned<String>>,
+    keys: BTreeMap<String, Spanned<String>>,
+    feedback: BTreeMap<String, Spanned<String>>,
+}
+
+/// Parse the contents of a configuration file.  A file that isn't valid TOML
+/// or that has a value of the wrong type is reported with a single error;
+/// otherwise, every invalid value is reported.
+fn parse_config(src: &str) -> Result<Options, Vec<ParseError>> {
+    let file =
+        toml::from_str::<ConfigFile>(src).map_err(|e| vec![ParseError::from_toml(src, &e)])?;
+    let mut values = Values {
+        src,
+        errors: Vec::new(),
+    };
+    let mut opts = Options {
+        location: values.parse("location", file.location),
+        hemisphere: values.parse("hemisphere", file.hemisphere),
+        phase_algorithm: values.parse("phase-algo", file.phase_algo),
+        eclipses: file.eclipses,
+        supermoons: file.supermoons,
+        community_dates: file.community_dates,
+        calendars: values.parse("calendars", file.calendars),
+        date_format: values.parse("date-format", file.date_format),
+        year_numbering: values.parse("year-numbering", file.year_numbering),
+        shading: values.parse("shade", file.shade),
+        weeks_before: file.weeks_before,
+        weeks_after: file.weeks_after,
+        weeks: file.weeks,
+        today_jump: values.parse("today-jump", file.today_jump),
+        week_start: values.parse("week-start", file.week_start),
+        spacing: values.parse("spacing", file.spacing),
+        grid: file.grid,
+        month_borders: values.parse("month-borders", file.month_borders),
+        month_labels: file.month_labels,
+        sparkline: file.sparkline,
+        markers: file.markers,
+        phase_labels: file.phase_labels,
+        game_messages: file.game_messages,
+        view: values.parse("view", file.view),
+        resume: file.resume,
+        bell: file.bell,
+        reduced_motion: file.reduced_motion,
+        confirm_quit: file.confirm_quit,
+        theme: file.theme,
+        color_depth: values.parse("color-depth", file.color_depth),
+        ..Options::default()
+    };
+    for (key, action) in &file.keys {
+        match Binding::new(key, action.get_ref()) {
+            Ok(binding) => opts.bindings.push(binding),
+            Err(e) => values.error(action, e.to_string()),
+        }
+    }
+    for (key, feedback) in file.feedback {
+        match key.parse::<Alert>() {
+            Ok(alert) => {
+                if let Some(feedback) = values.parse(&key, Some(feedback)) {
+                    opts.feedback.push((alert, feedback));
+                }
+            }
+            Err(e) => values.error(&feedback, e.to_string()),
+        }
+    }
+    if values.errors.is_empty() {
+        Ok(opts)
+    } else {
+        values.errors.sort_by_key(|e| e.line);
+        Err(values.errors)
+    }
+}
+
+/// Parses string values from the file at `src`, collecting any errors
+struct Values<'a> {
+    src: &'a str,
+    errors: Vec<ParseError>,
+}
+
+impl Values<'_> {
+    /// Parse the string `value` for `key`, if set, as a `T`
+    fn parse<T>(&mut self, key: &str, value: Option<Spanned<String>>) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = value?;
+        match value.get_ref().parse::<T>() {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.error(&value, format!("invalid value for {key:?}: {e}"));
+                None
+            }
+        }
+    }
+
+    fn error<T>(&mut self, value: &Spanned<T>, msg: String) {
+        self.errors
+            .push(ParseError::at(self.src, value.span().start, msg));
+    }
+}
+
+/// Returns the one-based number of the line of `src` containing byte `offset`
+fn line_at(src: &str, offset: usize) -> usize {
+    src.get(..offset).unwrap_or(src).matches('\n').count() + 1
+}
+
+/// An error in a configuration, theme, session, or script file
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+#[error("line {line}: {msg}")]
+pub(crate) struct ParseError {
+    /// One-based line number at which the error occurs
+    pub(crate) line: usize,
+    pub(crate) msg: String,
+}
+
+impl ParseError {
+    /// An error in the text of `src` at byte `offset`
+    pub(crate) fn at(src: &str, offset: usize, msg: String) -> ParseError {
+        ParseError {
+            line: line_at(src, offset),
+            msg,
+        }
+    }
+
+    /// Convert an error from deserializing `src` with the `toml` crate
+    pub(crate) fn from_toml(src: &str, e: &toml::de::Error) -> ParseError {
+        let offset = e.span().map_or(0, |span| span.start);
+        ParseError::at(src, offset, e.message().to_owned())
+    }
+}
+
+/// Format a list of errors for display after a colon, either on the same
+/// line (if there is only one) or as an indented list
+pub(crate) fn show_errors(errors: &[ParseError]) -> String {
+    match errors {
+        [e] => format!(" {e}"),
+        errors => errors.iter().map(|e| format!("\n    {e}")).collect(),
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum ConfigError {
+    #[error("failed to read config file {}", .path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to write config file {}", .path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("could not determine location of config file")]
+    NoPath,
+    #[error("config file is disabled by --no-config")]
+    Disabled,
+    #[error(transparent)]
+    Theme(#[from] ThemeError),
+    #[error("invalid config file {}:{}", .path.display(), show_errors(.errors))]
+    Invalid {
+        path: PathBuf,
+        errors: Vec<ParseError>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::astro::Location;
+    use crate::day_list::View;
+    use crate::feedback::Feedback;
+    use crate::keymap::Action;
+    use crate::moon::{Hemisphere, PhaseAlgorithm};
+    use crate::term::ColorSupport;
+    use crossterm::event::KeyCode;
+
+    #[test]
+    fn test_parse_config() {
+        let src = concat!(
+            "# Where I live\n",
+            "location = \"-33.87,151.21\"\n",
+            "phase-algo = \"meeus\"\n",
+            "eclipses = true\n",
+            "supermoons = false\n",
+            "color-depth = \"256\"\n",
+        );
+        let opts = parse_config(src).unwrap();
+        assert_eq!(
+            opts,
+            Options {
+                location: Some(Location {
+                    latitude: -33.87,
+                    longitude: 151.21
+                }),
+                phase_algorithm: Some(PhaseAlgorithm::Meeus),
+                eclipses: Some(true),
+                supermoons: Some(false),
+                color_depth: Some(ColorSupport::Indexed),
+                ..Options::default()
+            }
+        );
+        assert_eq!(opts.hemisphere(), Hemisphere::South);
+    }
+
+    #[test]
+    fn test_parse_config_keys() {
+        let src = "eclipses = true\n\n[keys]\nspace = \"page-down\"\nq = \"none\"\n";
+        let keymap = parse_config(src).unwrap().keymap();
+        assert_eq!(keymap.get(KeyCode::Char(' ')), Some(Action::PageDown));
+        assert_eq!(keymap.get(KeyCode::Char('q')), None);
+        let e = &parse_config("[keys]\nx = \"explode\"\n").unwrap_err()[0];
+        assert_eq!(e.line, 2);
+        assert_eq!(e.msg, "unknown action \"explode\"");
+    }
+
+    #[test]
+    fn test_parse_config_feedback() {
+        let src = "bell = false\n\n[feedback]\nend-of-time = \"flash\"\ninvalid-key = \"bell\"\n";
+        let feedback = parse_config(src).unwrap().feedback();
+        assert_eq!(feedback.get(Alert::EndOfTime), Feedback::Flash);
+        assert_eq!(feedback.get(Alert::InvalidKey), Feedback::None);
+        assert_eq!(feedback.get(Alert::FailedJump), Feedback::None);
+        let e = &parse_config("[feedback]\ntypo = \"bell\"\n").unwrap_err()[0];
+        assert_eq!(e.line, 2);
+        assert!(e.msg.starts_with("feedback condition must be"));
+    }
+
+    #[test]
+    fn test_parse_config_view() {
+        assert_eq!(
+            parse_config("view = \"List\"\n").unwrap().view(),
+            View::List
+        );
+        let e = &parse_config("view = \"month\"\n").unwrap_err()[0];
+        assert_eq!(
+            e.msg,
+            "invalid value for \"view\": view must be \"week\" or \"list\""
+        );
+    }
+
+    #[test]
+    fn test_parse_config_errors() {
+        let e = &parse_config("\neclipses = \"yes\"\n").unwrap_err()[0];
+        assert_eq!(e.line, 2);
+        assert_eq!(e.msg, "invalid type: string \"yes\", expected a boolean");
+        let e = &parse_config("\nhemisphere = \"east\"\n").unwrap_err()[0];
+        assert_eq!(e.line, 2);
+        assert!(e.msg.starts_with("invalid value for \"hemisphere\""));
+        let e = &parse_config("eclipses = true\nmoons = 2\n").unwrap_err()[0];
+        assert_eq!(e.line, 2);
+        assert!(e.msg.starts_with("unknown field `moons`"), "{}", e.msg);
+        let e = &parse_config("eclipses = true\nsupermoons = \n").unwrap_err()[0];
+        assert_eq!(e.line, 2);
+    }
+
+    #[test]
+    fn test_parse_config_all_errors() {
+        let src =
+            "hemisphere = \"east\"\neclipses = true\nshade = \"plaid\"\n[keys]\nx = \"explode\"\n";
+        let errors = parse_config(src).unwrap_err();
+        assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), [1, 3, 5]);
+        let e = ConfigError::Invalid {
+            path: PathBuf::from("config.toml"),
+            errors,
+        };
+        assert_eq!(
+            e.to_string(),
+            concat!(
+                "invalid config file config.toml:\n",
+                "    line 1: invalid value for \"hemisphere\": ",
+                "hemisphere must be \"north\" or \"south\"\n",
+                "    line 3: invalid value for \"shade\": ",
+                "shading must be \"off\", \"weeks\", or \"months\"\n",
+                "    line 5: unknown action \"explode\"",
+            )
+        );
+    }
+
+    #[test]
+    fn test_update_config() {
+        let src = concat!(
+            "# My settings\n",
+            "eclipses = false  # for now\n",
+            "location = \"\"\"\n",
+            "40.7,-74.0\"\"\"  # home\n",
+            "\n",
+            "[keys]\n",
+            "q = \"quit\"\n",
+        );
+        let updated = update_config(
+            src,
+            &[
+                ("eclipses", Value::Boolean(true)),
+                ("phase-algo", Value::String("meeus".into())),
+                ("location", Value::String("51.5,-0.1".into())),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            concat!(
+                "# My settings\n",
+                "eclipses = true  # for now\n",
+                "location = \"51.5,-0.1\"  # home\n",
+                "phase-algo = \"meeus\"\n",
+                "\n",
+                "[keys]\n",
+                "q = \"quit\"\n",
+            )
+        );
+        let opts = parse_config(&updated).unwrap();
+        assert_eq!(opts.eclipses, Some(true));
+        assert_eq!(opts.phase_algorithm, Some(PhaseAlgorithm::Meeus));
+        assert_eq!(opts.location, "51.5,-0.1".parse().ok());
+        assert_eq!(opts.keymap().get(KeyCode::Char('q')), Some(Action::Quit));
+        assert_eq!(
+            update_config("", &[("supermoons", Value::Boolean(false))]).unwrap(),
+            "supermoons = false\n"
+        );
+        assert_eq!(
+            update_config(
+                "[keys]\nq = \"quit\"",
+                &[("eclipses", Value::Boolean(true))]
+            )
+            .unwrap(),
+            "eclipses = true\n[keys]\nq = \"quit\"\n"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Commands sent to a running calendar by other programs
+use crate::calendar::YearNumbering;
+use crate::datefmt::DateFormat;
+use crate::keymap::{Action, ParseBindingError};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use thiserror::Error;
+use time::Date;
+
+/// A command for a running calendar
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum ControlCommand {
+    /// Perform an action, as though its key had been pressed
+    Action(Action),
+    /// Position the calendar around a date
+    Jump(Date),
+    /// Write the text currently on screen to a file
+    Screenshot(PathBuf),
+}
+
+impl ControlCommand {
+    /// Parse a command, which is either the name of an action (as used by
+    /// `--bind`), `jump DATE` (with the date in `YYYY-MM-DD` format or in
+    /// `format`), or `screenshot PATH`.  Returns `None` for blank lines and
+    /// lines starting with `#`.
+    pub(crate) fn parse(
+        line: &str,
+        format: DateFormat,
+    ) -> Result<Option<ControlCommand>, ParseCommandError> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let (name, arg) = match line.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, Some(arg.trim_start())),
+            None => (line, None),
+        };
+        let cmd = match (name, arg) {
+            ("jump", Some(arg)) => {
+                let date = DateFormat::default()
+                    .parse(arg)
+                    .or_else(|| format.parse(arg))
+                    .ok_or_else(|| ParseCommandError::Date(arg.to_owned()))?;
+                ControlCommand::Jump(date)
+            }
+            ("screenshot", Some(arg)) => ControlCommand::Screenshot(PathBuf::from(arg)),
+            ("jump" | "screenshot", None) => {
+                return Err(ParseCommandError::MissingArgument(name.to_owned()))
+            }
+            (_, None) => ControlCommand::Action(name.parse::<Action>()?),
+            (_, Some(_)) => return Err(ParseCommandError::Unknown(line.to_owned())),
+        };
+        Ok(Some(cmd))
+    }
+}
+
+impl fmt::Display for ControlCommand {
+    /// Shows the command in a form that `ControlCommand::parse()` accepts
+    /// regardless of the date format
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlCommand::Action(action) => write!(f, "{}", action.name()),
+            ControlCommand::Jump(date) => write!(
+                f,
+                "jump {}",
+                DateFormat::default().format(*date, YearNumbering::Astronomical)
+            ),
+            ControlCommand::Screenshot(path) => write!(f, "screenshot {}", path.display()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub(crate) enum ParseCommandError {
+    #[error("invalid command {0:?}")]
+    Unknown(String),
+    #[error("command {0:?} requires an argument")]
+    MissingArgument(String),
+    #[error("invalid date {0:?}")]
+    Date(String),
+    #[error(transparent)]
+    Action(#[from] ParseBindingError),
+}
+
+/// Start reading lines from the FIFO at `path` in the background and
+/// sending them to `sender`, reopening the FIFO each time all writers have
+/// closed it.  If `path` is not a FIFO, it is only read once.  Errors are sent
+/// along with the lines, after which reading stops.
+pub(crate) fn read_fifo(path: PathBuf, sender: Sender<io::Result<String>>) {
+    std::thread::spawn(move || loop {
+        // Opening a FIFO blocks until something opens it for writing
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                let _ = sender.send(Err(e));
+                return;
+            }
+        };
+        for line in BufReader::new(file).lines() {
+            let failed = line.is_err();
+            if sender.send(line).is_err() || failed {
+                return;
+            }
+        }
+        if !is_fifo(&path) {
+            return;
+        }
+    });
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|md| md.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &Path) -> bool {
+    false
+}
+
+/// A Unix socket on which a running calendar accepts commands sent with
+/// `--remote`.  The socket file is removed when this is dropped.
+#[cfg(unix)]
+#[derive(Debug)]
+pub(crate) struct CommandSocket {
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl CommandSocket {
+    /// Create a socket at `path` and start accepting connections on it in
+    /// the background, sending each line received to `sender`.  A leftover
+    /// socket file that nothing is listening on is replaced.
+    pub(crate) fn listen(
+        path: PathBuf,
+        sender: Sender<io::Result<String>>,
+    ) -> Result<CommandSocket, SocketError> {
+        use std::os::unix::net::{UnixListener, UnixStream};
+        let listener = match UnixListener::bind(&path) {
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+                if UnixStream::connect(&path).is_ok() {
+                    return Err(SocketError::InUse(path));
+                }
+                std::fs::remove_file(&path)
+                    .and_then(|()| UnixListener::bind(&path))
+                    .map_err(|source| SocketError::Bind {
+                        path: path.clone(),
+                        source,
+                    })?
+            }
+            r => r.map_err(|source| SocketError::Bind {
+                path: path.clone(),
+                source,
+            })?,
+        };
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                std::thread::spawn(move || {
+                    // A client that disconnects mid-line loses that line, but
+                    // that's no reason to stop listening
+                    for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                        if sender.send(Ok(line)).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        Ok(CommandSocket { path })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for CommandSocket {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Send a command to the calendar listening on the socket at `path`
+#[cfg(unix)]
+pub(crate) fn send_command(path: &Path, cmd: &ControlCommand) -> Result<(), SocketError> {
+    use std::io::Write;
+    use std::os::unix::net::UnixStream;
+    UnixStream::connect(path)
+        .and_then(|mut stream| writeln!(stream, "{cmd}"))
+        .map_err(|source| SocketError::Send {
+            path: path.to_owned(),
+            source,
+        })
+}
+
+#[cfg(unix)]
+#[derive(Debug, Error)]
+pub(crate) enum SocketError {
+    #[error("failed to listen on {}", .path.display())]
+    Bind {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("another program is already listening on {}", .0.display())]
+    InUse(PathBuf),
+    #[error("failed to send command to {}", .path.display())]
+    Send {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    #[test]
+    fn test_parse_command() {
+        let dmy = "DD.MM.YYYY".parse::<DateFormat>().unwrap();
+        for (line, cmd) in [
+            ("page-down", Some(ControlCommand::Action(Action::PageDown))),
+            (
+                "  jump 2025-06-01 ",
+                Some(ControlCommand::Jump(date!(2025 - 06 - 01))),
+            ),
+            (
+                "jump 01.06.2025",
+                Some(ControlCommand::Jump(date!(2025 - 06 - 01))),
+            ),
+            (
+                "screenshot /tmp/x.txt",
+                Some(ControlCommand::Screenshot(PathBuf::from("/tmp/x.txt"))),
+            ),
+            ("# comment", None),
+            ("", None),
+        ] {
+            assert_eq!(ControlCommand::parse(line, dmy), Ok(cmd), "{line:?}");
+        }
+        for (line, err) in [
+            ("jump", ParseCommandError::MissingArgument("jump".into())),
+            ("jump tomorrow", ParseCommandError::Date("tomorrow".into())),
+            ("help me", ParseCommandError::Unknown("help me".into())),
+            (
+                "explode",
+                ParseCommandError::Action(ParseBindingError::Action("explode".into())),
+            ),
+        ] {
+            assert_eq!(ControlCommand::parse(line, dmy), Err(err), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let dmy = "DD.MM.YYYY".parse::<DateFormat>().unwrap();
+        for cmd in [
+            ControlCommand::Action(Action::NextFullMoon),
+            ControlCommand::Jump(date!(-0044 - 03 - 15)),
+            ControlCommand::Screenshot(PathBuf::from("/tmp/shot.txt")),
+        ] {
+            assert_eq!(
+                ControlCommand::parse(&cmd.to_string(), dmy),
+                Ok(Some(cmd.clone()))
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket() {
+        let path = std::env::temp_dir().join(format!("nhmoon-test-{}.sock", std::process::id()));
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let socket = CommandSocket::listen(path.clone(), sender).unwrap();
+        assert!(matches!(
+            CommandSocket::listen(path.clone(), std::sync::mpsc::channel().0),
+            Err(SocketError::InUse(_))
+        ));
+        let cmd = ControlCommand::Jump(date!(2025 - 06 - 01));
+        send_command(&path, &cmd).unwrap();
+        let line = receiver
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+        assert_eq!(line, "jump 2025-06-01");
+        drop(socket);
+        assert!(!path.exists());
+    }
+}
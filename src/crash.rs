@@ -0,0 +1,101 @@
+//! Reports written when the calendar crashes
+use crate::dirs;
+use crate::keymap::Action;
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+use thiserror::Error;
+use time::OffsetDateTime;
+
+/// Number of the most recent actions included in a crash report
+const RECENT_ACTIONS: usize = 20;
+
+/// The actions most recently performed, shared with the panic hook so that
+/// they can be included in a crash report
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ActionLog(Arc<Mutex<VecDeque<Action>>>);
+
+impl ActionLog {
+    pub(crate) fn push(&self, action: Action) {
+        let mut log = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if log.len() >= RECENT_ACTIONS {
+            log.pop_front();
+        }
+        log.push_back(action);
+    }
+
+    /// Returns the logged actions, oldest first
+    pub(crate) fn recent(&self) -> Vec<Action> {
+        let log = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        log.iter().copied().collect()
+    }
+}
+
+/// Returns the text of a crash report for a panic described by `panic`
+pub(crate) fn crash_report(panic: &str, actions: &[Action], backtrace: &Backtrace) -> String {
+    let mut s = format!("nhmoon {} crashed\n\n{panic}\n", env!("CARGO_PKG_VERSION"));
+    s.push_str("\nRecent actions (oldest first):\n");
+    if actions.is_empty() {
+        s.push_str("    (none)\n");
+    }
+    for action in actions {
+        let _ = writeln!(s, "    {}", action.name());
+    }
+    let _ = write!(s, "\nBacktrace:\n{backtrace}\n");
+    s
+}
+
+/// Write `report` to a new file in the state directory and return its path
+pub(crate) fn save_crash_report(report: &str) -> Result<PathBuf, CrashReportError> {
+    let dir = dirs::state_dir().ok_or(CrashReportError::NoPath)?;
+    let stamp = OffsetDateTime::now_utc().unix_timestamp();
+    let path = dir.join(format!("crash-{stamp}.txt"));
+    match fs::create_dir_all(&dir).and_then(|()| fs::write(&path, report)) {
+        Ok(()) => Ok(path),
+        Err(source) => Err(CrashReportError::Write { path, source }),
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum CrashReportError {
+    #[error("failed to write crash report {}", .path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("could not determine where to write crash report")]
+    NoPath,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_report() {
+        let log = ActionLog::default();
+        for _ in 0..RECENT_ACTIONS {
+            log.push(Action::ScrollDown);
+        }
+        log.push(Action::Info);
+        let actions = log.recent();
+        assert_eq!(actions.len(), RECENT_ACTIONS);
+        assert_eq!(actions.last(), Some(&Action::Info));
+        let report = crash_report(
+            "panicked at src/app.rs:1:1:\noops",
+            &actions[RECENT_ACTIONS - 2..],
+            &Backtrace::disabled(),
+        );
+        assert!(report.contains(concat!(
+            "\n\npanicked at src/app.rs:1:1:\noops\n\n",
+            "Recent actions (oldest first):\n",
+            "    scroll-down\n",
+            "    info\n",
+        )));
+    }
+}
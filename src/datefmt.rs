@@ -0,0 +1,280 @@
+//! User-configurable formatting & parsing of dates
+use crate::calendar::YearNumbering;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+use thiserror::Error;
+use time::{Date, Month};
+
+/// An arrangement of a date's year (`YYYY`), month (`MM`), and day (`DD`)
+/// with a single character between each, like `DD.MM.YYYY`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct DateFormat {
+    fields: [DateField; 3],
+    separators: [char; 2],
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DateField {
+    Year,
+    Month,
+    Day,
+}
+
+impl DateField {
+    fn pattern(self) -> &'static str {
+        match self {
+            DateField::Year => "YYYY",
+            DateField::Month => "MM",
+            DateField::Day => "DD",
+        }
+    }
+}
+
+impl Default for DateFormat {
+    fn default() -> DateFormat {
+        DateFormat {
+            fields: [DateField::Year, DateField::Month, DateField::Day],
+            separators: ['-', '-'],
+        }
+    }
+}
+
+impl DateFormat {
+    pub(crate) fn format(&self, date: Date, numbering: YearNumbering) -> String {
+        self.format_ymd(date.year(), date.month(), date.day(), numbering)
+    }
+
+    /// Format a date given as a year (in astronomical numbering), month, and
+    /// day, which need not be in the Gregorian calendar
+    pub(crate) fn format_ymd(
+        &self,
+        year: i32,
+        month: Month,
+        day: u8,
+        numbering: YearNumbering,
+    ) -> String {
+        let (year, era) = numbering.split(year);
+        let mut s = String::new();
+        for (i, field) in self.fields.into_iter().enumerate() {
+            if let Some(&sep) = i.checked_sub(1).and_then(|j| self.separators.get(j)) {
+                s.push(sep);
+            }
+            // Writing to a String can't fail
+            let _ = match field {
+                DateField::Year if year < 0 => write!(s, "-{:04}", year.unsigned_abs()),
+                DateField::Year => write!(s, "{year:04}"),
+                DateField::Month => write!(s, "{:02}", u8::from(month)),
+                DateField::Day => write!(s, "{day:02}"),
+            };
+        }
+        if let Some(era) = era {
+            s.push(' ');
+            s.push_str(era);
+        }
+        s
+    }
+
+    /// Parse a date in this format, returning `None` if it doesn't match.  A
+    /// date whose year is followed by " BCE" (or " BC") is taken to be in that
+    /// era, regardless of how years are numbered for display.
+    pub(crate) fn parse(&self, s: &str) -> Option<Date> {
+        let (year, month, day) = self.split(s)?;
+        Date::from_calendar_date(
+            i32::try_from(year).ok()?,
+            Month::try_from(u8::try_from(month).ok()?).ok()?,
+            u8::try_from(day).ok()?,
+        )
+        .ok()
+    }
+
+    /// Returns whether `s` is laid out like a date in this format but has a
+    /// year outside the range supported by `Date`
+    pub(crate) fn is_out_of_range(&self, s: &str) -> bool {
+        self.split(s).is_some_and(|(y, _, _)| {
+            y < i64::from(Date::MIN.year()) || y > i64::from(Date::MAX.year())
+        })
+    }
+
+    /// Splits a string laid out like a date in this format into its year (in
+    /// astronomical numbering), month, and day, without checking that they
+    /// form a valid date.  Years must have at least four digits and may be
+    /// signed; months and days must have exactly two digits.
+    fn split(&self, s: &str) -> Option<(i64, i64, i64)> {
+        let (mut rest, bce) = split_era(s);
+        let mut values = [0; 3];
+        for (i, field) in self.fields.into_iter().enumerate() {
+            if let Some(&sep) = i.checked_sub(1).and_then(|j| self.separators.get(j)) {
+                rest = rest.strip_prefix(sep)?;
+            }
+            let sign_len = usize::from(field == DateField::Year && rest.starts_with(['-', '+']));
+            let digits_len = rest[sign_len..]
+                .bytes()
+                .take_while(u8::is_ascii_digit)
+                .count();
+            let valid_len = match field {
+                DateField::Year => digits_len >= 4,
+                DateField::Month | DateField::Day => digits_len == 2,
+            };
+            if !valid_len {
+                return None;
+            }
+            let (value, tail) = rest.split_at(sign_len + digits_len);
+            values[field as usize] = value.parse::<i64>().ok()?;
+            rest = tail;
+        }
+        let [year, month, day] = values;
+        match (rest.is_empty(), bce) {
+            (true, true) if year >= 1 => Some((1 - year, month, day)),
+            (true, false) => Some((year, month, day)),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a " BCE" or " BC" suffix off of a date
+fn split_era(s: &str) -> (&str, bool) {
+    match s.strip_suffix(" BCE").or_else(|| s.strip_suffix(" BC")) {
+        Some(s) => (s, true),
+        None => (s, false),
+    }
+}
+
+impl fmt::Display for DateFormat {
+    /// Shows the format as the pattern it was parsed from
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c] = self.fields.map(DateField::pattern);
+        let [s1, s2] = self.separators;
+        write!(f, "{a}{s1}{b}{s2}{c}")
+    }
+}
+
+impl FromStr for DateFormat {
+    type Err = ParseDateFormatError;
+
+    fn from_str(s: &str) -> Result<DateFormat, ParseDateFormatError> {
+        let mut rest = s;
+        let mut fields = Vec::with_capacity(3);
+        let mut separators = Vec::with_capacity(2);
+        loop {
+            let field = [DateField::Year, DateField::Month, DateField::Day]
+                .into_iter()
+                .find(|f| !fields.contains(f) && rest.starts_with(f.pattern()))
+                .ok_or(ParseDateFormatError)?;
+            fields.push(field);
+            rest = &rest[field.pattern().len()..];
+            let mut chars = rest.chars();
+            match chars.next() {
+                Some(sep) if fields.len() < 3 => {
+                    separators.push(sep);
+                    rest = chars.as_str();
+                }
+                None if fields.len() == 3 => break,
+                _ => return Err(ParseDateFormatError),
+            }
+        }
+        Ok(DateFormat {
+            fields: fields.try_into().map_err(|_| ParseDateFormatError)?,
+            separators: separators.try_into().map_err(|_| ParseDateFormatError)?,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("date format must arrange YYYY, MM, and DD with one character between each, e.g. \"DD.MM.YYYY\"")]
+pub(crate) struct ParseDateFormatError;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    #[test]
+    fn test_format() {
+        let era = YearNumbering::Era;
+        let dmy = "DD.MM.YYYY".parse::<DateFormat>().unwrap();
+        assert_eq!(dmy.format(date!(2024 - 03 - 09), era), "09.03.2024");
+        assert_eq!(dmy.format(date!(-0044 - 03 - 15), era), "15.03.0045 BCE");
+        assert_eq!(
+            dmy.format(date!(-0044 - 03 - 15), YearNumbering::Astronomical),
+            "15.03.-0044"
+        );
+        let mdy = "MM/DD/YYYY".parse::<DateFormat>().unwrap();
+        assert_eq!(mdy.format(date!(2024 - 03 - 09), era), "03/09/2024");
+        assert_eq!(
+            DateFormat::default().format(date!(0000 - 12 - 31), era),
+            "0001-12-31 BCE"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let dmy = "DD.MM.YYYY".parse::<DateFormat>().unwrap();
+        assert_eq!(dmy.parse("09.03.2024"), Some(date!(2024 - 03 - 09)));
+        assert_eq!(dmy.parse("2024-03-09"), None);
+        assert_eq!(dmy.parse("31.02.2024"), None);
+        assert_eq!(dmy.parse("9.3.2024"), None);
+        assert_eq!(dmy.parse("09.03.24"), None);
+        let odd = "YYYY[MM[DD".parse::<DateFormat>().unwrap();
+        assert_eq!(odd.parse("2024[03[09"), Some(date!(2024 - 03 - 09)));
+        assert_eq!(odd.to_string(), "YYYY[MM[DD");
+    }
+
+    #[test]
+    fn test_parse_bce() {
+        let ymd = DateFormat::default();
+        assert_eq!(ymd.parse("0045-03-15 BCE"), Some(date!(-0044 - 03 - 15)));
+        assert_eq!(ymd.parse("0001-01-01 BC"), Some(date!(0000 - 01 - 01)));
+        assert_eq!(ymd.parse("-0044-03-15"), Some(date!(-0044 - 03 - 15)));
+        // 45 BCE was a leap year, even though 45 CE wasn't
+        assert_eq!(ymd.parse("0045-02-29 BCE"), Some(date!(-0044 - 02 - 29)));
+        assert_eq!(ymd.parse("-0044-03-15 BCE"), None);
+        assert_eq!(ymd.parse("0000-03-15 BCE"), None);
+    }
+
+    #[cfg(feature = "large-dates")]
+    #[test]
+    fn test_parse_large_years() {
+        let ymd = DateFormat::default();
+        assert_eq!(
+            ymd.parse("123456-01-03"),
+            Date::from_calendar_date(123_456, Month::January, 3).ok()
+        );
+        assert_eq!(
+            ymd.parse("+12345-01-03"),
+            Date::from_calendar_date(12345, Month::January, 3).ok()
+        );
+        assert_eq!(ymd.parse("1000000-01-01 BCE"), Some(Date::MIN));
+        let dmy = "DD.MM.YYYY".parse::<DateFormat>().unwrap();
+        assert_eq!(dmy.parse("31.12.999999"), Some(Date::MAX));
+    }
+
+    #[test]
+    fn test_is_out_of_range() {
+        let (min, max) = (Date::MIN.year(), Date::MAX.year());
+        let ymd = DateFormat::default();
+        assert!(ymd.is_out_of_range(&format!("{}-01-01", max + 1)));
+        assert!(ymd.is_out_of_range(&format!("{}-12-31", min - 1)));
+        assert!(ymd.is_out_of_range(&format!("{}-01-01 BCE", 2 - min)));
+        assert!(!ymd.is_out_of_range(&format!("{}-01-01 BCE", 1 - min)));
+        assert!(!ymd.is_out_of_range("2024-03-09"));
+        assert!(!ymd.is_out_of_range("2024-13-09"));
+        assert!(!ymd.is_out_of_range("10000000"));
+        let dmy = "DD.MM.YYYY".parse::<DateFormat>().unwrap();
+        assert!(dmy.is_out_of_range(&format!("01.01.{}", max + 1)));
+        assert!(!dmy.is_out_of_range("01.01.2024"));
+    }
+
+    #[test]
+    fn test_parse_format() {
+        for s in [
+            "",
+            "DD.MM",
+            "DD.MM.YYYY.",
+            "DD..MM.YYYY",
+            "DD.DD.YYYY",
+            "D.M.Y",
+        ] {
+            assert_eq!(s.parse::<DateFormat>(), Err(ParseDateFormatError), "{s:?}");
+        }
+    }
+}
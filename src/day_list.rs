@@ -0,0 +1,151 @@
+//! The list view: the calendar as one line per day, for use with screen
+//! readers
+use crate::calendar::{DateStyler, YearNumbering};
+use crate::datefmt::DateFormat;
+use ratatui::prelude::*;
+use std::str::FromStr;
+use thiserror::Error;
+use time::{Date, Duration};
+
+/// The ways of showing the calendar that it can start in
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum View {
+    /// The usual grid of weeks
+    #[default]
+    Week,
+    /// One line per day, as drawn by [`DayList`]
+    List,
+}
+
+impl View {
+    pub(crate) const ALL: [View; 2] = [View::Week, View::List];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            View::Week => "week",
+            View::List => "list",
+        }
+    }
+}
+
+impl FromStr for View {
+    type Err = ParseViewError;
+
+    fn from_str(s: &str) -> Result<View, ParseViewError> {
+        View::ALL
+            .into_iter()
+            .find(|v| v.name().eq_ignore_ascii_case(s))
+            .ok_or(ParseViewError)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("view must be \"week\" or \"list\"")]
+pub(crate) struct ParseViewError;
+
+/// A column of consecutive days, one per line, centered on the selected day,
+/// e.g., "Wednesday 2025-06-11 — Full moon"
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DayList<'a, S> {
+    pub(crate) styler: &'a S,
+    pub(crate) selected: Date,
+    pub(crate) today: Date,
+    pub(crate) date_format: DateFormat,
+    pub(crate) year_numbering: YearNumbering,
+    /// Whether to separate the date from the phase with a hyphen instead of
+    /// an em dash
+    pub(crate) ascii: bool,
+}
+
+impl<S: DateStyler> DayList<'_, S> {
+    /// Returns the text of the line for `date`
+    pub(crate) fn line(&self, date: Date) -> String {
+        let dash = if self.ascii { '-' } else { '—' };
+        let mut s = format!(
+            "{} {}",
+            date.weekday(),
+            self.date_format.format(date, self.year_numbering)
+        );
+        if let Some(phase) = self.styler.moon_phase(date) {
+            let mut name = phase.name().chars();
+            if let Some(first) = name.next() {
+                s.push(' ');
+                s.push(dash);
+                s.push(' ');
+                s.extend(first.to_uppercase());
+                s.push_str(name.as_str());
+            }
+        }
+        if date == self.today {
+            s.push_str(" (today)");
+        }
+        s
+    }
+
+    /// Returns the row of `area` on which the selected day is drawn
+    pub(crate) fn selected_row(area: Rect) -> u16 {
+        area.y + area.height / 2
+    }
+}
+
+impl<S: DateStyler> Widget for DayList<'_, S> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let selected_row = DayList::<S>::selected_row(area);
+        for y in area.top()..area.bottom() {
+            let offset = Duration::days(i64::from(y) - i64::from(selected_row));
+            let Some(date) = self.selected.checked_add(offset) else {
+                continue;
+            };
+            let mut style = self.styler.date_style(date);
+            if date == self.selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            let row = Rect {
+                y,
+                height: 1,
+                ..area
+            };
+            Line::styled(self.line(date), style).render(row, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dump::buffer_text;
+    use crate::settings::{Highlighter, Settings};
+    use crate::theme::Theme;
+    use time::macros::date;
+    use time::UtcOffset;
+
+    #[test]
+    fn test_render() {
+        let highlighter = Highlighter {
+            settings: Settings::default(),
+            theme: Theme::default(),
+            offset: UtcOffset::UTC,
+            extra: (),
+        };
+        let list = DayList {
+            styler: &highlighter,
+            selected: date!(2023 - 11 - 27),
+            today: date!(2023 - 11 - 28),
+            date_format: DateFormat::default(),
+            year_numbering: YearNumbering::default(),
+            ascii: false,
+        };
+        let mut buf = Buffer::empty(Rect::new(0, 0, 45, 3));
+        list.render(buf.area, &mut buf);
+        assert_eq!(
+            buffer_text(&buf),
+            concat!(
+                "Sunday 2023-11-26 — Waxing gibbous\n",
+                "Monday 2023-11-27 — Full moon\n",
+                "Tuesday 2023-11-28 — Full moon (today)\n",
+            )
+        );
+        assert!(buf.get(0, 1).modifier.contains(Modifier::REVERSED));
+        assert!(!buf.get(0, 0).modifier.contains(Modifier::REVERSED));
+    }
+}
@@ -0,0 +1,69 @@
+//! Locations of nhmoon's files per the [XDG Base Directory Specification][xdg]
+//!
+//! [xdg]: https://specifications.freedesktop.org/basedir-spec/latest/
+use std::env;
+use std::path::PathBuf;
+
+/// Name of the subdirectory of each base directory in which nhmoon's files
+/// are stored
+const APP_DIR: &str = "nhmoon";
+
+/// Returns the directory for nhmoon's configuration files,
+/// `$XDG_CONFIG_HOME/nhmoon`
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    Some(base_dir("XDG_CONFIG_HOME", &[".config"])?.join(APP_DIR))
+}
+
+/// Returns the path to the default configuration file,
+/// `$XDG_CONFIG_HOME/nhmoon/config.toml`
+pub(crate) fn config_file() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+/// Returns the directory in which theme files are looked up,
+/// `$XDG_CONFIG_HOME/nhmoon/themes`
+pub(crate) fn themes_dir() -> Option<PathBuf> {
+    Some(config_dir()?.join("themes"))
+}
+
+/// Returns the directory for nhmoon's data files, `$XDG_DATA_HOME/nhmoon`
+// Nothing is stored here yet; it is provided for user data (as opposed to
+// settings or session state) that later features persist.
+#[allow(dead_code)]
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    Some(base_dir("XDG_DATA_HOME", &[".local", "share"])?.join(APP_DIR))
+}
+
+/// Returns the directory for nhmoon's state files,
+/// `$XDG_STATE_HOME/nhmoon`
+pub(crate) fn state_dir() -> Option<PathBuf> {
+    Some(base_dir("XDG_STATE_HOME", &[".local", "state"])?.join(APP_DIR))
+}
+
+/// Returns the path to the file in which the last session is saved,
+/// `$XDG_STATE_HOME/nhmoon/session.toml`
+pub(crate) fn session_file() -> Option<PathBuf> {
+    Some(state_dir()?.join("session.toml"))
+}
+
+/// Returns the value of the environment variable `var` if it is set to an
+/// absolute path; otherwise, returns the home directory joined with
+/// `default`.  Returns `None` if the home directory cannot be determined.
+fn base_dir(var: &str, default: &[&str]) -> Option<PathBuf> {
+    if let Some(path) = env::var_os(var).map(PathBuf::from) {
+        // The spec says relative paths are invalid and should be ignored
+        if path.is_absolute() {
+            return Some(path);
+        }
+    }
+    let mut path = home_dir()?;
+    path.extend(default);
+    Some(path)
+}
+
+fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    env::var_os(var)
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+}
@@ -0,0 +1,103 @@
+//! Support for `--dump-buffer`, which renders the calendar into an in-memory
+//! buffer instead of the terminal and prints the result as text
+use ratatui::buffer::Buffer;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The size of the buffer to render into and how many frames to print
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct DumpSpec {
+    pub(crate) width: u16,
+    pub(crate) height: u16,
+    /// If set, print the frames drawn on startup and after each scripted
+    /// action, up to this many; otherwise, only print the final frame
+    pub(crate) frames: Option<usize>,
+}
+
+impl FromStr for DumpSpec {
+    type Err = ParseDumpSpecError;
+
+    fn from_str(s: &str) -> Result<DumpSpec, ParseDumpSpecError> {
+        let (size, frames) = match s.split_once(',') {
+            Some((size, frames)) => {
+                let frames = frames
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or(ParseDumpSpecError)?;
+                (size, Some(frames))
+            }
+            None => (s, None),
+        };
+        let (width, height) = size.split_once(['x', 'X']).ok_or(ParseDumpSpecError)?;
+        let parse_dimen = |d: &str| {
+            d.trim()
+                .parse::<u16>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or(ParseDumpSpecError)
+        };
+        Ok(DumpSpec {
+            width: parse_dimen(width)?,
+            height: parse_dimen(height)?,
+            frames,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("invalid buffer dump spec; expected WIDTHxHEIGHT[,FRAMES]")]
+pub(crate) struct ParseDumpSpecError;
+
+/// Returns the symbols in `buffer` as lines of text, with trailing whitespace
+/// removed from each line.  Styles are discarded.
+pub(crate) fn buffer_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut s = String::new();
+    for y in area.top()..area.bottom() {
+        let line = (area.left()..area.right())
+            .map(|x| buffer.get(x, y).symbol())
+            .collect::<String>();
+        s.push_str(line.trim_end());
+        s.push('\n');
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+    use ratatui::style::Style;
+
+    #[test]
+    fn test_parse_dump_spec() {
+        assert_eq!(
+            "80x24".parse::<DumpSpec>(),
+            Ok(DumpSpec {
+                width: 80,
+                height: 24,
+                frames: None
+            })
+        );
+        assert_eq!(
+            "40X10,3".parse::<DumpSpec>(),
+            Ok(DumpSpec {
+                width: 40,
+                height: 10,
+                frames: Some(3)
+            })
+        );
+        for s in ["80", "80x", "0x24", "80x24,", "80x24,0", "80x24x3"] {
+            assert_eq!(s.parse::<DumpSpec>(), Err(ParseDumpSpecError), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn test_buffer_text() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 5, 2));
+        buffer.set_string(1, 0, "Hi", Style::new());
+        assert_eq!(buffer_text(&buffer), " Hi\n\n");
+    }
+}
@@ -0,0 +1,173 @@
+//! Prediction of lunar eclipses using the method from chapter 54 of Jean
+//! Meeus's *Astronomical Algorithms*
+use crate::calendar::DateStyler;
+use crate::lunation::{cos_series, local_time_on, sin_series, Lunation};
+use ratatui::style::Style;
+use std::fmt;
+use time::{Date, Time, UtcOffset};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub(crate) enum EclipseKind {
+    Penumbral,
+    Partial,
+    Total,
+}
+
+impl fmt::Display for EclipseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EclipseKind::Penumbral => write!(f, "penumbral"),
+            EclipseKind::Partial => write!(f, "partial"),
+            EclipseKind::Total => write!(f, "total"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct LunarEclipse {
+    pub(crate) kind: EclipseKind,
+    /// Local time of greatest eclipse
+    pub(crate) greatest: Time,
+}
+
+/// Returns the lunar eclipse, if any, whose greatest phase occurs on `date` in
+/// the timezone given by `offset`
+pub(crate) fn lunar_eclipse_on(date: Date, offset: UtcOffset) -> Option<LunarEclipse> {
+    Lunation::full_moons_near(date).into_iter().find_map(|lun| {
+        let (kind, jd) = eclipse_at_lunation(&lun)?;
+        let greatest = local_time_on(jd, date, offset)?;
+        Some(LunarEclipse { kind, greatest })
+    })
+}
+
+/// If the full moon of the given lunation is eclipsed, return the kind of
+/// eclipse and the Julian date (UT) of greatest eclipse
+fn eclipse_at_lunation(lun: &Lunation) -> Option<(EclipseKind, f64)> {
+    let Lunation {
+        e, m, mp, f, omega, ..
+    } = *lun;
+    if f.to_radians().sin().abs() > 0.36 {
+        // The Moon is too far from a node of its orbit
+        return None;
+    }
+    let f1 = 0.02665f64.mul_add(-omega.to_radians().sin(), f);
+    let a1 = 0.107_408_f64.mul_add(lun.k, 299.77);
+    // Doubled arguments that recur throughout the periodic terms
+    let (m2, mp2, f2) = (2.0 * m, 2.0 * mp, 2.0 * f1);
+    let jde = lun.mean_jde
+        + sin_series(&[
+            (-0.4065, mp),
+            (0.1727 * e, m),
+            (0.0161, mp2),
+            (-0.0097, f2),
+            (0.0073 * e, mp - m),
+            (-0.0050 * e, mp + m),
+            (-0.0023, mp - f2),
+            (0.0021 * e, m2),
+            (0.0012, mp + f2),
+            (0.0006 * e, mp2 + m),
+            (-0.0004, 3.0 * mp),
+            (-0.0003 * e, m + f2),
+            (0.0003, a1),
+            (-0.0002 * e, m - f2),
+            (-0.0002 * e, mp2 - m),
+            (-0.0002, omega),
+        ]);
+    let p = sin_series(&[
+        (0.2070 * e, m),
+        (0.0024 * e, m2),
+        (-0.0392, mp),
+        (0.0116, mp2),
+        (-0.0073 * e, mp + m),
+        (0.0067 * e, mp - m),
+        (0.0118, f2),
+    ]);
+    let q = 5.2207
+        + cos_series(&[
+            (-0.0048 * e, m),
+            (0.0020 * e, m2),
+            (-0.3299, mp),
+            (-0.0060 * e, mp + m),
+            (0.0041 * e, mp - m),
+        ]);
+    let (sin_f1, cos_f1) = f1.to_radians().sin_cos();
+    let gamma = p.mul_add(cos_f1, q * sin_f1) * 0.0048f64.mul_add(-cos_f1.abs(), 1.0);
+    let u = 0.0059
+        + cos_series(&[
+            (0.0046 * e, m),
+            (-0.0182, mp),
+            (0.0004, mp2),
+            (-0.0005, m + mp),
+        ]);
+    let penumbral_magnitude = (1.5573 + u - gamma.abs()) / 0.5450;
+    let umbral_magnitude = (1.0128 - u - gamma.abs()) / 0.5450;
+    let kind = if umbral_magnitude >= 1.0 {
+        EclipseKind::Total
+    } else if umbral_magnitude > 0.0 {
+        EclipseKind::Partial
+    } else if penumbral_magnitude > 0.0 {
+        EclipseKind::Penumbral
+    } else {
+        return None;
+    };
+    Some((kind, jde - lun.delta_t() / 86400.0))
+}
+
+/// A `DateStyler` that applies `style` to dates of lunar eclipses
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Eclipses {
+    pub(crate) offset: UtcOffset,
+    pub(crate) style: Style,
+}
+
+impl DateStyler for Eclipses {
+    fn date_style(&self, date: Date) -> Style {
+        if lunar_eclipse_on(date, self.offset).is_some() {
+            self.style
+        } else {
+            Style::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, offset, time};
+    use time::Duration;
+
+    #[test]
+    fn test_total_eclipse() {
+        let e = lunar_eclipse_on(date!(2022 - 11 - 08), offset!(UTC)).unwrap();
+        assert_eq!(e.kind, EclipseKind::Total);
+        assert!((e.greatest - time!(10:59)).abs() < Duration::minutes(5));
+    }
+
+    #[test]
+    fn test_partial_eclipse() {
+        let e = lunar_eclipse_on(date!(2023 - 10 - 28), offset!(UTC)).unwrap();
+        assert_eq!(e.kind, EclipseKind::Partial);
+        assert!((e.greatest - time!(20:14)).abs() < Duration::minutes(5));
+    }
+
+    #[test]
+    fn test_penumbral_eclipse() {
+        let e = lunar_eclipse_on(date!(2023 - 05 - 05), offset!(UTC)).unwrap();
+        assert_eq!(e.kind, EclipseKind::Penumbral);
+        assert!((e.greatest - time!(17:23)).abs() < Duration::minutes(5));
+    }
+
+    #[test]
+    fn test_eclipse_local_date() {
+        // Greatest eclipse was at 10:59 UTC, which was 05:59 in New York
+        let e = lunar_eclipse_on(date!(2022 - 11 - 08), offset!(-5)).unwrap();
+        assert!((e.greatest - time!(05:59)).abs() < Duration::minutes(5));
+        assert_eq!(lunar_eclipse_on(date!(2022 - 11 - 08), offset!(+14)), None);
+    }
+
+    #[test]
+    fn test_no_eclipse() {
+        assert_eq!(lunar_eclipse_on(date!(2023 - 11 - 27), offset!(UTC)), None);
+        assert_eq!(lunar_eclipse_on(date!(2022 - 11 - 09), offset!(UTC)), None);
+    }
+}
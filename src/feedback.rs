@@ -0,0 +1,170 @@
+//! Configurable feedback for keys & actions that can't be carried out
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A condition that the user is alerted to
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum Alert {
+    /// A key that isn't bound to anything or isn't accepted by the open popup
+    /// or prompt
+    InvalidKey,
+    /// Scrolling past the earliest or latest date that can be shown
+    EndOfTime,
+    /// A jump or other action that can't be carried out, e.g., jumping to the
+    /// next given date when no dates were given, or to a mistyped year
+    FailedJump,
+    /// Replaying a macro register that nothing has been recorded into
+    EmptyRegister,
+}
+
+impl Alert {
+    pub(crate) const ALL: [Alert; 4] = [
+        Alert::InvalidKey,
+        Alert::EndOfTime,
+        Alert::FailedJump,
+        Alert::EmptyRegister,
+    ];
+
+    /// The key for the alert in the `[feedback]` table of the configuration
+    /// file
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Alert::InvalidKey => "invalid-key",
+            Alert::EndOfTime => "end-of-time",
+            Alert::FailedJump => "failed-jump",
+            Alert::EmptyRegister => "empty-register",
+        }
+    }
+
+    /// The text shown for the alert with `Feedback::Message`
+    pub(crate) fn message(self) -> &'static str {
+        match self {
+            Alert::InvalidKey => " Key not bound ",
+            Alert::EndOfTime => " End of time ",
+            Alert::FailedJump => " Can't do that ",
+            Alert::EmptyRegister => " Register is empty ",
+        }
+    }
+}
+
+impl FromStr for Alert {
+    type Err = ParseAlertError;
+
+    fn from_str(s: &str) -> Result<Alert, ParseAlertError> {
+        Alert::ALL
+            .into_iter()
+            .find(|a| a.name() == s)
+            .ok_or(ParseAlertError)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error(
+    "feedback condition must be \"invalid-key\", \"end-of-time\", \"failed-jump\", or \"empty-register\""
+)]
+pub(crate) struct ParseAlertError;
+
+/// How the user is alerted to a condition
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum Feedback {
+    /// Ring the terminal bell
+    #[default]
+    Bell,
+    /// Briefly draw the screen in reverse video
+    Flash,
+    /// Show a short message in the bottom-right corner until the next
+    /// keypress
+    Message,
+    /// Do nothing
+    None,
+}
+
+impl Feedback {
+    pub(crate) const ALL: [Feedback; 4] = [
+        Feedback::Bell,
+        Feedback::Flash,
+        Feedback::Message,
+        Feedback::None,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Feedback::Bell => "bell",
+            Feedback::Flash => "flash",
+            Feedback::Message => "message",
+            Feedback::None => "none",
+        }
+    }
+}
+
+impl FromStr for Feedback {
+    type Err = ParseFeedbackError;
+
+    fn from_str(s: &str) -> Result<Feedback, ParseFeedbackError> {
+        Feedback::ALL
+            .into_iter()
+            .find(|f| f.name().eq_ignore_ascii_case(s))
+            .ok_or(ParseFeedbackError)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("feedback must be \"bell\", \"flash\", \"message\", or \"none\"")]
+pub(crate) struct ParseFeedbackError;
+
+/// The feedback to give for each condition
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct FeedbackMap {
+    pub(crate) invalid_key: Feedback,
+    pub(crate) end_of_time: Feedback,
+    pub(crate) failed_jump: Feedback,
+    pub(crate) empty_register: Feedback,
+}
+
+impl FeedbackMap {
+    /// Give the same feedback for every condition
+    pub(crate) fn uniform(feedback: Feedback) -> FeedbackMap {
+        FeedbackMap {
+            invalid_key: feedback,
+            end_of_time: feedback,
+            failed_jump: feedback,
+            empty_register: feedback,
+        }
+    }
+
+    pub(crate) fn get(&self, alert: Alert) -> Feedback {
+        match alert {
+            Alert::InvalidKey => self.invalid_key,
+            Alert::EndOfTime => self.end_of_time,
+            Alert::FailedJump => self.failed_jump,
+            Alert::EmptyRegister => self.empty_register,
+        }
+    }
+
+    pub(crate) fn set(&mut self, alert: Alert, feedback: Feedback) {
+        match alert {
+            Alert::InvalidKey => self.invalid_key = feedback,
+            Alert::EndOfTime => self.end_of_time = feedback,
+            Alert::FailedJump => self.failed_jump = feedback,
+            Alert::EmptyRegister => self.empty_register = feedback,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feedback_map() {
+        let mut map = FeedbackMap::uniform(Feedback::Message);
+        map.set("end-of-time".parse().unwrap(), "NONE".parse().unwrap());
+        assert_eq!(map.get(Alert::InvalidKey), Feedback::Message);
+        assert_eq!(map.get(Alert::EndOfTime), Feedback::None);
+        map.set("empty-register".parse().unwrap(), Feedback::Flash);
+        assert_eq!(map.get(Alert::EmptyRegister), Feedback::Flash);
+        assert_eq!(map.get(Alert::FailedJump), Feedback::Message);
+        assert_eq!("flicker".parse::<Feedback>(), Err(ParseFeedbackError));
+        assert_eq!("bad-key".parse::<Alert>(), Err(ParseAlertError));
+    }
+}
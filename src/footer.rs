@@ -0,0 +1,100 @@
+//! The line of NetHack luck information shown at the bottom of the screen in
+//! dashboard mode
+use crate::calendar::YearNumbering;
+use crate::datefmt::DateFormat;
+use crate::luck::Luck;
+use crate::moon::Phase;
+use ratatui::prelude::*;
+
+/// A one-line summary of the luck in effect today and how long it is until
+/// the next full & new moons
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct LuckFooter {
+    pub(crate) luck: Luck,
+    pub(crate) date_format: DateFormat,
+    pub(crate) year_numbering: YearNumbering,
+    /// Whether to show the messages that NetHack prints for a full or new
+    /// moon or Friday the 13th instead of naming them
+    pub(crate) game_messages: bool,
+    pub(crate) style: Style,
+}
+
+impl LuckFooter {
+    fn text(&self) -> String {
+        let luck = &self.luck;
+        let mut parts = vec![
+            format!(
+                "{} ({})",
+                self.date_format.format(luck.date, self.year_numbering),
+                luck.date.weekday()
+            ),
+            format!("Luck {:+}", luck.luck()),
+        ];
+        if self.game_messages {
+            parts.extend(luck.messages().into_iter().map(String::from));
+        } else {
+            match luck.phase {
+                Phase::Full => parts.push(String::from("Full moon tonight")),
+                Phase::New => parts.push(String::from("New moon tonight")),
+                Phase::Normal => (),
+            }
+            if luck.friday_13th {
+                parts.push(String::from("Friday the 13th"));
+            }
+        }
+        for (label, phase, next) in [
+            ("Full", Phase::Full, luck.next_full_moon),
+            ("New", Phase::New, luck.next_new_moon),
+        ] {
+            if let Some(next) = next.filter(|_| luck.phase != phase) {
+                let days = (next - luck.date).whole_days();
+                let unit = if days == 1 { "day" } else { "days" };
+                parts.push(format!("{label} moon in {days} {unit}"));
+            }
+        }
+        parts.join(" | ")
+    }
+}
+
+impl Widget for LuckFooter {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, self.style);
+        Line::raw(format!(" {}", self.text())).render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    fn footer(date: time::Date) -> LuckFooter {
+        LuckFooter {
+            luck: Luck::for_date(date),
+            date_format: DateFormat::default(),
+            year_numbering: YearNumbering::default(),
+            game_messages: false,
+            style: Style::new(),
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(
+            footer(date!(2023 - 11 - 20)).text(),
+            "2023-11-20 (Monday) | Luck +0 | Full moon in 7 days | New moon in 21 days"
+        );
+        assert_eq!(
+            footer(date!(2023 - 10 - 13)).text(),
+            "2023-10-13 (Friday) | Luck -1 | New moon tonight | Friday the 13th | Full moon in 15 days"
+        );
+        let footer = LuckFooter {
+            game_messages: true,
+            ..footer(date!(2023 - 11 - 27))
+        };
+        assert_eq!(
+            footer.text(),
+            "2023-11-27 (Monday) | Luck +1 | You are lucky!  Full moon tonight. | New moon in 14 days"
+        );
+    }
+}
@@ -1,51 +1,134 @@
-use ratatui::{layout::Flex, prelude::*, widgets::*};
+use crate::keymap::{key_name, Action, Keymap};
+use crate::popup::{Popup, PopupStyle};
+use crossterm::event::KeyCode;
+use ratatui::prelude::*;
+use ratatui::widgets::{Clear, Paragraph};
 
-static TEXT: &[&str] = &[
-    "j, UP           Scroll up one week\n",
-    "k, DOWN         Scroll down one week\n",
-    "w, PAGE UP      Scroll up one page\n",
-    "z, PAGE DOWN    Scroll down one page\n",
-    "0, HOME         Jump to today\n",
-    "?               Show this help\n",
-    "q, ESC          Quit\n",
-    "\n",
-    "Press the Any Key to dismiss.\n",
-];
+/// Popup shown on the first run, introducing the most important keys
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Welcome<'a> {
+    pub(crate) keymap: &'a Keymap,
+    pub(crate) style: PopupStyle,
+}
+
+impl Widget for Welcome<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let show_keys = |actions: &[Action]| {
+            let keys = actions
+                .iter()
+                .flat_map(|&a| self.keymap.keys_for(a))
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>();
+            if keys.is_empty() {
+                String::from("(unbound)")
+            } else {
+                keys.join(", ")
+            }
+        };
+        let help = show_keys(&[Action::Help]);
+        let lines = vec![
+            Line::raw("nhmoon shows NetHack's full & new moons on a scrolling calendar."),
+            Line::raw(""),
+            Line::raw(format!(
+                "  {:<20}Scroll",
+                show_keys(&[Action::ScrollDown, Action::ScrollUp])
+            )),
+            Line::raw(format!(
+                "  {:<20}Jump to today",
+                show_keys(&[Action::Today])
+            )),
+            Line::raw(format!("  {:<20}Quit", show_keys(&[Action::Quit]))),
+            Line::raw(""),
+            Line::raw(format!("Press {help} at any time to see all commands.")),
+            Line::raw(""),
+            Line::raw("Press the Any Key to begin."),
+        ];
+        Popup::new(" Welcome ", lines, self.style).render(area, buf);
+    }
+}
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub(crate) struct Help(pub(crate) Style);
+/// Popup listing the commands and the keys currently bound to them
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Help<'a> {
+    pub(crate) keymap: &'a Keymap,
+    pub(crate) style: PopupStyle,
+}
 
-impl Widget for Help {
+impl Widget for Help<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let lines = TEXT.iter().map(|&s| Line::raw(s)).collect::<Vec<_>>();
-        let text = Text::from(lines);
-        let height = u16::try_from(text.height())
-            .unwrap_or(u16::MAX)
-            .min(area.height)
-            .saturating_add(2);
-        let width = u16::try_from(text.width())
-            .unwrap_or(u16::MAX)
-            .min(area.width)
-            .saturating_add(2);
-        let para = Paragraph::new(text)
-            .block(
-                Block::bordered()
-                    .title(" Commands ")
-                    .title_alignment(Alignment::Center),
+        let mut lines = Action::ALL
+            .into_iter()
+            .filter_map(|action| {
+                let keys = self.keymap.keys_for(action);
+                (!keys.is_empty()).then(|| {
+                    let keys = keys.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    Line::raw(format!("{:<16}{}", keys.join(", "), action.description()))
+                })
+            })
+            .collect::<Vec<_>>();
+        lines.push(Line::raw(""));
+        lines.push(Line::raw("Press the Any Key to dismiss."));
+        Popup::new(" Commands ", lines, self.style).render(area, buf);
+    }
+}
+
+/// Strip along the bottom of the screen listing the keys that can complete a
+/// chord after its first key, `prefix`, has been pressed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct ChordHints<'a> {
+    pub(crate) keymap: &'a Keymap,
+    pub(crate) prefix: KeyCode,
+    pub(crate) style: Style,
+}
+
+impl ChordHints<'_> {
+    /// Returns the lines of the strip for a screen `width` columns wide: a
+    /// line naming the prefix followed by the completions, e.g.,
+    /// "m  Choose a month", in as many columns as fit
+    fn lines(&self, width: u16) -> Vec<Line<'static>> {
+        let entries = self
+            .keymap
+            .completions(self.prefix)
+            .into_iter()
+            .map(|(key, action)| format!("{}  {}", key_name(key), action.description()))
+            .collect::<Vec<_>>();
+        let widest = entries
+            .iter()
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or_default();
+        let columns = (usize::from(width) / (widest + 3)).max(1);
+        let mut lines = vec![Line::from(vec![
+            Span::styled(
+                format!(" {} ", key_name(self.prefix)),
+                Style::new().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("pressed; Esc to cancel"),
+        ])];
+        lines.extend(entries.chunks(columns).map(|row| {
+            Line::raw(
+                row.iter()
+                    .map(|s| format!("   {s:<widest$}"))
+                    .collect::<String>(),
             )
-            .style(self.0);
-        let [help_area] = Layout::horizontal([width]).flex(Flex::Center).areas(area);
-        let [help_area] = Layout::vertical([height])
-            .flex(Flex::Center)
-            .areas(help_area);
-        let outer_area = Rect {
-            x: help_area.x.saturating_sub(1),
-            y: help_area.y,
-            width: help_area.width.saturating_add(2),
-            height: help_area.height,
+        }));
+        lines
+    }
+}
+
+impl Widget for ChordHints<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let lines = self.lines(area.width);
+        let height = u16::try_from(lines.len())
+            .unwrap_or(u16::MAX)
+            .min(area.height);
+        let area = Rect {
+            y: area.bottom() - height,
+            height,
+            ..area
         };
-        Clear.render(outer_area, buf);
-        Block::new().style(self.0).render(outer_area, buf);
-        para.render(help_area, buf);
+        Clear.render(area, buf);
+        buf.set_style(area, self.style);
+        Paragraph::new(lines).render(area, buf);
     }
 }
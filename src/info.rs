@@ -0,0 +1,202 @@
+use crate::astro::{moon_rise_set, Location};
+use crate::calendar::YearNumbering;
+use crate::calendars::OtherCalendars;
+use crate::community::community_events_on;
+use crate::datefmt::DateFormat;
+use crate::eclipse::lunar_eclipse_on;
+use crate::luck::{is_friday_13th, next_friday_13th, Luck};
+use crate::moon::{Hemisphere, Phase, PhaseCalculator, Phoon};
+use crate::popup::{Popup, PopupStyle};
+use crate::supermoon::full_moon_size;
+use ratatui::prelude::*;
+use std::iter::successors;
+use time::{Date, Time, UtcOffset};
+
+/// Number of upcoming notable dates to list
+const UPCOMING_QTY: usize = 5;
+
+/// Popup showing details about a single date
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct DayInfo<'a> {
+    pub(crate) date: Date,
+    pub(crate) phoon: Phoon,
+    /// The dates highlighted on the command line
+    pub(crate) marked: &'a [Date],
+    pub(crate) location: Option<Location>,
+    pub(crate) hemisphere: Hemisphere,
+    pub(crate) offset: UtcOffset,
+    pub(crate) calendars: OtherCalendars,
+    pub(crate) date_format: DateFormat,
+    pub(crate) year_numbering: YearNumbering,
+    /// Whether to quote the messages that NetHack prints when a game is
+    /// started on the date
+    pub(crate) game_messages: bool,
+    pub(crate) style: PopupStyle,
+}
+
+impl DayInfo<'_> {
+    fn lines(&self) -> Vec<Line<'static>> {
+        let lunar = self.phoon.lunar_phase(self.date);
+        let mut lines = vec![Line::raw(format!(
+            "Date:           {} ({})",
+            self.date_format.format(self.date, self.year_numbering),
+            self.date.weekday()
+        ))];
+        lines.extend(
+            self.calendars
+                .lines(self.date, self.date_format, self.year_numbering)
+                .into_iter()
+                .map(Line::raw),
+        );
+        lines.push(Line::raw(if self.style.ascii {
+            format!(
+                "Moon:           {} (per {})",
+                lunar.name(),
+                self.phoon.algorithm.name(),
+            )
+        } else {
+            format!(
+                "Moon:           {} {} (per {})",
+                lunar.glyph(self.hemisphere),
+                lunar.name(),
+                self.phoon.algorithm.name(),
+            )
+        }));
+        if is_friday_13th(self.date) {
+            lines.push(Line::raw(match self.phoon.phase(self.date) {
+                Phase::Full => "Friday 13th:    yes, coinciding with a full moon",
+                Phase::New => "Friday 13th:    yes, coinciding with a new moon",
+                Phase::Normal => "Friday 13th:    yes",
+            }));
+        }
+        if self.game_messages {
+            let luck = Luck::for_date(self.date);
+            for (i, msg) in luck.messages().into_iter().enumerate() {
+                let label = if i == 0 { "NetHack says:" } else { "" };
+                lines.push(Line::raw(format!("{label:16}{msg}")));
+            }
+        }
+        if let Some((size, distance)) = full_moon_size(self.date, self.offset) {
+            lines.push(Line::raw(format!(
+                "Full moon:      {size} ({distance:.0} km away)"
+            )));
+        }
+        if let Some(eclipse) = lunar_eclipse_on(self.date, self.offset) {
+            lines.push(Line::raw(format!(
+                "Eclipse:        {} lunar eclipse, greatest at {}",
+                eclipse.kind,
+                show_time(Some(eclipse.greatest))
+            )));
+        }
+        for event in community_events_on(self.date) {
+            lines.push(Line::raw(format!("Event:          {event}")));
+        }
+        if let Some(loc) = self.location {
+            let rs = moon_rise_set(self.date, self.offset, loc);
+            lines.push(Line::raw(format!("Moonrise:       {}", show_time(rs.rise))));
+            lines.push(Line::raw(format!("Moonset:        {}", show_time(rs.set))));
+        }
+        for (i, (date, what)) in self.upcoming().into_iter().enumerate() {
+            let label = if i == 0 { "Upcoming:" } else { "" };
+            let days = (date - self.date).whole_days();
+            let unit = if days == 1 { "day" } else { "days" };
+            lines.push(Line::raw(format!(
+                "{label:16}{}  {what} (in {days} {unit})",
+                self.date_format.format(date, self.year_numbering),
+            )));
+        }
+        lines.push(Line::raw(""));
+        lines.push(Line::raw("Press the Any Key to dismiss."));
+        lines
+    }
+
+    /// Returns the next few full moons, new moons, Friday the 13ths, and
+    /// highlighted dates after the date, in order
+    fn upcoming(&self) -> Vec<(Date, &'static str)> {
+        let mut dates = Vec::new();
+        for (phase, what) in [(Phase::Full, "full moon"), (Phase::New, "new moon")] {
+            dates.extend(
+                successors(self.phoon.next_phase(self.date, phase), |&d| {
+                    self.phoon.next_phase(d, phase)
+                })
+                .take(UPCOMING_QTY)
+                .map(|d| (d, what)),
+            );
+        }
+        dates.extend(
+            successors(next_friday_13th(self.date), |&d| next_friday_13th(d))
+                .take(UPCOMING_QTY)
+                .map(|d| (d, "Friday the 13th")),
+        );
+        let mut marked = self
+            .marked
+            .iter()
+            .copied()
+            .filter(|&d| d > self.date)
+            .collect::<Vec<_>>();
+        marked.sort_unstable();
+        marked.dedup();
+        dates.extend(
+            marked
+                .into_iter()
+                .take(UPCOMING_QTY)
+                .map(|d| (d, "given date")),
+        );
+        // A stable sort keeps dates that are notable for more than one reason
+        // in the order above
+        dates.sort_by_key(|&(d, _)| d);
+        dates.truncate(UPCOMING_QTY);
+        dates
+    }
+}
+
+impl Widget for DayInfo<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Popup::new(" Info ", self.lines(), self.style).render(area, buf);
+    }
+}
+
+fn show_time(t: Option<Time>) -> String {
+    match t {
+        Some(t) => format!("{:02}:{:02}", t.hour(), t.minute()),
+        None => String::from("none"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moon::PhaseAlgorithm;
+    use time::macros::date;
+
+    #[test]
+    fn test_upcoming() {
+        let marked = [date!(2023 - 12 - 25), date!(2023 - 11 - 01)];
+        let info = DayInfo {
+            date: date!(2023 - 11 - 20),
+            phoon: Phoon {
+                algorithm: PhaseAlgorithm::NetHack,
+                offset: UtcOffset::UTC,
+            },
+            marked: &marked,
+            location: None,
+            hemisphere: Hemisphere::North,
+            offset: UtcOffset::UTC,
+            calendars: OtherCalendars::default(),
+            date_format: DateFormat::default(),
+            year_numbering: YearNumbering::default(),
+            game_messages: false,
+            style: PopupStyle::default(),
+        };
+        assert_eq!(
+            info.upcoming(),
+            [
+                (date!(2023 - 11 - 27), "full moon"),
+                (date!(2023 - 12 - 11), "new moon"),
+                (date!(2023 - 12 - 25), "given date"),
+                (date!(2023 - 12 - 26), "full moon"),
+                (date!(2024 - 01 - 10), "new moon"),
+            ]
+        );
+    }
+}
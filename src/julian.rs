@@ -0,0 +1,60 @@
+//! Conversion of dates to the (proleptic) Julian calendar
+use time::{Date, Month};
+
+/// A date in the Julian calendar, extended backwards indefinitely
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct JulianDate {
+    pub(crate) year: i32,
+    pub(crate) month: Month,
+    pub(crate) day: u8,
+}
+
+impl From<Date> for JulianDate {
+    fn from(date: Date) -> JulianDate {
+        // Algorithm from E. G. Richards, "Calendars", in *Explanatory
+        // Supplement to the Astronomical Almanac* (3rd ed.), using floor
+        // division so that it also works before the algorithm's epoch
+        let c = i64::from(date.to_julian_day()) + 32082;
+        let d = (4 * c + 3).div_euclid(1461);
+        let e = c - (1461 * d).div_euclid(4);
+        let m = (5 * e + 2).div_euclid(153);
+        let day = e - (153 * m + 2).div_euclid(5) + 1;
+        let month = m + 3 - 12 * m.div_euclid(10);
+        let year = d - 4800 + m.div_euclid(10);
+        JulianDate {
+            year: i32::try_from(year).expect("Julian year should fit in an i32"),
+            month: u8::try_from(month)
+                .ok()
+                .and_then(|m| Month::try_from(m).ok())
+                .expect("Julian month should be from 1 to 12"),
+            day: u8::try_from(day).expect("Julian day of month should fit in a u8"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+    use time::Month::*;
+
+    #[test]
+    fn test_from_date() {
+        for (gregorian, (year, month, day)) in [
+            (date!(1582 - 10 - 15), (1582, October, 5)),
+            (date!(1900 - 03 - 13), (1900, February, 29)),
+            (date!(2024 - 03 - 10), (2024, February, 26)),
+            (date!(0000 - 12 - 30), (1, January, 1)),
+            (date!(-0044 - 03 - 13), (-44, March, 15)),
+        ] {
+            assert_eq!(JulianDate::from(gregorian), JulianDate { year, month, day });
+        }
+    }
+
+    #[test]
+    fn test_extremes() {
+        // Make sure nothing panics at the ends of time
+        let _ = JulianDate::from(Date::MIN);
+        let _ = JulianDate::from(Date::MAX);
+    }
+}
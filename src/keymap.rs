@@ -0,0 +1,567 @@
+//! Mapping of keys to the commands they invoke
+use crossterm::event::KeyCode;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A command that can be bound to a key
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Action {
+    /// Scroll down one week
+    ScrollDown,
+    /// Scroll up one week
+    ScrollUp,
+    /// Scroll down one screenful
+    PageDown,
+    /// Scroll up one screenful
+    PageUp,
+    /// Jump to today
+    Today,
+    /// Center the last date given on the command line, or else today
+    Recenter,
+    /// Jump to the next date given on the command line
+    NextDate,
+    /// Jump to the previous date given on the command line
+    PrevDate,
+    /// Jump to the next full moon
+    NextFullMoon,
+    /// Jump to the previous full moon
+    PrevFullMoon,
+    /// Jump to the next new moon
+    NextNewMoon,
+    /// Jump to the previous new moon
+    PrevNewMoon,
+    /// Prompt for a year and jump to its start
+    JumpToYear,
+    /// Pick a month of the year being shown from a popup and jump to it
+    ChooseMonth,
+    /// Jump to a month by typing its name
+    FindMonth,
+    /// Repeat the last jump
+    RepeatJump,
+    /// Repeat the last jump in the opposite direction
+    ReverseJump,
+    /// Start or stop recording keys into a register
+    RecordMacro,
+    /// Replay the keys recorded into a register
+    PlayMacro,
+    /// Animate the Moon's phases through the month being shown
+    AnimatePhases,
+    /// Copy the dates on screen to the clipboard as a Markdown table
+    CopyTable,
+    /// Toggle between the grid of weeks and the one-day-per-line list
+    ListView,
+    /// Cycle through the styles of borders between months
+    MonthBorders,
+    /// Show information about today or the selected day
+    Info,
+    /// Explain the colors used in the calendar
+    Legend,
+    /// Open the popup for adjusting display settings
+    Options,
+    /// Open the editor for the theme's colors
+    EditTheme,
+    /// Show the key bindings
+    Help,
+    /// Exit the calendar
+    Quit,
+}
+
+impl Action {
+    pub(crate) const ALL: [Action; 29] = [
+        Action::ScrollDown,
+        Action::ScrollUp,
+        Action::PageDown,
+        Action::PageUp,
+        Action::Today,
+        Action::Recenter,
+        Action::NextDate,
+        Action::PrevDate,
+        Action::NextFullMoon,
+        Action::PrevFullMoon,
+        Action::NextNewMoon,
+        Action::PrevNewMoon,
+        Action::JumpToYear,
+        Action::ChooseMonth,
+        Action::FindMonth,
+        Action::RepeatJump,
+        Action::ReverseJump,
+        Action::RecordMacro,
+        Action::PlayMacro,
+        Action::AnimatePhases,
+        Action::CopyTable,
+        Action::ListView,
+        Action::MonthBorders,
+        Action::Info,
+        Action::Legend,
+        Action::Options,
+        Action::EditTheme,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Action::ScrollDown => "scroll-down",
+            Action::ScrollUp => "scroll-up",
+            Action::PageDown => "page-down",
+            Action::PageUp => "page-up",
+            Action::Today => "today",
+            Action::Recenter => "recenter",
+            Action::NextDate => "next-date",
+            Action::PrevDate => "prev-date",
+            Action::NextFullMoon => "next-full-moon",
+            Action::PrevFullMoon => "prev-full-moon",
+            Action::NextNewMoon => "next-new-moon",
+            Action::PrevNewMoon => "prev-new-moon",
+            Action::JumpToYear => "jump-to-year",
+            Action::ChooseMonth => "choose-month",
+            Action::FindMonth => "find-month",
+            Action::RepeatJump => "repeat-jump",
+            Action::ReverseJump => "reverse-jump",
+            Action::RecordMacro => "record-macro",
+            Action::PlayMacro => "play-macro",
+            Action::AnimatePhases => "animate-phases",
+            Action::CopyTable => "copy-table",
+            Action::ListView => "list-view",
+            Action::MonthBorders => "month-borders",
+            Action::Info => "info",
+            Action::Legend => "legend",
+            Action::Options => "options",
+            Action::EditTheme => "edit-theme",
+            Action::Help => "help",
+            Action::Quit => "quit",
+        }
+    }
+
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Action::ScrollDown => "Scroll down one week",
+            Action::ScrollUp => "Scroll up one week",
+            Action::PageDown => "Scroll down one page",
+            Action::PageUp => "Scroll up one page",
+            Action::Today => "Jump to today",
+            Action::Recenter => "Center last given date or today",
+            Action::NextDate => "Jump to next given date",
+            Action::PrevDate => "Jump to previous given date",
+            Action::NextFullMoon => "Jump to next full moon",
+            Action::PrevFullMoon => "Jump to previous full moon",
+            Action::NextNewMoon => "Jump to next new moon",
+            Action::PrevNewMoon => "Jump to previous new moon",
+            Action::JumpToYear => "Jump to the start of a year",
+            Action::ChooseMonth => "Jump to a month of this year",
+            Action::FindMonth => "Jump to a month by typing it",
+            Action::RepeatJump => "Repeat the last jump",
+            Action::ReverseJump => "Repeat the last jump backwards",
+            Action::RecordMacro => "Start/stop recording keys",
+            Action::PlayMacro => "Replay recorded keys",
+            Action::AnimatePhases => "Animate the Moon through a month",
+            Action::CopyTable => "Copy shown dates as Markdown",
+            Action::ListView => "Toggle one-day-per-line list",
+            Action::MonthBorders => "Cycle month borders",
+            Action::Info => "Show info about today",
+            Action::Legend => "Explain the colors",
+            Action::Options => "Adjust options",
+            Action::EditTheme => "Edit the theme's colors",
+            Action::Help => "Show this help",
+            Action::Quit => "Quit",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = ParseBindingError;
+
+    fn from_str(s: &str) -> Result<Action, ParseBindingError> {
+        Action::ALL
+            .into_iter()
+            .find(|a| a.name() == s)
+            .ok_or_else(|| ParseBindingError::Action(s.to_owned()))
+    }
+}
+
+/// Names of non-character keys, with the display name first
+static KEY_NAMES: &[(KeyCode, &[&str])] = &[
+    (KeyCode::Up, &["up"]),
+    (KeyCode::Down, &["down"]),
+    (KeyCode::Left, &["left"]),
+    (KeyCode::Right, &["right"]),
+    (KeyCode::PageUp, &["page up", "pageup", "page-up", "pgup"]),
+    (
+        KeyCode::PageDown,
+        &["page down", "pagedown", "page-down", "pgdn"],
+    ),
+    (KeyCode::Home, &["home"]),
+    (KeyCode::End, &["end"]),
+    (KeyCode::Esc, &["esc", "escape"]),
+    (KeyCode::Enter, &["enter", "return"]),
+    (KeyCode::Tab, &["tab"]),
+    (KeyCode::BackTab, &["shift tab", "shift-tab", "backtab"]),
+    (KeyCode::Backspace, &["backspace"]),
+    (KeyCode::Delete, &["delete", "del"]),
+    (KeyCode::Insert, &["insert", "ins"]),
+    (KeyCode::Char(' '), &["space"]),
+];
+
+/// Parse a key given either as a single character or as the name of a
+/// special key, like `space` or `pgdn`
+pub(crate) fn parse_key(s: &str) -> Result<KeyCode, ParseBindingError> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+    let lower = s.to_ascii_lowercase();
+    if let Some(&(key, _)) = KEY_NAMES
+        .iter()
+        .find(|(_, names)| names.contains(&lower.as_str()))
+    {
+        return Ok(key);
+    }
+    if let Some(n) = lower
+        .strip_prefix('f')
+        .and_then(|n| n.parse::<u8>().ok())
+        .filter(|n| (1..=12).contains(n))
+    {
+        return Ok(KeyCode::F(n));
+    }
+    Err(ParseBindingError::Key(s.to_owned()))
+}
+
+/// Returns the name of `key` for display in the help popup
+pub(crate) fn key_name(key: KeyCode) -> String {
+    if let Some((_, names)) = KEY_NAMES.iter().find(|&&(k, _)| k == key) {
+        names[0].to_ascii_uppercase()
+    } else if let KeyCode::Char(c) = key {
+        c.to_string()
+    } else if let KeyCode::F(n) = key {
+        format!("F{n}")
+    } else {
+        format!("{key:?}")
+    }
+}
+
+/// Sort key for listing keys with character keys first
+fn key_order(key: KeyCode) -> (bool, String) {
+    (!matches!(key, KeyCode::Char(c) if c != ' '), key_name(key))
+}
+
+/// A single key or a chord of two keys pressed one after the other, like
+/// `g m`, that can be bound to an action
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Chord {
+    /// The first key of a chord, or `None` for a single key
+    pub(crate) prefix: Option<KeyCode>,
+    pub(crate) key: KeyCode,
+}
+
+impl Chord {
+    pub(crate) fn single(key: KeyCode) -> Chord {
+        Chord { prefix: None, key }
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(prefix) = self.prefix {
+            write!(f, "{} ", key_name(prefix))?;
+        }
+        write!(f, "{}", key_name(self.key))
+    }
+}
+
+impl FromStr for Chord {
+    type Err = ParseBindingError;
+
+    /// Parse a single key or two keys separated by whitespace
+    fn from_str(s: &str) -> Result<Chord, ParseBindingError> {
+        // Try the whole string first so that names like "page up" work
+        if let Ok(key) = parse_key(s) {
+            return Ok(Chord::single(key));
+        }
+        let mut parts = s.split_whitespace();
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(prefix), Some(key), None) => Ok(Chord {
+                prefix: Some(parse_key(prefix)?),
+                key: parse_key(key)?,
+            }),
+            _ => Err(ParseBindingError::Key(s.to_owned())),
+        }
+    }
+}
+
+/// An assignment of an action to a key or chord, or the removal of its
+/// binding if `action` is `None`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Binding {
+    pub(crate) chord: Chord,
+    pub(crate) action: Option<Action>,
+}
+
+impl Binding {
+    /// Construct a binding from the name of a key or chord and the name of an
+    /// action (or `none`)
+    pub fn new(key: &str, action: &str) -> Result<Binding, ParseBindingError> {
+        let chord = key.parse::<Chord>()?;
+        let action = match action {
+            "none" => None,
+            a => Some(a.parse::<Action>()?),
+        };
+        Ok(Binding { chord, action })
+    }
+}
+
+impl FromStr for Binding {
+    type Err = ParseBindingError;
+
+    /// Parse a binding of the form `KEY=ACTION`
+    fn from_str(s: &str) -> Result<Binding, ParseBindingError> {
+        // Split at the last '=' so that '=' itself can be bound
+        let (key, action) = s.rsplit_once('=').ok_or(ParseBindingError::Format)?;
+        Binding::new(key, action)
+    }
+}
+
+/// Error returned when parsing an invalid [`Binding`]
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum ParseBindingError {
+    #[error("key binding must be of the form KEY=ACTION")]
+    Format,
+    #[error("unknown key {0:?}")]
+    Key(String),
+    #[error("unknown action {0:?}")]
+    Action(String),
+}
+
+/// A mapping from keys & chords to the actions they perform
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Keymap(HashMap<Chord, Action>);
+
+impl Keymap {
+    /// Returns the action bound to `key` on its own
+    pub(crate) fn get(&self, key: KeyCode) -> Option<Action> {
+        self.0.get(&Chord::single(key)).copied()
+    }
+
+    /// Returns the action bound to the chord of `prefix` followed by `key`
+    pub(crate) fn get_chord(&self, prefix: KeyCode, key: KeyCode) -> Option<Action> {
+        self.0
+            .get(&Chord {
+                prefix: Some(prefix),
+                key,
+            })
+            .copied()
+    }
+
+    /// Returns whether `key` begins any bound chords
+    pub(crate) fn is_prefix(&self, key: KeyCode) -> bool {
+        self.0.keys().any(|c| c.prefix == Some(key))
+    }
+
+    /// Returns the second keys of the chords beginning with `prefix` and the
+    /// actions they're bound to, with character keys first
+    pub(crate) fn completions(&self, prefix: KeyCode) -> Vec<(KeyCode, Action)> {
+        let mut completions = self
+            .0
+            .iter()
+            .filter(|(c, _)| c.prefix == Some(prefix))
+            .map(|(c, &a)| (c.key, a))
+            .collect::<Vec<_>>();
+        completions.sort_by_key(|&(k, _)| key_order(k));
+        completions
+    }
+
+    /// Bind `binding.chord` to `binding.action`, replacing any existing
+    /// binding for the chord.  As a key can't both do something on its own
+    /// and begin chords, binding a key on its own also removes the chords it
+    /// begins, and binding a chord removes its first key's own binding.
+    pub fn bind(&mut self, binding: Binding) {
+        let chord = binding.chord;
+        match chord.prefix {
+            Some(prefix) => {
+                self.0.remove(&Chord::single(prefix));
+            }
+            None => self.0.retain(|c, _| c.prefix != Some(chord.key)),
+        }
+        match binding.action {
+            Some(action) => self.0.insert(chord, action),
+            None => self.0.remove(&chord),
+        };
+    }
+
+    /// Returns the keys & chords bound to `action`, with single keys before
+    /// chords and character keys first
+    pub(crate) fn keys_for(&self, action: Action) -> Vec<Chord> {
+        let mut chords = self
+            .0
+            .iter()
+            .filter(|&(_, &a)| a == action)
+            .map(|(&c, _)| c)
+            .collect::<Vec<_>>();
+        chords.sort_by_key(|&c| (c.prefix.map(key_order), key_order(c.key)));
+        chords
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        let keys = [
+            (KeyCode::Char('j'), Action::ScrollDown),
+            (KeyCode::Down, Action::ScrollDown),
+            (KeyCode::Char('k'), Action::ScrollUp),
+            (KeyCode::Up, Action::ScrollUp),
+            (KeyCode::Char('z'), Action::PageDown),
+            (KeyCode::PageDown, Action::PageDown),
+            (KeyCode::Char('w'), Action::PageUp),
+            (KeyCode::PageUp, Action::PageUp),
+            (KeyCode::Char('0'), Action::Today),
+            (KeyCode::Home, Action::Today),
+            (KeyCode::Char('.'), Action::Recenter),
+            (KeyCode::Tab, Action::NextDate),
+            (KeyCode::BackTab, Action::PrevDate),
+            (KeyCode::Char('f'), Action::NextFullMoon),
+            (KeyCode::Char('F'), Action::PrevFullMoon),
+            (KeyCode::Char('n'), Action::NextNewMoon),
+            (KeyCode::Char('N'), Action::PrevNewMoon),
+            (KeyCode::Char('y'), Action::JumpToYear),
+            (KeyCode::Char('m'), Action::ChooseMonth),
+            (KeyCode::Char('g'), Action::FindMonth),
+            (KeyCode::Char(';'), Action::RepeatJump),
+            (KeyCode::Char(','), Action::ReverseJump),
+            (KeyCode::Char('Q'), Action::RecordMacro),
+            (KeyCode::Char('@'), Action::PlayMacro),
+            (KeyCode::Char('p'), Action::AnimatePhases),
+            (KeyCode::Char('c'), Action::CopyTable),
+            (KeyCode::Char('v'), Action::ListView),
+            (KeyCode::Char('b'), Action::MonthBorders),
+            (KeyCode::Char('i'), Action::Info),
+            (KeyCode::Char('l'), Action::Legend),
+            (KeyCode::Char('o'), Action::Options),
+            (KeyCode::Char('T'), Action::EditTheme),
+            (KeyCode::Char('?'), Action::Help),
+            (KeyCode::Char('q'), Action::Quit),
+            (KeyCode::Esc, Action::Quit),
+        ];
+        Keymap(
+            keys.into_iter()
+                .map(|(key, action)| (Chord::single(key), action))
+                .collect(),
+        )
+    }
+}
+
+impl<'a> Extend<&'a Binding> for Keymap {
+    fn extend<I: IntoIterator<Item = &'a Binding>>(&mut self, iter: I) {
+        for &b in iter {
+            self.bind(b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_binding() {
+        assert_eq!(
+            "space=page-down".parse::<Binding>(),
+            Ok(Binding {
+                chord: Chord::single(KeyCode::Char(' ')),
+                action: Some(Action::PageDown)
+            })
+        );
+        assert_eq!(
+            "==help".parse::<Binding>(),
+            Ok(Binding {
+                chord: Chord::single(KeyCode::Char('=')),
+                action: Some(Action::Help)
+            })
+        );
+        assert_eq!(
+            "PgDn=none".parse::<Binding>(),
+            Ok(Binding {
+                chord: Chord::single(KeyCode::PageDown),
+                action: None
+            })
+        );
+        assert_eq!(
+            "f13=quit".parse::<Binding>(),
+            Err(ParseBindingError::Key("f13".into()))
+        );
+        assert_eq!(
+            "x=explode".parse::<Binding>(),
+            Err(ParseBindingError::Action("explode".into()))
+        );
+        assert_eq!("quit".parse::<Binding>(), Err(ParseBindingError::Format));
+        assert_eq!(
+            "g  tab=today".parse::<Binding>(),
+            Ok(Binding {
+                chord: Chord {
+                    prefix: Some(KeyCode::Char('g')),
+                    key: KeyCode::Tab
+                },
+                action: Some(Action::Today)
+            })
+        );
+        assert_eq!(
+            "page up=none".parse::<Binding>().map(|b| b.chord),
+            Ok(Chord::single(KeyCode::PageUp))
+        );
+        assert_eq!(
+            "g g g=today".parse::<Binding>(),
+            Err(ParseBindingError::Key("g g g".into()))
+        );
+    }
+
+    #[test]
+    fn test_keymap() {
+        let mut keymap = Keymap::default();
+        keymap.extend(&[
+            "space=page-down".parse::<Binding>().unwrap(),
+            "q=none".parse::<Binding>().unwrap(),
+        ]);
+        assert_eq!(keymap.get(KeyCode::Char(' ')), Some(Action::PageDown));
+        assert_eq!(keymap.get(KeyCode::Char('q')), None);
+        assert_eq!(
+            keymap.keys_for(Action::PageDown),
+            [KeyCode::Char('z'), KeyCode::PageDown, KeyCode::Char(' ')].map(Chord::single)
+        );
+    }
+
+    #[test]
+    fn test_chords() {
+        let mut keymap = Keymap::default();
+        keymap.extend(&[
+            "g m=choose-month".parse::<Binding>().unwrap(),
+            "g y=jump-to-year".parse::<Binding>().unwrap(),
+            "g 0=today".parse::<Binding>().unwrap(),
+        ]);
+        assert_eq!(keymap.get(KeyCode::Char('g')), None);
+        assert!(keymap.is_prefix(KeyCode::Char('g')));
+        assert_eq!(
+            keymap.get_chord(KeyCode::Char('g'), KeyCode::Char('y')),
+            Some(Action::JumpToYear)
+        );
+        assert_eq!(
+            keymap.completions(KeyCode::Char('g')),
+            [
+                (KeyCode::Char('0'), Action::Today),
+                (KeyCode::Char('m'), Action::ChooseMonth),
+                (KeyCode::Char('y'), Action::JumpToYear),
+            ]
+        );
+        assert_eq!(
+            keymap
+                .keys_for(Action::Today)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["0", "HOME", "g 0"]
+        );
+        keymap.bind("g=find-month".parse().unwrap());
+        assert!(!keymap.is_prefix(KeyCode::Char('g')));
+        assert_eq!(keymap.get(KeyCode::Char('g')), Some(Action::FindMonth));
+    }
+}
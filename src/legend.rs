@@ -0,0 +1,67 @@
+use crate::popup::{Popup, PopupStyle};
+use crate::settings::Settings;
+use crate::theme::Theme;
+use ratatui::prelude::*;
+
+/// Popup explaining what each highlight in the calendar means, using the
+/// styles from the active theme.  Highlights for layers that are turned off
+/// are omitted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Legend {
+    pub(crate) theme: Theme,
+    pub(crate) settings: Settings,
+    pub(crate) style: PopupStyle,
+}
+
+impl Legend {
+    fn lines(&self) -> Vec<Line<'static>> {
+        let Legend {
+            theme, settings, ..
+        } = *self;
+        let today = theme.today;
+        let marker = today.patch(theme.today_marker);
+        let mut lines = vec![
+            entry(theme.full_moon, "Full moon"),
+            entry(theme.new_moon, "New moon"),
+            Line::from(vec![
+                Span::styled("[", marker),
+                Span::styled("13", today),
+                Span::styled("]", marker),
+                Span::raw(" Today"),
+            ]),
+            entry(theme.marked, "Date given on the command line"),
+            entry(
+                theme.full_moon.patch(theme.friday_13th),
+                "Full or new moon on Friday the 13th",
+            ),
+        ];
+        if settings.supermoons {
+            lines.push(entry(theme.supermoon, "Supermoon"));
+            lines.push(entry(theme.micromoon, "Micromoon"));
+        }
+        if settings.eclipses {
+            lines.push(entry(theme.eclipse, "Lunar eclipse"));
+        }
+        if settings.community_dates {
+            lines.push(entry(theme.community_date, "NetHack community event"));
+        }
+        lines.push(Line::raw(""));
+        lines.push(Line::raw("Press the Any Key to dismiss."));
+        lines
+    }
+}
+
+impl Widget for Legend {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Popup::new(" Legend ", self.lines(), self.style).render(area, buf);
+    }
+}
+
+/// A line showing a sample date in `style` followed by a description
+fn entry(style: Style, description: &'static str) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(" 13 ", style),
+        Span::raw(" "),
+        Span::raw(description),
+    ])
+}
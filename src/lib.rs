@@ -0,0 +1,62 @@
+//! `nhmoon` is primarily a program for viewing a scrollable calendar in the
+//! terminal with NetHack's new & full moons highlighted.  This library exposes
+//! the program's moon phase calculations for use by other tools, along with
+//! [`AppBuilder`] for embedding the calendar pager in other terminal programs.
+mod animation;
+mod app;
+mod astro;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod calendar;
+mod calendars;
+mod cli;
+mod community;
+mod compare;
+mod config;
+mod control;
+mod crash;
+mod datefmt;
+mod day_list;
+mod dirs;
+mod dump;
+mod eclipse;
+mod feedback;
+mod footer;
+mod help;
+mod info;
+mod json;
+mod julian;
+mod keymap;
+mod legend;
+mod locale;
+mod luck;
+mod lunation;
+mod markdown;
+mod month_menu;
+mod moon;
+mod options;
+mod options_menu;
+mod popup;
+mod script;
+mod session;
+mod settings;
+mod shade;
+mod status;
+mod supermoon;
+mod term;
+mod theme;
+mod theme_editor;
+pub use crate::app::AppBuilder;
+pub use crate::calendar::{CachedStyler, DateStyler, MonthCalendar, MonthState};
+pub use crate::keymap::{Action, Binding, Chord, Keymap, ParseBindingError};
+pub use crate::moon::{
+    next_phase, phases_in, previous_phase, Conway, LunarPhase, Meeus, NetHack, Phase,
+    PhaseCalculator, PhasesIn,
+};
+use time::{format_description::FormatItem, macros::format_description};
+
+#[doc(hidden)]
+pub use crate::cli::main;
+
+pub(crate) static YMD_FMT: &[FormatItem<'_>] = format_description!("[year]-[month]-[day]");
@@ -0,0 +1,77 @@
+//! Deriving defaults from the user's locale
+use std::env;
+use time::Weekday;
+
+/// Regions whose weeks don't start on Monday, grouped by the day on which
+/// they do start, per the `weekData` in CLDR's supplemental data
+const FIRST_DAYS: [(Weekday, &[&str]); 3] = [
+    (Weekday::Friday, &["MV"]),
+    (
+        Weekday::Saturday,
+        &[
+            "AE", "AF", "BH", "DJ", "DZ", "EG", "IQ", "IR", "JO", "KW", "LY", "OM", "QA", "SD",
+            "SY",
+        ],
+    ),
+    (
+        Weekday::Sunday,
+        &[
+            "AG", "AS", "BD", "BR", "BS", "BT", "BW", "BZ", "CA", "CO", "DM", "DO", "ET", "GT",
+            "GU", "HK", "HN", "ID", "IL", "IN", "JM", "JP", "KE", "KH", "KR", "LA", "MH", "MM",
+            "MO", "MT", "MX", "MZ", "NI", "NP", "PA", "PE", "PH", "PK", "PR", "PT", "PY", "SA",
+            "SG", "SV", "TH", "TT", "TW", "UM", "US", "VE", "VI", "WS", "YE", "ZA", "ZW",
+        ],
+    ),
+];
+
+/// Returns the first day of the week in the locale used for formatting dates,
+/// as set by `LC_ALL`, `LC_TIME`, or `LANG` (in that order of precedence), or
+/// `None` if the locale is unset or doesn't specify a region
+pub(crate) fn week_start() -> Option<Weekday> {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .into_iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| first_day(&value))
+}
+
+/// Returns the first day of the week for a POSIX locale name like
+/// `en_GB.UTF-8` (or a BCP 47 tag like `en-GB`), based on its region
+fn first_day(locale: &str) -> Option<Weekday> {
+    let name = locale.split(['.', '@']).next().unwrap_or_default();
+    let region = name
+        .split(['_', '-'])
+        .skip(1)
+        .find(|subtag| subtag.len() == 2 && subtag.bytes().all(|b| b.is_ascii_alphabetic()))?;
+    let region = region.to_ascii_uppercase();
+    Some(
+        FIRST_DAYS
+            .iter()
+            .find(|(_, regions)| regions.contains(&region.as_str()))
+            .map_or(Weekday::Monday, |&(wd, _)| wd),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_day() {
+        for (locale, wd) in [
+            ("en_US.UTF-8", Some(Weekday::Sunday)),
+            ("en_GB.UTF-8", Some(Weekday::Monday)),
+            ("de_DE@euro", Some(Weekday::Monday)),
+            ("ar_EG", Some(Weekday::Saturday)),
+            ("dv_MV.UTF-8", Some(Weekday::Friday)),
+            ("sr_Latn_RS", Some(Weekday::Monday)),
+            ("pt-br", Some(Weekday::Sunday)),
+            ("es_419", None),
+            ("en", None),
+            ("C.UTF-8", None),
+            ("POSIX", None),
+        ] {
+            assert_eq!(first_day(locale), wd, "{locale}");
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! The effects of the date on a game of NetHack
+use crate::calendar::DateStyler;
+use crate::json::json_string;
+use crate::moon::{NetHack, Phase, PhaseCalculator};
+use ratatui::style::Style;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+use thiserror::Error;
+use time::{Date, Month, Weekday};
+
+static FULL_MOON_MESSAGE: &str = "You are lucky!  Full moon tonight.";
+static NEW_MOON_MESSAGE: &str = "Be careful!  New moon tonight.";
+static FRIDAY_13TH_MESSAGE: &str = "Watch out!  Bad things can happen on Friday the 13th.";
+
+/// The luck-related circumstances in effect for a game of NetHack started or
+/// restored on a given date
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Luck {
+    pub(crate) date: Date,
+    pub(crate) phase: Phase,
+    pub(crate) friday_13th: bool,
+    /// The first day of the next full moon after `date`
+    pub(crate) next_full_moon: Option<Date>,
+    /// The first day of the next new moon after `date`
+    pub(crate) next_new_moon: Option<Date>,
+}
+
+impl Luck {
+    pub(crate) fn for_date(date: Date) -> Luck {
+        Luck {
+            date,
+            phase: NetHack.phase(date),
+            friday_13th: is_friday_13th(date),
+            next_full_moon: NetHack.next_phase(date, Phase::Full),
+            next_new_moon: NetHack.next_phase(date, Phase::New),
+        }
+    }
+
+    /// The change in Luck applied when a game is started or restored.  This
+    /// is also the player's base Luck, towards which Luck times out.
+    pub(crate) fn luck(&self) -> i32 {
+        let mut luck = 0;
+        if self.phase == Phase::Full {
+            luck += 1;
+        }
+        if self.friday_13th {
+            luck -= 1;
+        }
+        luck
+    }
+
+    /// The messages that the game prints on starting or restoring
+    pub(crate) fn messages(&self) -> Vec<&'static str> {
+        let mut messages = Vec::new();
+        match self.phase {
+            Phase::Full => messages.push(FULL_MOON_MESSAGE),
+            Phase::New => messages.push(NEW_MOON_MESSAGE),
+            Phase::Normal => (),
+        }
+        if self.friday_13th {
+            messages.push(FRIDAY_13TH_MESSAGE);
+        }
+        messages
+    }
+
+    /// Descriptions of the gameplay effects in force
+    pub(crate) fn effects(&self) -> Vec<&'static str> {
+        let mut effects = Vec::new();
+        match self.phase {
+            Phase::Full => effects.push("Luck +1, and Luck times out towards +1 instead of 0"),
+            Phase::New => effects.push(
+                "A cockatrice's hissing always starts petrification unless you carry a lizard corpse",
+            ),
+            Phase::Normal => (),
+        }
+        if self.friday_13th {
+            effects.push("Luck -1, and Luck times out towards 1 less than it otherwise would");
+        }
+        effects
+    }
+
+    pub(crate) fn to_json(self) -> String {
+        let phase = match self.phase {
+            Phase::Normal => "normal",
+            Phase::Full => "full",
+            Phase::New => "new",
+        };
+        let mut s = String::from("{");
+        let _ = write!(
+            s,
+            r#""date": {}, "phase": {}, "friday_13th": {}, "luck": {}"#,
+            json_string(&self.date.to_string()),
+            json_string(phase),
+            self.friday_13th,
+            self.luck(),
+        );
+        for (key, values) in [("messages", self.messages()), ("effects", self.effects())] {
+            let values = values.into_iter().map(json_string).collect::<Vec<_>>();
+            let _ = write!(s, r#", "{key}": [{}]"#, values.join(", "));
+        }
+        for (key, date) in [
+            ("next_full_moon", self.next_full_moon),
+            ("next_new_moon", self.next_new_moon),
+        ] {
+            let value = date.map_or_else(|| String::from("null"), |d| json_string(&d.to_string()));
+            let _ = write!(s, r#", "{key}": {value}"#);
+        }
+        s.push('}');
+        s
+    }
+
+    /// Returns a one-row table in GitHub-flavored Markdown summarizing the
+    /// luck for `date`
+    pub(crate) fn to_markdown(self) -> String {
+        let phase = match self.phase {
+            Phase::Normal => "normal",
+            Phase::Full => "full moon",
+            Phase::New => "new moon",
+        };
+        let date_cell = |date: Option<Date>| {
+            date.map_or_else(|| String::from("—"), |d| format!("{d} ({})", d.weekday()))
+        };
+        format!(
+            concat!(
+                "| Date | Phase | Friday the 13th | Luck | Next full moon | Next new moon |\n",
+                "| --- | --- | --- | --: | --- | --- |\n",
+                "| {} ({}) | {} | {} | {:+} | {} | {} |\n",
+            ),
+            self.date,
+            self.date.weekday(),
+            phase,
+            if self.friday_13th { "yes" } else { "no" },
+            self.luck(),
+            date_cell(self.next_full_moon),
+            date_cell(self.next_new_moon),
+        )
+    }
+}
+
+/// The formats in which `nhmoon luck` can output its information
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum LuckFormat {
+    #[default]
+    Text,
+    Json,
+    Markdown,
+}
+
+impl LuckFormat {
+    pub(crate) const ALL: [LuckFormat; 3] =
+        [LuckFormat::Text, LuckFormat::Json, LuckFormat::Markdown];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            LuckFormat::Text => "text",
+            LuckFormat::Json => "json",
+            LuckFormat::Markdown => "markdown",
+        }
+    }
+
+    /// Render `luck` in this format
+    pub(crate) fn render(self, luck: Luck) -> String {
+        match self {
+            LuckFormat::Text => luck.to_string(),
+            LuckFormat::Json => format!("{}\n", luck.to_json()),
+            LuckFormat::Markdown => luck.to_markdown(),
+        }
+    }
+}
+
+impl FromStr for LuckFormat {
+    type Err = ParseLuckFormatError;
+
+    fn from_str(s: &str) -> Result<LuckFormat, ParseLuckFormatError> {
+        LuckFormat::ALL
+            .into_iter()
+            .find(|fmt| fmt.name().eq_ignore_ascii_case(s))
+            .ok_or(ParseLuckFormatError)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("format must be \"text\", \"json\", or \"markdown\"")]
+pub(crate) struct ParseLuckFormatError;
+
+impl fmt::Display for Luck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({})", self.date, self.date.weekday())?;
+        let messages = self.messages();
+        if messages.is_empty() {
+            writeln!(f, "No luck modifiers are in effect.")?;
+        } else {
+            for msg in messages {
+                writeln!(f, "  {msg}")?;
+            }
+            writeln!(f)?;
+            for effect in self.effects() {
+                writeln!(f, "- {effect}")?;
+            }
+        }
+        for (label, date) in [("full", self.next_full_moon), ("new", self.next_new_moon)] {
+            if let Some(d) = date {
+                writeln!(f, "Next {label} moon: {d} ({})", d.weekday())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn is_friday_13th(date: Date) -> bool {
+    date.weekday() == Weekday::Friday && date.day() == 13
+}
+
+/// Returns the first Friday the 13th after `after`, or `None` if there is no
+/// such date before the end of time
+pub(crate) fn next_friday_13th(after: Date) -> Option<Date> {
+    let mut date = after.replace_day(13).ok()?;
+    while date <= after || !is_friday_13th(date) {
+        let (year, month) = match date.month() {
+            Month::December => (date.year().checked_add(1)?, Month::January),
+            m => (date.year(), m.next()),
+        };
+        date = Date::from_calendar_date(year, month, 13).ok()?;
+    }
+    Some(date)
+}
+
+/// A `DateStyler` that applies `style` to the rare dates on which a full or
+/// new moon falls on a Friday the 13th, as determined by the given
+/// `PhaseCalculator`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Friday13th<P> {
+    pub(crate) phases: P,
+    pub(crate) style: Style,
+}
+
+impl<P: PhaseCalculator> DateStyler for Friday13th<P> {
+    fn date_style(&self, date: Date) -> Style {
+        if is_friday_13th(date) && self.phases.phase(date) != Phase::Normal {
+            self.style
+        } else {
+            Style::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    #[test]
+    fn test_next_friday_13th() {
+        assert_eq!(
+            next_friday_13th(date!(2023 - 10 - 12)),
+            Some(date!(2023 - 10 - 13))
+        );
+        assert_eq!(
+            next_friday_13th(date!(2023 - 10 - 13)),
+            Some(date!(2024 - 09 - 13))
+        );
+        assert_eq!(
+            next_friday_13th(date!(2024 - 09 - 30)),
+            Some(date!(2024 - 12 - 13))
+        );
+    }
+
+    #[test]
+    fn test_full_moon() {
+        let luck = Luck::for_date(date!(2023 - 11 - 27));
+        assert_eq!(luck.luck(), 1);
+        assert_eq!(luck.messages(), [FULL_MOON_MESSAGE]);
+    }
+
+    #[test]
+    fn test_friday_13th_new_moon() {
+        let luck = Luck::for_date(date!(2023 - 10 - 13));
+        assert_eq!(luck.phase, Phase::New);
+        assert!(luck.friday_13th);
+        assert_eq!(luck.luck(), -1);
+        assert_eq!(luck.messages(), [NEW_MOON_MESSAGE, FRIDAY_13TH_MESSAGE]);
+    }
+
+    #[test]
+    fn test_json() {
+        let luck = Luck::for_date(date!(2023 - 11 - 20));
+        assert_eq!(
+            luck.to_json(),
+            r#"{"date": "2023-11-20", "phase": "normal", "friday_13th": false, "luck": 0, "messages": [], "effects": [], "next_full_moon": "2023-11-27", "next_new_moon": "2023-12-11"}"#
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        let luck = Luck::for_date(date!(2023 - 11 - 27));
+        assert_eq!(
+            luck.to_markdown(),
+            concat!(
+                "| Date | Phase | Friday the 13th | Luck | Next full moon | Next new moon |\n",
+                "| --- | --- | --- | --: | --- | --- |\n",
+                "| 2023-11-27 (Monday) | full moon | no | +1 | 2023-12-26 (Tuesday) | 2023-12-11 (Monday) |\n",
+            )
+        );
+        assert_eq!("Markdown".parse::<LuckFormat>(), Ok(LuckFormat::Markdown));
+        assert_eq!("csv".parse::<LuckFormat>(), Err(ParseLuckFormatError));
+    }
+}
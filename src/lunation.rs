@@ -0,0 +1,142 @@
+//! Times of full moons, computed using the methods from chapters 49 and 54 of
+//! Jean Meeus's *Astronomical Algorithms*
+use crate::astro::julian_midnight;
+use time::{Date, Duration, Time, UtcOffset};
+
+/// Mean number of lunations per Julian year
+const LUNATIONS_PER_YEAR: f64 = 12.3685;
+
+/// The fundamental arguments (in degrees) for the lunation with number `k`.
+/// Lunations are numbered from the new moon of 2000 January 6, and full moons
+/// have lunation numbers ending in .5.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Lunation {
+    pub(crate) k: f64,
+    /// Eccentricity factor of the Earth's orbit
+    pub(crate) e: f64,
+    /// Sun's mean anomaly
+    pub(crate) m: f64,
+    /// Moon's mean anomaly
+    pub(crate) mp: f64,
+    /// Moon's argument of latitude
+    pub(crate) f: f64,
+    /// Longitude of the ascending node of the Moon's orbit
+    pub(crate) omega: f64,
+    /// Julian Ephemeris Day of the mean phase
+    pub(crate) mean_jde: f64,
+}
+
+impl Lunation {
+    pub(crate) fn new(k: f64) -> Lunation {
+        let t = k / 1236.85;
+        Lunation {
+            k,
+            e: (-0.002_516_f64).mul_add(t, 1.0),
+            m: poly(t, &[0.0, 0.0, -0.000_001_4, -0.000_000_11])
+                + 29.105_356_7_f64.mul_add(k, 2.5534),
+            mp: poly(t, &[0.0, 0.0, 0.010_758_2, 0.000_012_38, -0.000_000_058])
+                + 385.816_935_28_f64.mul_add(k, 201.5643),
+            f: poly(t, &[0.0, 0.0, -0.001_611_8, -0.000_002_27, 0.000_000_011])
+                + 390.670_502_84_f64.mul_add(k, 160.7108),
+            omega: poly(t, &[0.0, 0.0, 0.002_067_2, 0.000_002_15])
+                + (-1.563_755_88_f64).mul_add(k, 124.7746),
+            mean_jde: poly(
+                t,
+                &[0.0, 0.0, 0.000_154_37, -0.000_000_150, 0.000_000_000_73],
+            ) + 29.530_588_861_f64.mul_add(k, 2_451_550.097_66),
+        }
+    }
+
+    /// Returns the full moons whose lunation numbers are close enough to
+    /// `date` that one of them may fall on it
+    pub(crate) fn full_moons_near(date: Date) -> [Lunation; 3] {
+        let year = f64::from(date.year()) + (f64::from(date.ordinal()) - 0.5) / 365.25;
+        let k = (year - 2000.0).mul_add(LUNATIONS_PER_YEAR, -0.5).round() + 0.5;
+        [k - 1.0, k, k + 1.0].map(Lunation::new)
+    }
+
+    /// Returns the Julian date (UT) of the true full moon for this lunation
+    pub(crate) fn full_moon(&self) -> f64 {
+        let Lunation {
+            e, m, mp, f, omega, ..
+        } = *self;
+        let (m2, mp2, f2) = (2.0 * m, 2.0 * mp, 2.0 * f);
+        let jde = self.mean_jde
+            + sin_series(&[
+                (-0.40614, mp),
+                (0.17302 * e, m),
+                (0.01614, mp2),
+                (0.01043, f2),
+                (0.00734 * e, mp - m),
+                (-0.00515 * e, mp + m),
+                (0.00209 * e * e, m2),
+                (-0.00111, mp - f2),
+                (-0.00057, mp + f2),
+                (0.00056 * e, mp2 + m),
+                (-0.00042, 3.0 * mp),
+                (0.00042 * e, m + f2),
+                (0.00038 * e, m - f2),
+                (-0.00024 * e, mp2 - m),
+                (-0.00017, omega),
+            ]);
+        jde - self.delta_t() / 86400.0
+    }
+
+    /// Approximate difference in seconds between Terrestrial Time and
+    /// Universal Time around the time of this lunation, using the long-term
+    /// parabola of Morrison & Stephenson
+    pub(crate) fn delta_t(&self) -> f64 {
+        let centuries = (2000.0 + self.k / LUNATIONS_PER_YEAR - 1820.0) / 100.0;
+        (32.0 * centuries).mul_add(centuries, -20.0)
+    }
+}
+
+/// Returns the local time on `date` at which Julian date `jd` (UT) occurs in
+/// the timezone given by `offset`, or `None` if `jd` is not on `date`
+pub(crate) fn local_time_on(jd: f64, date: Date, offset: UtcOffset) -> Option<Time> {
+    let since_midnight = jd - julian_midnight(date, offset);
+    (0.0..1.0)
+        .contains(&since_midnight)
+        .then(|| Time::MIDNIGHT + Duration::seconds_f64(since_midnight * 86400.0))
+}
+
+/// Evaluate the polynomial with the given coefficients (in order of increasing
+/// degree) at `x`
+pub(crate) fn poly(x: f64, coefficients: &[f64]) -> f64 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0.0, |acc, &c| acc.mul_add(x, c))
+}
+
+/// Sum the terms `coefficient * sin(angle)` with angles in degrees
+pub(crate) fn sin_series(terms: &[(f64, f64)]) -> f64 {
+    terms
+        .iter()
+        .map(|&(c, angle)| c * angle.to_radians().sin())
+        .sum()
+}
+
+/// Sum the terms `coefficient * cos(angle)` with angles in degrees
+pub(crate) fn cos_series(terms: &[(f64, f64)]) -> f64 {
+    terms
+        .iter()
+        .map(|&(c, angle)| c * angle.to_radians().cos())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, offset, time};
+
+    #[test]
+    fn test_full_moon() {
+        // The full moon of 2023 November was at 09:16 UTC on the 27th.
+        let when = Lunation::full_moons_near(date!(2023 - 11 - 27))
+            .into_iter()
+            .find_map(|lun| local_time_on(lun.full_moon(), date!(2023 - 11 - 27), offset!(UTC)))
+            .unwrap();
+        assert!((when - time!(09:16)).abs() < Duration::minutes(5));
+    }
+}
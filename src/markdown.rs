@@ -0,0 +1,57 @@
+//! Tables of dates and their phases of the moon in GitHub-flavored Markdown
+use crate::calendar::YearNumbering;
+use crate::datefmt::DateFormat;
+use crate::moon::PhaseCalculator;
+use std::fmt::Write;
+use std::iter::successors;
+use time::Date;
+
+/// Returns a Markdown table listing each date from `start` through `end`
+/// along with its weekday and its phase of the moon as computed by `phases`
+pub(crate) fn phase_table<P: PhaseCalculator>(
+    phases: &P,
+    start: Date,
+    end: Date,
+    date_format: DateFormat,
+    year_numbering: YearNumbering,
+) -> String {
+    let mut s = String::from("| Date | Weekday | Phase |\n| --- | --- | --- |\n");
+    for date in successors(Some(start), |d| d.next_day()).take_while(|&d| d <= end) {
+        // Writing to a String can't fail
+        let _ = writeln!(
+            s,
+            "| {} | {} | {} |",
+            date_format.format(date, year_numbering),
+            date.weekday(),
+            phases.lunar_phase(date).name()
+        );
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moon::NetHack;
+    use time::macros::date;
+
+    #[test]
+    fn test_phase_table() {
+        assert_eq!(
+            phase_table(
+                &NetHack,
+                date!(2023 - 11 - 26),
+                date!(2023 - 11 - 28),
+                DateFormat::default(),
+                YearNumbering::default(),
+            ),
+            concat!(
+                "| Date | Weekday | Phase |\n",
+                "| --- | --- | --- |\n",
+                "| 2023-11-26 | Sunday | waxing gibbous |\n",
+                "| 2023-11-27 | Monday | full moon |\n",
+                "| 2023-11-28 | Tuesday | full moon |\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use crate::calendar::YearNumbering;
+use crate::popup::{Popup, PopupStyle};
+use ratatui::prelude::*;
+use time::Month;
+
+/// The keys that select each month in the month menu, in order
+pub(crate) const MONTH_KEYS: [char; 12] =
+    ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l'];
+
+/// Popup listing the months of `year`, with the one at index `selected`
+/// highlighted
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct MonthMenu {
+    pub(crate) year: i32,
+    pub(crate) selected: usize,
+    pub(crate) year_numbering: YearNumbering,
+    pub(crate) style: PopupStyle,
+}
+
+impl Widget for MonthMenu {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut month = Month::January;
+        let mut lines = MONTH_KEYS
+            .into_iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let text = format!("{key}  {month:<9}");
+                month = month.next();
+                if i == self.selected {
+                    Line::styled(text, Style::new().reversed())
+                } else {
+                    Line::raw(text)
+                }
+            })
+            .collect::<Vec<_>>();
+        lines.push(Line::raw(""));
+        lines.push(Line::raw("a-l: jump to month"));
+        lines.push(Line::raw("UP/DOWN: select    ENTER: jump"));
+        lines.push(Line::raw("m, ESC: close"));
+        let title = match self.year_numbering.split(self.year) {
+            (year, Some(era)) => format!(" {year} {era} "),
+            (year, None) => format!(" {year} "),
+        };
+        Popup::new(&title, lines, self.style).render(area, buf);
+    }
+}
+
+/// Interpret a partially-typed month like "mar 27", "March 2027", or "2027-03"
+/// as the month it most plausibly refers to.  A month name may be abbreviated
+/// to any prefix (with ties going to the earlier month), a year of one or two
+/// digits is taken to be in the same century as `current_year`, and a
+/// missing year means `current_year`.  A lone number of one or two digits is
+/// taken to be a month of `current_year`.
+pub(crate) fn parse_month_query(query: &str, current_year: i32) -> Option<(i32, Month)> {
+    let mut name = None;
+    let mut numbers = Vec::new();
+    for token in query
+        .split(|c: char| c.is_whitespace() || matches!(c, '-' | '/' | '.' | ','))
+        .filter(|t| !t.is_empty())
+    {
+        if token.bytes().all(|b| b.is_ascii_digit()) {
+            numbers.push(token);
+        } else if name.is_none() && token.chars().all(char::is_alphabetic) {
+            name = Some(token);
+        } else {
+            return None;
+        }
+    }
+    let (month, year) = match (name, numbers.as_slice()) {
+        (Some(name), []) => (month_named(name)?, None),
+        (Some(name), &[year]) => (month_named(name)?, Some(year)),
+        (None, &[n]) if n.len() <= 2 => (month_numbered(n)?, None),
+        (None, &[year]) => (Month::January, Some(year)),
+        (None, &[a, b]) if a.len() <= 2 && b.len() > 2 => (month_numbered(a)?, Some(b)),
+        (None, &[year, m]) => (month_numbered(m)?, Some(year)),
+        _ => return None,
+    };
+    let year = match year {
+        None => current_year,
+        Some(y) if y.len() <= 2 => {
+            let century = current_year - current_year.rem_euclid(100);
+            century + y.parse::<i32>().ok()?
+        }
+        Some(y) => y.parse::<i32>().ok()?,
+    };
+    Some((year, month))
+}
+
+/// Returns the first month whose name starts with `prefix`, ignoring case
+fn month_named(prefix: &str) -> Option<Month> {
+    let prefix = prefix.to_lowercase();
+    let mut month = Month::January;
+    for _ in 0..12 {
+        if month.to_string().to_lowercase().starts_with(&prefix) {
+            return Some(month);
+        }
+        month = month.next();
+    }
+    None
+}
+
+fn month_numbered(n: &str) -> Option<Month> {
+    Month::try_from(n.parse::<u8>().ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_month_query() {
+        for (query, expected) in [
+            ("mar 27", Some((2027, Month::March))),
+            ("March 2027", Some((2027, Month::March))),
+            ("2027-03", Some((2027, Month::March))),
+            ("2027/3", Some((2027, Month::March))),
+            ("03/2027", Some((2027, Month::March))),
+            ("27 mar", Some((2027, Month::March))),
+            ("ma", Some((2024, Month::March))),
+            ("may", Some((2024, Month::May))),
+            ("j", Some((2024, Month::January))),
+            ("jul 1969", Some((1969, Month::July))),
+            ("sep 5", Some((2005, Month::September))),
+            ("11", Some((2024, Month::November))),
+            ("1969", Some((1969, Month::January))),
+            ("13", None),
+            ("2027-13", None),
+            ("smarch", None),
+            ("mar apr", None),
+            ("mar 1 2", None),
+            ("", None),
+        ] {
+            assert_eq!(parse_month_query(query, 2024), expected, "{query:?}");
+        }
+    }
+}
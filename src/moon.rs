@@ -1,42 +1,550 @@
+use crate::astro::{elongation, julian_midnight};
 use crate::calendar::DateStyler;
-use ratatui::style::{Style, Stylize};
-use time::Date;
+use ratatui::style::Style;
+use std::iter::{successors, FusedIterator};
+use std::ops::{Bound, RangeBounds};
+use std::str::FromStr;
+use thiserror::Error;
+use time::{Date, UtcOffset};
 
+/// The classification of a date's moon phase that matters to NetHack's luck
+/// calculations
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
-enum Phase {
+pub enum Phase {
     Normal,
     Full,
     New,
 }
 
-impl Phase {
-    fn for_date(date: Date) -> Phase {
+/// A method of determining the phase of the moon on a given date
+pub trait PhaseCalculator {
+    /// Returns the phase of the moon on `date`
+    fn lunar_phase(&self, date: Date) -> LunarPhase;
+
+    /// Returns whether `date` is a full moon, new moon, or neither
+    fn phase(&self, date: Date) -> Phase {
+        match self.lunar_phase(date) {
+            LunarPhase::New => Phase::New,
+            LunarPhase::Full => Phase::Full,
+            _ => Phase::Normal,
+        }
+    }
+
+    /// Returns the first day of the next run of consecutive days with phase
+    /// `phase` that starts after `after`, or `None` if there is no such run
+    /// before the end of time
+    fn next_phase(&self, after: Date, phase: Phase) -> Option<Date> {
+        let mut prev = self.phase(after);
+        for date in successors(after.next_day(), |d| d.next_day()) {
+            let p = self.phase(date);
+            if p == phase && prev != phase {
+                return Some(date);
+            }
+            prev = p;
+        }
+        None
+    }
+
+    /// Returns the first day of the last run of consecutive days with phase
+    /// `phase` that starts before `before`, or `None` if there is no such run
+    /// after the start of time
+    fn previous_phase(&self, before: Date, phase: Phase) -> Option<Date> {
+        let mut start = None;
+        for date in successors(before.previous_day(), |d| d.previous_day()) {
+            if self.phase(date) == phase {
+                start = Some(date);
+            } else if start.is_some() {
+                break;
+            }
+        }
+        start
+    }
+}
+
+/// The algorithm used by NetHack's `phase_of_the_moon()`, which is the same in
+/// every version of the game from 3.4 through 3.7
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct NetHack;
+
+impl PhaseCalculator for NetHack {
+    fn lunar_phase(&self, date: Date) -> LunarPhase {
+        // NetHack's phase depends only on the year's position in the 19-year
+        // Metonic cycle and on the day of the year, so every possible result
+        // is precomputed by `build.rs`.
+        //
         // This is inaccurate for 2,147,481,750 BC and earlier, but I don't
         // think the `time` library is going to be supporting dates that old
         // any time soon.
-        let year = date.year().saturating_sub(1900);
-        let goldn = (year % 19) + 1;
-        let mut epact = (11 * goldn + 18) % 30;
-        if (epact == 25 && goldn > 11) || epact == 24 {
-            epact += 1;
-        }
-        match (((((i32::from(date.ordinal()) - 1 + epact) * 6) + 11) % 177) / 22) & 7 {
-            0 => Phase::New,
-            4 => Phase::Full,
-            _ => Phase::Normal,
+        let cycle = usize::try_from(date.year().saturating_sub(1900) % 19 + 18).unwrap_or_default();
+        let day = usize::from(date.ordinal() - 1);
+        LunarPhase::from_index(i32::from(NETHACK_PHASES[cycle][day]))
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/nethack_phases.rs"));
+
+/// John Conway's mental-arithmetic approximation of the moon's age.  It is
+/// intended for the 20th and 21st centuries and drifts further from the true
+/// phase the further one strays from them.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Conway;
+
+impl PhaseCalculator for Conway {
+    fn lunar_phase(&self, date: Date) -> LunarPhase {
+        let mut r = date.year().rem_euclid(100) % 19;
+        if r > 9 {
+            r -= 19;
         }
+        r = (r * 11).rem_euclid(30) + i32::from(u8::from(date.month())) + i32::from(date.day());
+        if u8::from(date.month()) < 3 {
+            r += 2;
+        }
+        // Conway subtracts 4 for the 1900s and 8.3 for the 2000s; work in
+        // tenths of a day so as to round the result to the nearest day.
+        let tenths = r * 10 - if date.year() < 2000 { 40 } else { 83 };
+        let age = (tenths + 5).div_euclid(10).rem_euclid(30);
+        LunarPhase::from_age(age)
+    }
+}
+
+/// Astronomical phases computed from the positions of the Sun and Moon, in
+/// the manner of Jean Meeus's *Astronomical Algorithms*.  A date is given the
+/// phase of a new moon, first quarter, full moon, or last quarter only if the
+/// instant of that phase occurs during it in the timezone given by the
+/// contained offset.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Meeus(pub UtcOffset);
+
+impl PhaseCalculator for Meeus {
+    fn lunar_phase(&self, date: Date) -> LunarPhase {
+        let midnight = julian_midnight(date, self.0);
+        let start = elongation(midnight);
+        let change = (elongation(midnight + 1.0) - start).rem_euclid(360.0);
+        for (target, phase) in [
+            (0.0, LunarPhase::New),
+            (90.0, LunarPhase::FirstQuarter),
+            (180.0, LunarPhase::Full),
+            (270.0, LunarPhase::LastQuarter),
+        ] {
+            if (target - start).rem_euclid(360.0) < change {
+                return phase;
+            }
+        }
+        if start < 90.0 {
+            LunarPhase::WaxingCrescent
+        } else if start < 180.0 {
+            LunarPhase::WaxingGibbous
+        } else if start < 270.0 {
+            LunarPhase::WaningGibbous
+        } else {
+            LunarPhase::WaningCrescent
+        }
+    }
+}
+
+/// Returns an iterator over the dates in `range`, in order, paired with their
+/// phases as computed by the current version of NetHack.  Use
+/// [`PhasesIn::new()`] to compute the phases with a different
+/// `PhaseCalculator`.
+///
+/// Unbounded ends of `range` extend to the earliest or latest date supported
+/// by the `time` crate.
+pub fn phases_in<R: RangeBounds<Date>>(range: R) -> PhasesIn<NetHack> {
+    PhasesIn::new(NetHack, range)
+}
+
+/// Returns the first day of the next full moon, new moon, or stretch of
+/// neither (depending on `phase`) that starts after `after`, as computed by
+/// the current version of NetHack.  Use [`PhaseCalculator::next_phase()`] to
+/// compute phases with a different `PhaseCalculator`.
+pub fn next_phase(after: Date, phase: Phase) -> Option<Date> {
+    NetHack.next_phase(after, phase)
+}
+
+/// Returns the first day of the last full moon, new moon, or stretch of
+/// neither (depending on `phase`) that starts before `before`, as computed by
+/// the current version of NetHack.  Use [`PhaseCalculator::previous_phase()`]
+/// to compute phases with a different `PhaseCalculator`.
+pub fn previous_phase(before: Date, phase: Phase) -> Option<Date> {
+    NetHack.previous_phase(before, phase)
+}
+
+/// An iterator over a range of dates paired with their phases.  Created by
+/// [`phases_in()`] or [`PhasesIn::new()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PhasesIn<P> {
+    calculator: P,
+    /// The remaining dates, as an inclusive range, or `None` if exhausted
+    dates: Option<(Date, Date)>,
+}
+
+impl<P: PhaseCalculator> PhasesIn<P> {
+    /// Iterate over the dates in `range` paired with their phases as computed
+    /// by `calculator`
+    pub fn new<R: RangeBounds<Date>>(calculator: P, range: R) -> Self {
+        let start = match range.start_bound() {
+            Bound::Included(&d) => Some(d),
+            Bound::Excluded(&d) => d.next_day(),
+            Bound::Unbounded => Some(Date::MIN),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&d) => Some(d),
+            Bound::Excluded(&d) => d.previous_day(),
+            Bound::Unbounded => Some(Date::MAX),
+        };
+        let dates = start.zip(end).filter(|(start, end)| start <= end);
+        PhasesIn { calculator, dates }
+    }
+}
+
+impl<P: PhaseCalculator> Iterator for PhasesIn<P> {
+    type Item = (Date, Phase);
+
+    fn next(&mut self) -> Option<(Date, Phase)> {
+        let (start, end) = self.dates?;
+        self.dates = start
+            .next_day()
+            .map(|d| (d, end))
+            .filter(|&(d, end)| d <= end);
+        Some((start, self.calculator.phase(start)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.dates.map_or(0, |(start, end)| {
+            usize::try_from(end.to_julian_day() - start.to_julian_day() + 1).unwrap_or(usize::MAX)
+        });
+        (len, Some(len))
+    }
+}
+
+impl<P: PhaseCalculator> DoubleEndedIterator for PhasesIn<P> {
+    fn next_back(&mut self) -> Option<(Date, Phase)> {
+        let (start, end) = self.dates?;
+        self.dates = end
+            .previous_day()
+            .map(|d| (start, d))
+            .filter(|&(start, d)| start <= d);
+        Some((end, self.calculator.phase(end)))
+    }
+}
+
+impl<P: PhaseCalculator> ExactSizeIterator for PhasesIn<P> {}
+
+impl<P: PhaseCalculator> FusedIterator for PhasesIn<P> {}
+
+/// The phase-calculation algorithms that can be selected by the user
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub(crate) enum PhaseAlgorithm {
+    #[default]
+    NetHack,
+    Conway,
+    Meeus,
+}
+
+impl PhaseAlgorithm {
+    pub(crate) const ALL: [PhaseAlgorithm; 3] = [
+        PhaseAlgorithm::NetHack,
+        PhaseAlgorithm::Conway,
+        PhaseAlgorithm::Meeus,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            PhaseAlgorithm::NetHack => "nethack",
+            PhaseAlgorithm::Conway => "conway",
+            PhaseAlgorithm::Meeus => "meeus",
+        }
+    }
+}
+
+impl FromStr for PhaseAlgorithm {
+    type Err = ParsePhaseAlgorithmError;
+
+    fn from_str(s: &str) -> Result<PhaseAlgorithm, ParsePhaseAlgorithmError> {
+        PhaseAlgorithm::ALL
+            .into_iter()
+            .find(|algo| algo.name().eq_ignore_ascii_case(s))
+            .ok_or(ParsePhaseAlgorithmError)
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("phase algorithm must be \"nethack\", \"conway\", or \"meeus\"")]
+pub(crate) struct ParsePhaseAlgorithmError;
+
+/// The eight phases of the moon as computed by NetHack's
+/// `phase_of_the_moon()`
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum LunarPhase {
+    New,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    Full,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+impl LunarPhase {
+    /// Convert a phase index as returned by NetHack's `phase_of_the_moon()`
+    fn from_index(index: i32) -> LunarPhase {
+        match index {
+            0 => LunarPhase::New,
+            1 => LunarPhase::WaxingCrescent,
+            2 => LunarPhase::FirstQuarter,
+            3 => LunarPhase::WaxingGibbous,
+            4 => LunarPhase::Full,
+            5 => LunarPhase::WaningGibbous,
+            6 => LunarPhase::LastQuarter,
+            _ => LunarPhase::WaningCrescent,
+        }
+    }
+
+    /// Convert an age of the moon in days (0 through 29) to the nearest of
+    /// the eight phases
+    fn from_age(age: i32) -> LunarPhase {
+        // round(age * 8 / 29.5), wrapping around at the end of the month
+        LunarPhase::from_index(((age * 16 + 29) / 59) % 8)
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            LunarPhase::New => "new moon",
+            LunarPhase::WaxingCrescent => "waxing crescent",
+            LunarPhase::FirstQuarter => "first quarter",
+            LunarPhase::WaxingGibbous => "waxing gibbous",
+            LunarPhase::Full => "full moon",
+            LunarPhase::WaningGibbous => "waning gibbous",
+            LunarPhase::LastQuarter => "last quarter",
+            LunarPhase::WaningCrescent => "waning crescent",
+        }
+    }
+
+    /// Returns how much of the moon is lit during the phase, in quarters
+    pub(crate) fn quarters_lit(self) -> usize {
+        match self {
+            LunarPhase::New => 0,
+            LunarPhase::WaxingCrescent | LunarPhase::WaningCrescent => 1,
+            LunarPhase::FirstQuarter | LunarPhase::LastQuarter => 2,
+            LunarPhase::WaxingGibbous | LunarPhase::WaningGibbous => 3,
+            LunarPhase::Full => 4,
+        }
+    }
+
+    /// Returns an emoji depicting the phase as seen from the given hemisphere.
+    /// Seen from the south, the Moon is flipped, so a waxing moon is lit on
+    /// the left instead of the right.
+    pub(crate) fn glyph(self, hemisphere: Hemisphere) -> char {
+        match (self, hemisphere) {
+            (LunarPhase::New, _) => '🌑',
+            (LunarPhase::Full, _) => '🌕',
+            (LunarPhase::WaxingCrescent, Hemisphere::North)
+            | (LunarPhase::WaningCrescent, Hemisphere::South) => '🌒',
+            (LunarPhase::FirstQuarter, Hemisphere::North)
+            | (LunarPhase::LastQuarter, Hemisphere::South) => '🌓',
+            (LunarPhase::WaxingGibbous, Hemisphere::North)
+            | (LunarPhase::WaningGibbous, Hemisphere::South) => '🌔',
+            (LunarPhase::WaningGibbous, Hemisphere::North)
+            | (LunarPhase::WaxingGibbous, Hemisphere::South) => '🌖',
+            (LunarPhase::LastQuarter, Hemisphere::North)
+            | (LunarPhase::FirstQuarter, Hemisphere::South) => '🌗',
+            (LunarPhase::WaningCrescent, Hemisphere::North)
+            | (LunarPhase::WaxingCrescent, Hemisphere::South) => '🌘',
+        }
+    }
+}
+
+/// The hemisphere of the Earth from which the Moon is viewed
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub(crate) enum Hemisphere {
+    #[default]
+    North,
+    South,
+}
+
+impl Hemisphere {
+    pub(crate) const ALL: [Hemisphere; 2] = [Hemisphere::North, Hemisphere::South];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Hemisphere::North => "north",
+            Hemisphere::South => "south",
+        }
+    }
+}
+
+impl FromStr for Hemisphere {
+    type Err = ParseHemisphereError;
+
+    fn from_str(s: &str) -> Result<Hemisphere, ParseHemisphereError> {
+        match s.to_ascii_lowercase().as_str() {
+            "north" | "n" => Ok(Hemisphere::North),
+            "south" | "s" => Ok(Hemisphere::South),
+            _ => Err(ParseHemisphereError),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("hemisphere must be \"north\" or \"south\"")]
+pub(crate) struct ParseHemisphereError;
+
+/// The `PhaseCalculator` for the selected `PhaseAlgorithm`
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub(crate) struct Phoon;
+pub(crate) struct Phoon {
+    pub(crate) algorithm: PhaseAlgorithm,
+    pub(crate) offset: UtcOffset,
+}
 
-impl DateStyler for Phoon {
+impl PhaseCalculator for Phoon {
+    fn lunar_phase(&self, date: Date) -> LunarPhase {
+        match self.algorithm {
+            PhaseAlgorithm::NetHack => NetHack.lunar_phase(date),
+            PhaseAlgorithm::Conway => Conway.lunar_phase(date),
+            PhaseAlgorithm::Meeus => Meeus(self.offset).lunar_phase(date),
+        }
+    }
+}
+
+/// A `DateStyler` that highlights full & new moons, as determined by the
+/// given `PhaseCalculator`, with the given styles
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct MoonPhases<P> {
+    pub(crate) phases: P,
+    pub(crate) full: Style,
+    pub(crate) new: Style,
+}
+
+impl<P: PhaseCalculator> DateStyler for MoonPhases<P> {
     fn date_style(&self, date: Date) -> Style {
-        match Phase::for_date(date) {
+        match self.phases.phase(date) {
             Phase::Normal => Style::new(),
-            Phase::Full => Style::new().light_yellow().bold(),
-            Phase::New => Style::new().light_blue(),
+            Phase::Full => self.full,
+            Phase::New => self.new,
         }
     }
+
+    fn moon_phase(&self, date: Date) -> Option<LunarPhase> {
+        Some(self.phases.lunar_phase(date))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, offset};
+
+    #[test]
+    fn test_nethack() {
+        assert_eq!(NetHack.phase(date!(2023 - 11 - 27)), Phase::Full);
+        assert_eq!(NetHack.phase(date!(2023 - 11 - 13)), Phase::New);
+        assert_eq!(NetHack.phase(date!(2023 - 11 - 20)), Phase::Normal);
+    }
+
+    #[test]
+    fn test_nethack_table() {
+        // NetHack's `phase_of_the_moon()`, as computed before the table
+        let formula = |date: Date| {
+            let year = date.year().saturating_sub(1900);
+            let goldn = (year % 19) + 1;
+            let mut epact = (11 * goldn + 18) % 30;
+            if (epact == 25 && goldn > 11) || epact == 24 {
+                epact += 1;
+            }
+            LunarPhase::from_index(
+                (((((i32::from(date.ordinal()) - 1 + epact) * 6) + 11) % 177) / 22) & 7,
+            )
+        };
+        // Cover every year of the cycle on both sides of 1900, including leap
+        // years
+        for year in (1900 - 19)..(1900 + 2 * 19) {
+            let start = Date::from_ordinal_date(year, 1).unwrap();
+            for date in successors(Some(start), |d| d.next_day()).take_while(|d| d.year() == year) {
+                assert_eq!(NetHack.lunar_phase(date), formula(date), "{date}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_phases_in() {
+        let phases = phases_in(date!(2023 - 11 - 12)..date!(2023 - 11 - 15)).collect::<Vec<_>>();
+        assert_eq!(
+            phases,
+            [
+                (date!(2023 - 11 - 12), Phase::New),
+                (date!(2023 - 11 - 13), Phase::New),
+                (date!(2023 - 11 - 14), Phase::New),
+            ]
+        );
+        let mut iter = phases_in(date!(2023 - 11 - 20)..=date!(2023 - 11 - 27));
+        assert_eq!(iter.len(), 8);
+        assert_eq!(iter.next_back(), Some((date!(2023 - 11 - 27), Phase::Full)));
+        assert_eq!(iter.next(), Some((date!(2023 - 11 - 20), Phase::Normal)));
+        assert_eq!(iter.len(), 6);
+        assert_eq!(
+            phases_in(date!(2023 - 11 - 20)..date!(2023 - 11 - 20)).next(),
+            None
+        );
+        assert_eq!(
+            phases_in(Date::MAX..).map(|(d, _)| d).collect::<Vec<_>>(),
+            [Date::MAX]
+        );
+        assert_eq!(phases_in(..).len(), phases_in(..=Date::MAX).len());
+    }
+
+    #[test]
+    fn test_next_phase() {
+        // NetHack's full moons at the end of 2023 ran from November 27
+        // through 29 and from December 26 through 29.
+        let nov = date!(2023 - 11 - 27);
+        let dec = date!(2023 - 12 - 26);
+        assert_eq!(next_phase(date!(2023 - 11 - 20), Phase::Full), Some(nov));
+        assert_eq!(next_phase(nov, Phase::Full), Some(dec));
+        assert_eq!(next_phase(date!(2023 - 11 - 28), Phase::Full), Some(dec));
+        assert_eq!(previous_phase(dec, Phase::Full), Some(nov));
+        assert_eq!(
+            previous_phase(date!(2023 - 11 - 29), Phase::Full),
+            Some(nov)
+        );
+        assert_eq!(
+            next_phase(date!(2023 - 11 - 28), Phase::New),
+            Some(date!(2023 - 12 - 11))
+        );
+        assert_eq!(next_phase(Date::MAX, Phase::Full), None);
+        assert_eq!(previous_phase(Date::MIN, Phase::Full), None);
+    }
+
+    #[test]
+    fn test_conway() {
+        assert_eq!(Conway.phase(date!(2023 - 11 - 27)), Phase::Full);
+        assert_eq!(Conway.phase(date!(2023 - 11 - 13)), Phase::New);
+        assert_eq!(Conway.phase(date!(2023 - 11 - 20)), Phase::Normal);
+    }
+
+    #[test]
+    fn test_meeus() {
+        // The full moon of 2023 November was at 09:16 UTC on the 27th, and
+        // the new moon was at 09:27 UTC on the 13th.
+        let meeus = Meeus(offset!(UTC));
+        assert_eq!(
+            meeus.lunar_phase(date!(2023 - 11 - 26)),
+            LunarPhase::WaxingGibbous
+        );
+        assert_eq!(meeus.lunar_phase(date!(2023 - 11 - 27)), LunarPhase::Full);
+        assert_eq!(
+            meeus.lunar_phase(date!(2023 - 11 - 28)),
+            LunarPhase::WaningGibbous
+        );
+        assert_eq!(meeus.lunar_phase(date!(2023 - 11 - 13)), LunarPhase::New);
+        assert_eq!(
+            meeus.lunar_phase(date!(2023 - 11 - 20)),
+            LunarPhase::FirstQuarter
+        );
+        // In Kiribati (UTC+14), the full moon fell on the 27th at 23:16.
+        assert_eq!(
+            Meeus(offset!(+14)).phase(date!(2023 - 11 - 27)),
+            Phase::Full
+        );
+    }
 }
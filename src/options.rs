@@ -0,0 +1,542 @@
+//! Settings given on the command line, in the `NHMOON_OPTS` environment
+//! variable, and in the configuration file.  When an option is set in more
+//! than one of these places, the command line takes precedence over
+//! `NHMOON_OPTS`, which takes precedence over the configuration file, which
+//! takes precedence over the built-in defaults.
+use crate::astro::Location;
+use crate::calendar::{MonthBorders, TodayJump, WeekSpacing, WeekSplit, WeekStart, YearNumbering};
+use crate::calendars::OtherCalendars;
+use crate::config::ConfigSource;
+use crate::datefmt::DateFormat;
+use crate::day_list::View;
+use crate::dump::DumpSpec;
+use crate::feedback::{Alert, Feedback, FeedbackMap};
+use crate::keymap::{Binding, Keymap};
+use crate::locale;
+use crate::moon::{Hemisphere, PhaseAlgorithm};
+use crate::shade::Shading;
+use crate::term::ColorSupport;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::PathBuf;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Settings from a single source.  Fields that were not given are `None`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Options {
+    pub(crate) location: Option<Location>,
+    pub(crate) hemisphere: Option<Hemisphere>,
+    pub(crate) phase_algorithm: Option<PhaseAlgorithm>,
+    pub(crate) eclipses: Option<bool>,
+    pub(crate) supermoons: Option<bool>,
+    pub(crate) community_dates: Option<bool>,
+    /// Calendars in which the info popup also shows the date
+    pub(crate) calendars: Option<OtherCalendars>,
+    /// How to show dates in the info popup
+    pub(crate) date_format: Option<DateFormat>,
+    /// How to show years before 1 CE
+    pub(crate) year_numbering: Option<YearNumbering>,
+    /// Which alternate stretches of dates to shade
+    pub(crate) shading: Option<Shading>,
+    /// Number of weeks to show before the start date on launch
+    pub(crate) weeks_before: Option<usize>,
+    /// Number of weeks to show after the start date on launch
+    pub(crate) weeks_after: Option<usize>,
+    /// Number of weeks to show regardless of the terminal's height
+    pub(crate) weeks: Option<NonZeroUsize>,
+    /// How the `today` action positions today
+    pub(crate) today_jump: Option<TodayJump>,
+    /// The weekday in the first column of the calendar
+    pub(crate) week_start: Option<WeekStart>,
+    /// Where to put blank lines between weeks
+    pub(crate) spacing: Option<WeekSpacing>,
+    /// Whether to draw lines around every day
+    pub(crate) grid: Option<bool>,
+    /// Which borders between months to draw
+    pub(crate) month_borders: Option<MonthBorders>,
+    /// Whether to write each month's name under its first day
+    pub(crate) month_labels: Option<bool>,
+    /// Whether to draw a sparkline of the moon's phases under each week
+    pub(crate) sparkline: Option<bool>,
+    /// Whether to write textual markers after full & new moons
+    pub(crate) markers: Option<bool>,
+    /// Whether to name full & new moons in the right margin
+    pub(crate) phase_labels: Option<bool>,
+    /// Whether to quote NetHack's messages for full & new moons
+    pub(crate) game_messages: Option<bool>,
+    /// The view to start in
+    pub(crate) view: Option<View>,
+    /// Whether to reopen the calendar where the last session left off
+    pub(crate) resume: Option<bool>,
+    /// Whether to ring the terminal bell on invalid keys and at the ends of
+    /// time
+    pub(crate) bell: Option<bool>,
+    /// Whether to scroll by a whole week at once instead of gliding
+    pub(crate) reduced_motion: Option<bool>,
+    /// Whether to ask before quitting with the `quit` action
+    pub(crate) confirm_quit: Option<bool>,
+    /// Feedback to give for specific conditions, in order (only settable in
+    /// the configuration file)
+    pub(crate) feedback: Vec<(Alert, Feedback)>,
+    /// Whether to show the welcome popup even if this isn't the first run
+    /// (only settable outside of the configuration file)
+    pub(crate) tour: Option<bool>,
+    /// File of actions to perform on startup (only settable outside of the
+    /// configuration file)
+    pub(crate) script: Option<PathBuf>,
+    /// FIFO from which to read commands while running (only settable outside
+    /// of the configuration file)
+    pub(crate) command_fifo: Option<PathBuf>,
+    /// Unix socket on which to accept commands while running (only settable
+    /// outside of the configuration file)
+    pub(crate) listen: Option<PathBuf>,
+    /// Whether to keep today centered as the date changes and show a footer
+    /// of luck information (only settable outside of the configuration file)
+    pub(crate) dashboard: Option<bool>,
+    /// Phase algorithm to use in a copy of the calendar drawn beside the
+    /// usual one (only settable outside of the configuration file)
+    pub(crate) compare_algo: Option<PhaseAlgorithm>,
+    /// Theme to use in a copy of the calendar drawn beside the usual one
+    /// (only settable outside of the configuration file)
+    pub(crate) compare_theme: Option<String>,
+    /// Number of seconds to wait between automatically advancing the calendar
+    /// by a month (only settable outside of the configuration file)
+    pub(crate) auto_scroll: Option<NonZeroU64>,
+    /// Render into a buffer of the given size and print it instead of running
+    /// interactively (only settable outside of the configuration file)
+    pub(crate) dump_buffer: Option<DumpSpec>,
+    /// Name of a built-in theme or a theme file, or path to a theme file
+    pub(crate) theme: Option<String>,
+    /// Color depth to use instead of the one detected from the environment
+    pub(crate) color_depth: Option<ColorSupport>,
+    /// Key bindings to apply on top of the defaults, in order
+    pub(crate) bindings: Vec<Binding>,
+    /// Which configuration file to read (only settable outside of the
+    /// configuration file)
+    pub(crate) config: Option<ConfigSource>,
+}
+
+impl Options {
+    /// Fill in the fields of `self` that are unset with those of `fallback`.
+    /// Key bindings & feedback settings from `self` are applied after those
+    /// from `fallback`.
+    pub(crate) fn or(self, fallback: Options) -> Options {
+        Options {
+            location: self.location.or(fallback.location),
+            hemisphere: self.hemisphere.or(fallback.hemisphere),
+            phase_algorithm: self.phase_algorithm.or(fallback.phase_algorithm),
+            eclipses: self.eclipses.or(fallback.eclipses),
+            supermoons: self.supermoons.or(fallback.supermoons),
+            community_dates: self.community_dates.or(fallback.community_dates),
+            calendars: self.calendars.or(fallback.calendars),
+            date_format: self.date_format.or(fallback.date_format),
+            year_numbering: self.year_numbering.or(fallback.year_numbering),
+            shading: self.shading.or(fallback.shading),
+            weeks_before: self.weeks_before.or(fallback.weeks_before),
+            weeks_after: self.weeks_after.or(fallback.weeks_after),
+            weeks: self.weeks.or(fallback.weeks),
+            today_jump: self.today_jump.or(fallback.today_jump),
+            week_start: self.week_start.or(fallback.week_start),
+            spacing: self.spacing.or(fallback.spacing),
+            grid: self.grid.or(fallback.grid),
+            month_borders: self.month_borders.or(fallback.month_borders),
+            month_labels: self.month_labels.or(fallback.month_labels),
+            sparkline: self.sparkline.or(fallback.sparkline),
+            markers: self.markers.or(fallback.markers),
+            phase_labels: self.phase_labels.or(fallback.phase_labels),
+            game_messages: self.game_messages.or(fallback.game_messages),
+            view: self.view.or(fallback.view),
+            resume: self.resume.or(fallback.resume),
+            bell: self.bell.or(fallback.bell),
+            reduced_motion: self.reduced_motion.or(fallback.reduced_motion),
+            confirm_quit: self.confirm_quit.or(fallback.confirm_quit),
+            feedback: fallback.feedback.into_iter().chain(self.feedback).collect(),
+            tour: self.tour.or(fallback.tour),
+            script: self.script.or(fallback.script),
+            command_fifo: self.command_fifo.or(fallback.command_fifo),
+            listen: self.listen.or(fallback.listen),
+            dashboard: self.dashboard.or(fallback.dashboard),
+            compare_algo: self.compare_algo.or(fallback.compare_algo),
+            compare_theme: self.compare_theme.or(fallback.compare_theme),
+            auto_scroll: self.auto_scroll.or(fallback.auto_scroll),
+            dump_buffer: self.dump_buffer.or(fallback.dump_buffer),
+            theme: self.theme.or(fallback.theme),
+            color_depth: self.color_depth.or(fallback.color_depth),
+            bindings: fallback.bindings.into_iter().chain(self.bindings).collect(),
+            config: self.config.or(fallback.config),
+        }
+    }
+
+    /// Given the combined options from the command line & `NHMOON_OPTS`,
+    /// load the configuration file that they select using `load_config` and
+    /// fill in any remaining unset options from it
+    pub(crate) fn resolve<F, E>(self, load_config: F) -> Result<Options, E>
+    where
+        F: FnOnce(&ConfigSource) -> Result<Options, E>,
+    {
+        let config = load_config(&self.config_source())?;
+        Ok(self.or(config))
+    }
+
+    /// Returns the number of settings (other than key bindings) that are set
+    pub(crate) fn count_settings(&self) -> usize {
+        [
+            self.location.is_some(),
+            self.hemisphere.is_some(),
+            self.phase_algorithm.is_some(),
+            self.eclipses.is_some(),
+            self.supermoons.is_some(),
+            self.community_dates.is_some(),
+            self.calendars.is_some(),
+            self.date_format.is_some(),
+            self.year_numbering.is_some(),
+            self.shading.is_some(),
+            self.weeks_before.is_some(),
+            self.weeks_after.is_some(),
+            self.weeks.is_some(),
+            self.today_jump.is_some(),
+            self.week_start.is_some(),
+            self.spacing.is_some(),
+            self.grid.is_some(),
+            self.month_borders.is_some(),
+            self.month_labels.is_some(),
+            self.sparkline.is_some(),
+            self.markers.is_some(),
+            self.phase_labels.is_some(),
+            self.game_messages.is_some(),
+            self.view.is_some(),
+            self.resume.is_some(),
+            self.bell.is_some(),
+            self.reduced_motion.is_some(),
+            self.confirm_quit.is_some(),
+            self.theme.is_some(),
+            self.color_depth.is_some(),
+        ]
+        .into_iter()
+        .filter(|&b| b)
+        .count()
+            + self.feedback.len()
+    }
+
+    pub(crate) fn config_source(&self) -> ConfigSource {
+        self.config.clone().unwrap_or_default()
+    }
+
+    /// The hemisphere from which to depict the Moon, defaulting to the one
+    /// containing `location`, if given
+    pub(crate) fn hemisphere(&self) -> Hemisphere {
+        match (self.hemisphere, self.location) {
+            (Some(h), _) => h,
+            (None, Some(loc)) if loc.latitude < 0.0 => Hemisphere::South,
+            (None, _) => Hemisphere::North,
+        }
+    }
+
+    pub(crate) fn phase_algorithm(&self) -> PhaseAlgorithm {
+        self.phase_algorithm.unwrap_or_default()
+    }
+
+    pub(crate) fn eclipses(&self) -> bool {
+        self.eclipses.unwrap_or_default()
+    }
+
+    pub(crate) fn supermoons(&self) -> bool {
+        self.supermoons.unwrap_or_default()
+    }
+
+    pub(crate) fn community_dates(&self) -> bool {
+        self.community_dates.unwrap_or_default()
+    }
+
+    pub(crate) fn calendars(&self) -> OtherCalendars {
+        self.calendars.unwrap_or_default()
+    }
+
+    pub(crate) fn date_format(&self) -> DateFormat {
+        self.date_format.unwrap_or_default()
+    }
+
+    pub(crate) fn year_numbering(&self) -> YearNumbering {
+        self.year_numbering.unwrap_or_default()
+    }
+
+    pub(crate) fn shading(&self) -> Shading {
+        self.shading.unwrap_or_default()
+    }
+
+    pub(crate) fn week_split(&self) -> WeekSplit {
+        WeekSplit {
+            before: self.weeks_before,
+            after: self.weeks_after,
+        }
+    }
+
+    pub(crate) fn today_jump(&self) -> TodayJump {
+        self.today_jump.unwrap_or_default()
+    }
+
+    /// Returns the configured week start, falling back to that of the user's
+    /// locale and then to Sunday
+    pub(crate) fn week_start(&self) -> WeekStart {
+        self.week_start
+            .or_else(|| locale::week_start().map(WeekStart))
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn spacing(&self) -> WeekSpacing {
+        self.spacing.unwrap_or_default()
+    }
+
+    pub(crate) fn grid(&self) -> bool {
+        self.grid.unwrap_or_default()
+    }
+
+    pub(crate) fn month_borders(&self) -> MonthBorders {
+        self.month_borders.unwrap_or_default()
+    }
+
+    pub(crate) fn month_labels(&self) -> bool {
+        self.month_labels.unwrap_or_default()
+    }
+
+    pub(crate) fn sparkline(&self) -> bool {
+        self.sparkline.unwrap_or_default()
+    }
+
+    pub(crate) fn markers(&self) -> bool {
+        self.markers.unwrap_or_default()
+    }
+
+    pub(crate) fn phase_labels(&self) -> bool {
+        self.phase_labels.unwrap_or_default()
+    }
+
+    pub(crate) fn game_messages(&self) -> bool {
+        self.game_messages.unwrap_or_default()
+    }
+
+    pub(crate) fn view(&self) -> View {
+        self.view.unwrap_or_default()
+    }
+
+    pub(crate) fn resume(&self) -> bool {
+        self.resume.unwrap_or_default()
+    }
+
+    pub(crate) fn bell(&self) -> bool {
+        self.bell.unwrap_or(true)
+    }
+
+    pub(crate) fn reduced_motion(&self) -> bool {
+        self.reduced_motion.unwrap_or_default()
+    }
+
+    pub(crate) fn confirm_quit(&self) -> bool {
+        self.confirm_quit.unwrap_or_default()
+    }
+
+    /// The feedback to give for each condition.  Conditions not set in the
+    /// `[feedback]` table ring the bell, or show a message with reduced
+    /// motion, as terminals may flash the screen for the bell.  With the bell
+    /// turned off, nothing rings it.
+    pub(crate) fn feedback(&self) -> FeedbackMap {
+        let mut map = if self.reduced_motion() {
+            FeedbackMap::uniform(Feedback::Message)
+        } else {
+            FeedbackMap::default()
+        };
+        for &(alert, feedback) in &self.feedback {
+            map.set(alert, feedback);
+        }
+        if !self.bell() {
+            for alert in Alert::ALL {
+                if map.get(alert) == Feedback::Bell {
+                    map.set(alert, Feedback::None);
+                }
+            }
+        }
+        map
+    }
+
+    pub(crate) fn tour(&self) -> bool {
+        self.tour.unwrap_or_default()
+    }
+
+    pub(crate) fn dashboard(&self) -> bool {
+        self.dashboard.unwrap_or_default()
+    }
+
+    pub(crate) fn auto_scroll(&self) -> Option<Duration> {
+        self.auto_scroll.map(|secs| Duration::from_secs(secs.get()))
+    }
+
+    /// The theme to use, falling back to `default` (which depends on the
+    /// terminal's background)
+    pub(crate) fn theme<'a>(&'a self, default: &'a str) -> &'a str {
+        self.theme.as_deref().unwrap_or(default)
+    }
+
+    pub(crate) fn keymap(&self) -> Keymap {
+        let mut keymap = Keymap::default();
+        keymap.extend(&self.bindings);
+        keymap
+    }
+}
+
+/// Split a string of command-line arguments into individual arguments.
+/// Arguments are separated by whitespace, which can be included in an argument
+/// by enclosing it in single or double quotes or by escaping it with a
+/// backslash.
+pub(crate) fn split_args(s: &str) -> Result<Vec<String>, SplitArgsError> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some('\\') if c == '"' => {
+                            arg.push(chars.next().ok_or(SplitArgsError::TrailingBackslash)?);
+                        }
+                        Some(ch) => arg.push(ch),
+                        None => return Err(SplitArgsError::UnterminatedQuote),
+                    }
+                }
+            }
+            '\\' => current
+                .get_or_insert_with(String::new)
+                .push(chars.next().ok_or(SplitArgsError::TrailingBackslash)?),
+            c if c.is_whitespace() => args.extend(current.take()),
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+pub(crate) enum SplitArgsError {
+    #[error("unterminated quoted string")]
+    UnterminatedQuote,
+    #[error("backslash at end of string")]
+    TrailingBackslash,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::Action;
+    use crossterm::event::KeyCode;
+
+    #[test]
+    fn test_precedence() {
+        let cli = Options {
+            phase_algorithm: Some(PhaseAlgorithm::Meeus),
+            bindings: vec!["x=quit".parse().unwrap()],
+            ..Options::default()
+        };
+        let env = Options {
+            phase_algorithm: Some(PhaseAlgorithm::Conway),
+            community_dates: Some(false),
+            bindings: vec!["x=help".parse().unwrap(), "y=help".parse().unwrap()],
+            config: Some(ConfigSource::Path(PathBuf::from("nhmoon.toml"))),
+            ..Options::default()
+        };
+        let config = Options {
+            phase_algorithm: Some(PhaseAlgorithm::NetHack),
+            community_dates: Some(true),
+            eclipses: Some(true),
+            bindings: vec!["y=quit".parse().unwrap(), "z=info".parse().unwrap()],
+            ..Options::default()
+        };
+        let opts = cli
+            .or(env)
+            .resolve(|source| {
+                assert_eq!(
+                    source,
+                    &ConfigSource::Path(PathBuf::from("nhmoon.toml")),
+                    "config file from NHMOON_OPTS should be used"
+                );
+                Ok::<_, ()>(config)
+            })
+            .unwrap();
+        assert_eq!(opts.phase_algorithm(), PhaseAlgorithm::Meeus);
+        assert!(!opts.community_dates());
+        assert!(opts.eclipses());
+        assert!(!opts.supermoons());
+        assert_eq!(opts.hemisphere(), Hemisphere::North);
+        let keymap = opts.keymap();
+        assert_eq!(keymap.get(KeyCode::Char('x')), Some(Action::Quit));
+        assert_eq!(keymap.get(KeyCode::Char('y')), Some(Action::Help));
+        assert_eq!(keymap.get(KeyCode::Char('z')), Some(Action::Info));
+    }
+
+    #[test]
+    fn test_cli_false_overrides_config() {
+        let cli = Options {
+            eclipses: Some(false),
+            grid: Some(false),
+            bell: Some(true),
+            ..Options::default()
+        };
+        let env = Options {
+            grid: Some(true),
+            bell: Some(false),
+            ..Options::default()
+        };
+        let config = Options {
+            eclipses: Some(true),
+            supermoons: Some(true),
+            dashboard: Some(true),
+            ..Options::default()
+        };
+        let opts = cli.or(env).or(config);
+        assert!(!opts.eclipses());
+        assert!(!opts.grid());
+        assert!(opts.bell());
+        assert!(opts.supermoons());
+        assert!(opts.dashboard());
+    }
+
+    #[test]
+    fn test_cli_config_overrides_env() {
+        let cli = Options {
+            config: Some(ConfigSource::Disabled),
+            ..Options::default()
+        };
+        let env = Options {
+            config: Some(ConfigSource::Path(PathBuf::from("nhmoon.toml"))),
+            ..Options::default()
+        };
+        assert_eq!(cli.or(env).config_source(), ConfigSource::Disabled);
+        assert_eq!(Options::default().config_source(), ConfigSource::Default);
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            split_args("  --eclipses --bind 'space=page-down'\t-l \"40.7, -74.0\" ").unwrap(),
+            [
+                "--eclipses",
+                "--bind",
+                "space=page-down",
+                "-l",
+                "40.7, -74.0"
+            ]
+        );
+        assert_eq!(
+            split_args(r#"--config ~/My\ Config/nh.toml --bind=\"='help'"#).unwrap(),
+            ["--config", "~/My Config/nh.toml", "--bind=\"=help"]
+        );
+        assert_eq!(split_args("''").unwrap(), [""]);
+        assert!(split_args("").unwrap().is_empty());
+        assert_eq!(
+            split_args("--bind 'q=quit"),
+            Err(SplitArgsError::UnterminatedQuote)
+        );
+        assert_eq!(split_args("foo\\"), Err(SplitArgsError::TrailingBackslash));
+    }
+}
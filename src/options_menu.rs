@@ -0,0 +1,43 @@
+use crate::popup::{Popup, PopupStyle};
+use crate::settings::{Setting, Settings};
+use ratatui::prelude::*;
+
+/// Popup listing the adjustable settings, with the one at index `selected`
+/// highlighted
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct OptionsMenu<'a> {
+    pub(crate) settings: Settings,
+    pub(crate) selected: usize,
+    /// A message about the last attempt to save the settings, if any
+    pub(crate) status: Option<&'a str>,
+    pub(crate) style: PopupStyle,
+}
+
+impl Widget for OptionsMenu<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut lines = Setting::ALL
+            .into_iter()
+            .enumerate()
+            .map(|(i, setting)| {
+                let text = format!(
+                    "{:<18}< {:^7} >",
+                    setting.label(),
+                    setting.show(&self.settings)
+                );
+                if i == self.selected {
+                    Line::styled(text, Style::new().reversed())
+                } else {
+                    Line::raw(text)
+                }
+            })
+            .collect::<Vec<_>>();
+        lines.push(Line::raw(""));
+        if let Some(status) = self.status {
+            lines.push(Line::raw(status.to_owned()));
+            lines.push(Line::raw(""));
+        }
+        lines.push(Line::raw("UP/DOWN: select    LEFT/RIGHT: change"));
+        lines.push(Line::raw("s: save to config  o, ESC: close"));
+        Popup::new(" Options ", lines, self.style).render(area, buf);
+    }
+}
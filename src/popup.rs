@@ -0,0 +1,141 @@
+use ratatui::{layout::Flex, prelude::*, symbols::border, widgets::*};
+
+/// Border for terminals that can't display box-drawing characters
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// The styles for a popup's text and for its border & title
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct PopupStyle {
+    pub(crate) text: Style,
+    pub(crate) border: Style,
+    /// Draw the border with ASCII characters and leave out emoji
+    pub(crate) ascii: bool,
+}
+
+/// A bordered, titled box of text drawn centered over the rest of the screen
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Popup<'a> {
+    title: &'a str,
+    text: Text<'a>,
+    style: PopupStyle,
+}
+
+impl<'a> Popup<'a> {
+    pub(crate) fn new<T: Into<Text<'a>>>(title: &'a str, text: T, style: PopupStyle) -> Popup<'a> {
+        Popup {
+            title,
+            text: text.into(),
+            style,
+        }
+    }
+}
+
+impl Widget for Popup<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let height = u16::try_from(self.text.height())
+            .unwrap_or(u16::MAX)
+            .min(area.height)
+            .saturating_add(2);
+        let width = u16::try_from(self.text.width())
+            .unwrap_or(u16::MAX)
+            .min(area.width)
+            .saturating_add(2);
+        let para = Paragraph::new(self.text)
+            .block(
+                Block::bordered()
+                    .border_set(if self.style.ascii {
+                        ASCII_BORDER
+                    } else {
+                        border::PLAIN
+                    })
+                    .title(self.title)
+                    .title_alignment(Alignment::Center)
+                    .title_style(self.style.border)
+                    .border_style(self.style.border),
+            )
+            .style(self.style.text);
+        let [popup_area] = Layout::horizontal([width]).flex(Flex::Center).areas(area);
+        let [popup_area] = Layout::vertical([height])
+            .flex(Flex::Center)
+            .areas(popup_area);
+        let outer_area = Rect {
+            x: popup_area.x.saturating_sub(1),
+            y: popup_area.y,
+            width: popup_area.width.saturating_add(2),
+            height: popup_area.height,
+        };
+        Clear.render(outer_area, buf);
+        Block::new().style(self.style.text).render(outer_area, buf);
+        para.render(popup_area, buf);
+    }
+}
+
+/// A popup displaying a message for the user to acknowledge
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Notice<'a> {
+    pub(crate) title: &'a str,
+    pub(crate) message: &'a str,
+    pub(crate) style: PopupStyle,
+}
+
+impl Widget for Notice<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut lines = self.message.lines().map(Line::raw).collect::<Vec<_>>();
+        lines.push(Line::raw(""));
+        lines.push(Line::raw("Press the Any Key to dismiss."));
+        Popup::new(self.title, lines, self.style).render(area, buf);
+    }
+}
+
+/// A popup asking the user a yes-or-no question, answered with `y` or Enter
+/// for yes and any other key for no
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Confirm<'a> {
+    pub(crate) title: &'a str,
+    pub(crate) question: &'a str,
+    pub(crate) style: PopupStyle,
+}
+
+impl Widget for Confirm<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let lines = vec![
+            Line::raw(self.question),
+            Line::raw(""),
+            Line::raw("y, ENTER: yes    Any other key: no"),
+        ];
+        Popup::new(self.title, lines, self.style).render(area, buf);
+    }
+}
+
+/// A popup asking the user to type in a value, shown after `label` with a
+/// cursor at the end
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Prompt<'a> {
+    pub(crate) title: &'a str,
+    pub(crate) label: &'a str,
+    pub(crate) input: &'a str,
+    /// A line shown under the input, e.g., describing what it will do
+    pub(crate) preview: Option<&'a str>,
+    pub(crate) style: PopupStyle,
+}
+
+impl Widget for Prompt<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut lines = vec![Line::raw(format!("{}{}_", self.label, self.input))];
+        if let Some(preview) = self.preview {
+            lines.push(Line::raw(preview.to_owned()));
+        }
+        lines.push(Line::raw(""));
+        lines.push(Line::raw("Press Enter to confirm or Escape to cancel."));
+        Popup::new(self.title, lines, self.style).render(area, buf);
+    }
+}
@@ -0,0 +1,81 @@
+//! Reading lists of actions to perform on startup from script files
+use crate::config::ParseError;
+use crate::keymap::Action;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Load a script from the file at `path`
+pub(crate) fn load_script(path: &Path) -> Result<Vec<Action>, ScriptError> {
+    let src = fs::read_to_string(path).map_err(|source| ScriptError::Read {
+        path: path.to_owned(),
+        source,
+    })?;
+    parse_script(&src).map_err(|error| ScriptError::Invalid {
+        path: path.to_owned(),
+        error,
+    })
+}
+
+/// Parse a script consisting of one action name (as used by `--bind`) per
+/// line.  Blank lines and lines starting with `#` are ignored.
+fn parse_script(src: &str) -> Result<Vec<Action>, ParseError> {
+    let mut actions = Vec::new();
+    for (i, line) in src.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let action = line.parse::<Action>().map_err(|e| ParseError {
+            line: line_no,
+            msg: e.to_string(),
+        })?;
+        actions.push(action);
+    }
+    Ok(actions)
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum ScriptError {
+    #[error("failed to read script file {}", .path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("invalid script file {}: {error}", .path.display())]
+    Invalid { path: PathBuf, error: ParseError },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_script() {
+        let src = concat!(
+            "# Look at next month\n",
+            "page-down\n",
+            "\n",
+            "  scroll-up  \n",
+            "info\n",
+        );
+        assert_eq!(
+            parse_script(src),
+            Ok(vec![Action::PageDown, Action::ScrollUp, Action::Info])
+        );
+    }
+
+    #[test]
+    fn test_parse_script_error() {
+        assert_eq!(
+            parse_script("today\njump\n"),
+            Err(ParseError {
+                line: 2,
+                msg: String::from("unknown action \"jump\""),
+            })
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Saving & restoring the state of the calendar between runs
+use crate::calendar::{DateStyler, WeekWindow};
+use crate::config::ParseError;
+use crate::dirs;
+use crate::YMD_FMT;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+use time::Date;
+use toml::{Spanned, Value};
+
+/// The parts of the calendar's state that are restored by `--resume`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Session {
+    /// The date around which the calendar was positioned
+    pub(crate) date: Date,
+    /// The dates given on the command line
+    pub(crate) marked: Vec<Date>,
+    /// Index into `marked` of the date last jumped to
+    pub(crate) marked_index: usize,
+}
+
+impl Session {
+    pub(crate) fn from_window<S: DateStyler>(window: &WeekWindow<S>) -> Session {
+        Session {
+            date: window.focus_date(),
+            marked: window.marked_dates().to_vec(),
+            marked_index: window.current_marked_index(),
+        }
+    }
+
+    /// Position `window` where it was when the session was saved
+    pub(crate) fn restore<S: DateStyler>(self, window: WeekWindow<S>) -> WeekWindow<S> {
+        window
+            .marked(self.marked)
+            .marked_index(self.marked_index)
+            .start_date(self.date)
+    }
+
+    fn to_toml(&self) -> String {
+        let marked = self
+            .marked
+            .iter()
+            .map(Date::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "date = {}\nmarked = {}\nmarked-index = {}\n",
+            Value::String(self.date.to_string()),
+            Value::String(marked),
+            self.marked_index,
+        )
+    }
+
+    fn from_toml(src: &str) -> Result<Session, ParseError> {
+        let file =
+            toml::from_str::<SessionFile>(src).map_err(|e| ParseError::from_toml(src, &e))?;
+        let err = |value: &Spanned<String>, key: &str| {
+            ParseError::at(src, value.span().start, format!("invalid date for {key:?}"))
+        };
+        let date =
+            Date::parse(file.date.get_ref(), &YMD_FMT).map_err(|_| err(&file.date, "date"))?;
+        let marked = match &file.marked {
+            Some(value) => value
+                .get_ref()
+                .split_whitespace()
+                .map(|d| Date::parse(d, &YMD_FMT))
+                .collect::<Result<_, _>>()
+                .map_err(|_| err(value, "marked"))?,
+            None => Vec::new(),
+        };
+        Ok(Session {
+            date,
+            marked,
+            marked_index: file.marked_index,
+        })
+    }
+}
+
+/// The contents of a session file.  Unknown keys are ignored so that sessions
+/// saved by newer versions can still be resumed.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct SessionFile {
+    date: Spanned<String>,
+    marked: Option<Spanned<String>>,
+    #[serde(default)]
+    marked_index: usize,
+}
+
+/// Load the session saved by the last run, if any
+pub(crate) fn load_session() -> Result<Option<Session>, SessionError> {
+    let Some(path) = dirs::session_file() else {
+        return Ok(None);
+    };
+    let src = match fs::read_to_string(&path) {
+        Ok(src) => src,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => return Err(SessionError::Read { path, source }),
+    };
+    match Session::from_toml(&src) {
+        Ok(session) => Ok(Some(session)),
+        Err(error) => Err(SessionError::Invalid { path, error }),
+    }
+}
+
+/// Returns true if no session has been saved yet, i.e., if this is the first
+/// time nhmoon has been run
+pub(crate) fn is_first_run() -> bool {
+    dirs::session_file().is_some_and(|path| !path.exists())
+}
+
+/// Save `session` for resumption by a later run
+pub(crate) fn save_session(session: &Session) -> Result<(), SessionError> {
+    let path = dirs::session_file().ok_or(SessionError::NoPath)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|source| SessionError::Write {
+            path: path.clone(),
+            source,
+        })?;
+    }
+    fs::write(&path, session.to_toml()).map_err(|source| SessionError::Write { path, source })
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum SessionError {
+    #[error("failed to read session file {}", .path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to write session file {}", .path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("could not determine location of session file")]
+    NoPath,
+    #[error("invalid session file {}: {error}", .path.display())]
+    Invalid { path: PathBuf, error: ParseError },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    #[test]
+    fn test_roundtrip() {
+        let session = Session {
+            date: date!(2024 - 03 - 10),
+            marked: vec![date!(2024 - 03 - 25), date!(-0044 - 03 - 15)],
+            marked_index: 1,
+        };
+        let src = session.to_toml();
+        assert_eq!(
+            src,
+            "date = \"2024-03-10\"\nmarked = \"2024-03-25 -0044-03-15\"\nmarked-index = 1\n"
+        );
+        assert_eq!(Session::from_toml(&src), Ok(session));
+    }
+
+    #[test]
+    fn test_from_toml_errors() {
+        assert_eq!(
+            Session::from_toml("marked = \"\"\n").unwrap_err().msg,
+            "missing field `date`"
+        );
+        assert_eq!(
+            Session::from_toml("date = \"2024-02-30\"\n")
+                .unwrap_err()
+                .msg,
+            "invalid date for \"date\""
+        );
+    }
+}
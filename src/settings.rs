@@ -0,0 +1,323 @@
+//! Display settings that can be adjusted while the calendar is open
+use crate::calendar::{DateStyler, WeekStart};
+use crate::community::CommunityDates;
+use crate::eclipse::Eclipses;
+use crate::luck::Friday13th;
+use crate::moon::{Hemisphere, LunarPhase, MoonPhases, PhaseAlgorithm, PhaseCalculator, Phoon};
+use crate::options::Options;
+use crate::shade::{AlternateShading, Shading};
+use crate::supermoon::Supermoons;
+use crate::theme::Theme;
+use ratatui::style::Style;
+use time::{Date, UtcOffset};
+use toml::Value;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Settings {
+    pub(crate) phase_algorithm: PhaseAlgorithm,
+    pub(crate) hemisphere: Hemisphere,
+    pub(crate) eclipses: bool,
+    pub(crate) supermoons: bool,
+    pub(crate) community_dates: bool,
+    pub(crate) shading: Shading,
+    pub(crate) week_start: WeekStart,
+    /// Whether to follow the current date and show the luck footer
+    pub(crate) dashboard: bool,
+}
+
+impl From<&Options> for Settings {
+    fn from(opts: &Options) -> Settings {
+        Settings {
+            phase_algorithm: opts.phase_algorithm(),
+            hemisphere: opts.hemisphere(),
+            eclipses: opts.eclipses(),
+            supermoons: opts.supermoons(),
+            community_dates: opts.community_dates(),
+            shading: opts.shading(),
+            week_start: opts.week_start(),
+            dashboard: opts.dashboard(),
+        }
+    }
+}
+
+impl Settings {
+    /// Set each setting that differs between `old` and `new` to its value in
+    /// `new`, leaving the others as they are
+    pub(crate) fn apply_changes(&mut self, old: &Settings, new: &Settings) {
+        for setting in Setting::ALL {
+            if setting.value(old) != setting.value(new) {
+                setting.copy(new, self);
+            }
+        }
+    }
+}
+
+/// An individual adjustable setting, as listed in the options popup
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Setting {
+    PhaseAlgorithm,
+    Hemisphere,
+    Eclipses,
+    Supermoons,
+    CommunityDates,
+    Shading,
+    WeekStart,
+    Dashboard,
+}
+
+impl Setting {
+    pub(crate) const ALL: [Setting; 8] = [
+        Setting::PhaseAlgorithm,
+        Setting::Hemisphere,
+        Setting::Eclipses,
+        Setting::Supermoons,
+        Setting::CommunityDates,
+        Setting::Shading,
+        Setting::WeekStart,
+        Setting::Dashboard,
+    ];
+
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Setting::PhaseAlgorithm => "Phase algorithm",
+            Setting::Hemisphere => "Hemisphere",
+            Setting::Eclipses => "Eclipses",
+            Setting::Supermoons => "Supermoons",
+            Setting::CommunityDates => "Community dates",
+            Setting::Shading => "Shading",
+            Setting::WeekStart => "Week start",
+            Setting::Dashboard => "Follow today",
+        }
+    }
+
+    /// The key for the setting in the configuration file
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Setting::PhaseAlgorithm => "phase-algo",
+            Setting::Hemisphere => "hemisphere",
+            Setting::Eclipses => "eclipses",
+            Setting::Supermoons => "supermoons",
+            Setting::CommunityDates => "community-dates",
+            Setting::Shading => "shade",
+            Setting::WeekStart => "week-start",
+            Setting::Dashboard => "dashboard",
+        }
+    }
+
+    /// The setting's current value, for display
+    pub(crate) fn show(self, settings: &Settings) -> &'static str {
+        let on_off = |b: bool| if b { "on" } else { "off" };
+        match self {
+            Setting::PhaseAlgorithm => settings.phase_algorithm.name(),
+            Setting::Hemisphere => settings.hemisphere.name(),
+            Setting::Eclipses => on_off(settings.eclipses),
+            Setting::Supermoons => on_off(settings.supermoons),
+            Setting::CommunityDates => on_off(settings.community_dates),
+            Setting::Shading => settings.shading.name(),
+            // Abbreviated to fit the popup's column of values
+            Setting::WeekStart => &settings.week_start.name()[..3],
+            Setting::Dashboard => on_off(settings.dashboard),
+        }
+    }
+
+    /// The setting's current value, for writing to the configuration file
+    pub(crate) fn value(self, settings: &Settings) -> Value {
+        match self {
+            Setting::PhaseAlgorithm => Value::String(settings.phase_algorithm.name().into()),
+            Setting::Hemisphere => Value::String(settings.hemisphere.name().into()),
+            Setting::Eclipses => Value::Boolean(settings.eclipses),
+            Setting::Supermoons => Value::Boolean(settings.supermoons),
+            Setting::CommunityDates => Value::Boolean(settings.community_dates),
+            Setting::Shading => Value::String(settings.shading.name().into()),
+            Setting::WeekStart => Value::String(settings.week_start.name().into()),
+            Setting::Dashboard => Value::Boolean(settings.dashboard),
+        }
+    }
+
+    /// Set the setting in `dest` to its value in `src`
+    fn copy(self, src: &Settings, dest: &mut Settings) {
+        match self {
+            Setting::PhaseAlgorithm => dest.phase_algorithm = src.phase_algorithm,
+            Setting::Hemisphere => dest.hemisphere = src.hemisphere,
+            Setting::Eclipses => dest.eclipses = src.eclipses,
+            Setting::Supermoons => dest.supermoons = src.supermoons,
+            Setting::CommunityDates => dest.community_dates = src.community_dates,
+            Setting::Shading => dest.shading = src.shading,
+            Setting::WeekStart => dest.week_start = src.week_start,
+            Setting::Dashboard => dest.dashboard = src.dashboard,
+        }
+    }
+
+    /// Change the setting to its next (or, if `forwards` is false, previous)
+    /// value
+    pub(crate) fn adjust(self, settings: &mut Settings, forwards: bool) {
+        match self {
+            Setting::PhaseAlgorithm => {
+                settings.phase_algorithm =
+                    cycle(&PhaseAlgorithm::ALL, settings.phase_algorithm, forwards);
+            }
+            Setting::Hemisphere => {
+                settings.hemisphere = cycle(&Hemisphere::ALL, settings.hemisphere, forwards);
+            }
+            Setting::Eclipses => settings.eclipses = !settings.eclipses,
+            Setting::Supermoons => settings.supermoons = !settings.supermoons,
+            Setting::CommunityDates => settings.community_dates = !settings.community_dates,
+            Setting::Shading => {
+                settings.shading = cycle(&Shading::ALL, settings.shading, forwards);
+            }
+            Setting::WeekStart => {
+                let WeekStart(wd) = settings.week_start;
+                settings.week_start = WeekStart(if forwards { wd.next() } else { wd.previous() });
+            }
+            Setting::Dashboard => settings.dashboard = !settings.dashboard,
+        }
+    }
+}
+
+/// Returns the element of `values` after (or before) `current`, wrapping
+/// around at the ends
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, forwards: bool) -> T {
+    let i = values.iter().position(|&v| v == current).unwrap_or(0);
+    let j = if forwards {
+        (i + 1) % values.len()
+    } else {
+        (i + values.len() - 1) % values.len()
+    };
+    values[j]
+}
+
+/// The calendar's `DateStyler`, which highlights moon phases plus whichever
+/// optional layers are enabled in its `Settings`, using the styles from its
+/// `Theme`.  Any styles from `extra` (such as those supplied by a program
+/// embedding the calendar) are patched on top.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Highlighter<X = ()> {
+    pub(crate) settings: Settings,
+    pub(crate) theme: Theme,
+    pub(crate) offset: UtcOffset,
+    pub(crate) extra: X,
+}
+
+impl<X> Highlighter<X> {
+    pub(crate) fn phoon(&self) -> Phoon {
+        Phoon {
+            algorithm: self.settings.phase_algorithm,
+            offset: self.offset,
+        }
+    }
+}
+
+impl<X: DateStyler> DateStyler for Highlighter<X> {
+    fn date_style(&self, date: Date) -> Style {
+        let Settings {
+            eclipses,
+            supermoons,
+            community_dates,
+            shading,
+            week_start,
+            ..
+        } = self.settings;
+        let Highlighter { theme, offset, .. } = *self;
+        let phoon = self.phoon();
+        let phases = MoonPhases {
+            phases: phoon,
+            full: theme.full_moon,
+            new: theme.new_moon,
+        };
+        let supermoons = supermoons.then_some(Supermoons {
+            offset,
+            supermoon: theme.supermoon,
+            micromoon: theme.micromoon,
+        });
+        let eclipses = eclipses.then_some(Eclipses {
+            offset,
+            style: theme.eclipse,
+        });
+        let friday_13th = Friday13th {
+            phases: phoon,
+            style: theme.friday_13th,
+        };
+        let community_dates = community_dates.then_some(CommunityDates(theme.community_date));
+        // Applied first, so that everything else is drawn on top of it
+        let shading = AlternateShading {
+            shading,
+            style: theme.shade,
+            week_start: week_start.0,
+        };
+        (
+            shading,
+            (
+                ((phases, supermoons), eclipses),
+                (friday_13th, community_dates),
+            ),
+        )
+            .date_style(date)
+            .patch(self.extra.date_style(date))
+    }
+
+    fn moon_phase(&self, date: Date) -> Option<LunarPhase> {
+        Some(self.phoon().lunar_phase(date))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjust() {
+        let mut settings = Settings::default();
+        Setting::PhaseAlgorithm.adjust(&mut settings, false);
+        assert_eq!(settings.phase_algorithm, PhaseAlgorithm::Meeus);
+        Setting::PhaseAlgorithm.adjust(&mut settings, true);
+        assert_eq!(settings.phase_algorithm, PhaseAlgorithm::NetHack);
+        Setting::Hemisphere.adjust(&mut settings, true);
+        assert_eq!(Setting::Hemisphere.show(&settings), "south");
+        Setting::Eclipses.adjust(&mut settings, true);
+        assert_eq!(Setting::Eclipses.show(&settings), "on");
+        assert_eq!(Setting::Eclipses.value(&settings), Value::Boolean(true));
+        Setting::Shading.adjust(&mut settings, false);
+        assert_eq!(Setting::Shading.show(&settings), "months");
+        Setting::WeekStart.adjust(&mut settings, false);
+        assert_eq!(Setting::WeekStart.show(&settings), "sat");
+        assert_eq!(
+            Setting::WeekStart.value(&settings),
+            Value::String("saturday".into())
+        );
+        assert_eq!(
+            Setting::WeekStart.value(&settings).as_str().map(str::parse),
+            Some(Ok(WeekStart(time::Weekday::Saturday)))
+        );
+        Setting::Dashboard.adjust(&mut settings, true);
+        assert_eq!(Setting::Dashboard.value(&settings), Value::Boolean(true));
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let old = Settings::default();
+        // Changed in the options popup but not saved
+        let mut current = Settings {
+            eclipses: true,
+            hemisphere: Hemisphere::South,
+            ..old
+        };
+        // Reloaded after the file was edited
+        let new = Settings {
+            hemisphere: Hemisphere::North,
+            supermoons: true,
+            phase_algorithm: PhaseAlgorithm::Meeus,
+            ..old
+        };
+        current.apply_changes(&old, &new);
+        assert_eq!(
+            current,
+            Settings {
+                eclipses: true,
+                hemisphere: Hemisphere::South,
+                supermoons: true,
+                phase_algorithm: PhaseAlgorithm::Meeus,
+                ..old
+            }
+        );
+    }
+}
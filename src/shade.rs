@@ -0,0 +1,124 @@
+//! Shading of alternate weeks or months to make rows easier to follow
+use crate::calendar::DateStyler;
+use ratatui::style::Style;
+use std::str::FromStr;
+use thiserror::Error;
+use time::{Date, Weekday};
+
+/// Which stretches of dates to shade every other one of
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub(crate) enum Shading {
+    #[default]
+    Off,
+    Weeks,
+    Months,
+}
+
+impl Shading {
+    pub(crate) const ALL: [Shading; 3] = [Shading::Off, Shading::Weeks, Shading::Months];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Shading::Off => "off",
+            Shading::Weeks => "weeks",
+            Shading::Months => "months",
+        }
+    }
+}
+
+impl FromStr for Shading {
+    type Err = ParseShadingError;
+
+    fn from_str(s: &str) -> Result<Shading, ParseShadingError> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" | "none" => Ok(Shading::Off),
+            "weeks" => Ok(Shading::Weeks),
+            "months" => Ok(Shading::Months),
+            _ => Err(ParseShadingError),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("shading must be \"off\", \"weeks\", or \"months\"")]
+pub(crate) struct ParseShadingError;
+
+/// Applies `style` to every other week or month, as selected by `shading`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct AlternateShading {
+    pub(crate) shading: Shading,
+    pub(crate) style: Style,
+    /// The weekday on which weeks start, so that shaded weeks line up with the
+    /// calendar's rows
+    pub(crate) week_start: Weekday,
+}
+
+impl DateStyler for AlternateShading {
+    fn date_style(&self, date: Date) -> Style {
+        let odd = match self.shading {
+            Shading::Off => false,
+            // Julian day numbers divisible by 7 are Mondays, so adding 1 and
+            // then subtracting the start's distance from Sunday makes the
+            // weeks start on `week_start`.
+            Shading::Weeks => {
+                let start = i32::from(self.week_start.number_days_from_sunday());
+                (date.to_julian_day() + 1 - start).div_euclid(7) % 2 != 0
+            }
+            Shading::Months => (date.year() * 12 + i32::from(u8::from(date.month()))) % 2 != 0,
+        };
+        if odd {
+            self.style
+        } else {
+            Style::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Stylize;
+    use time::macros::date;
+
+    #[test]
+    fn test_weeks() {
+        let shade = AlternateShading {
+            shading: Shading::Weeks,
+            style: Style::new().on_dark_gray(),
+            week_start: Weekday::Sunday,
+        };
+        // Sunday through Saturday share a style, which then flips
+        let week = shade.date_style(date!(2024 - 03 - 10));
+        for day in 11..=16 {
+            let date = Date::from_calendar_date(2024, time::Month::March, day).unwrap();
+            assert_eq!(shade.date_style(date), week);
+        }
+        assert_ne!(shade.date_style(date!(2024 - 03 - 17)), week);
+        assert_eq!(shade.date_style(date!(2024 - 03 - 24)), week);
+        let shade = AlternateShading {
+            week_start: Weekday::Monday,
+            ..shade
+        };
+        let week = shade.date_style(date!(2024 - 03 - 11));
+        assert_eq!(shade.date_style(date!(2024 - 03 - 17)), week);
+        assert_ne!(shade.date_style(date!(2024 - 03 - 18)), week);
+    }
+
+    #[test]
+    fn test_months() {
+        let shade = AlternateShading {
+            shading: Shading::Months,
+            style: Style::new().on_dark_gray(),
+            week_start: Weekday::Sunday,
+        };
+        let december = shade.date_style(date!(2023 - 12 - 31));
+        assert_ne!(shade.date_style(date!(2024 - 01 - 01)), december);
+        assert_eq!(shade.date_style(date!(2024 - 02 - 29)), december);
+        let off = AlternateShading {
+            shading: Shading::Off,
+            style: Style::new().on_dark_gray(),
+            week_start: Weekday::Sunday,
+        };
+        assert_eq!(off.date_style(date!(2024 - 01 - 01)), Style::new());
+    }
+}
@@ -0,0 +1,238 @@
+//! One-line summaries of the Moon's current phase for status bars
+use crate::json::json_string;
+use crate::moon::{Hemisphere, LunarPhase, Phase, PhaseCalculator};
+use std::fmt::Write;
+use std::str::FromStr;
+use thiserror::Error;
+use time::Date;
+
+/// The phase of the Moon on a given date and how long it is until the next
+/// full & new moons
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct MoonStatus {
+    pub(crate) date: Date,
+    pub(crate) phase: LunarPhase,
+    pub(crate) hemisphere: Hemisphere,
+    /// The first day of the current full moon if `date` is one, else of the
+    /// next full moon after `date`
+    pub(crate) full_moon: Option<Date>,
+    /// The first day of the current new moon if `date` is one, else of the
+    /// next new moon after `date`
+    pub(crate) new_moon: Option<Date>,
+}
+
+impl MoonStatus {
+    pub(crate) fn new<P: PhaseCalculator>(
+        phases: &P,
+        date: Date,
+        hemisphere: Hemisphere,
+    ) -> MoonStatus {
+        let phase = phases.lunar_phase(date);
+        let upcoming = |lunar, target| {
+            if phase == lunar {
+                Some(date)
+            } else {
+                phases.next_phase(date, target)
+            }
+        };
+        MoonStatus {
+            date,
+            phase,
+            hemisphere,
+            full_moon: upcoming(LunarPhase::Full, Phase::Full),
+            new_moon: upcoming(LunarPhase::New, Phase::New),
+        }
+    }
+
+    /// Returns whether `date` is a full or new moon
+    pub(crate) fn is_notable(&self) -> bool {
+        matches!(self.phase, LunarPhase::Full | LunarPhase::New)
+    }
+
+    /// Returns the number of days until the next full moon, which is zero
+    /// during a full moon
+    fn days_to_full(&self) -> Option<i64> {
+        self.full_moon.map(|d| (d - self.date).whole_days())
+    }
+
+    /// A short countdown to the next full moon, e.g., "3d"
+    fn countdown(&self) -> String {
+        match self.days_to_full() {
+            Some(0) => String::from("full"),
+            Some(days) => format!("{days}d"),
+            None => String::from("?"),
+        }
+    }
+
+    /// Returns a JSON object for a Waybar custom module with `text`,
+    /// `tooltip`, and `class` fields, the last being the name of the phase
+    /// with hyphens in place of spaces so that it can be styled with CSS
+    pub(crate) fn to_waybar(self) -> String {
+        let text = format!("{} {}", self.phase.glyph(self.hemisphere), self.countdown());
+        let mut tooltip = format!("{}: {}", self.date, self.phase.name());
+        match (self.days_to_full(), self.full_moon) {
+            (Some(0), _) => tooltip.push_str("\nFull moon tonight"),
+            (Some(days), Some(d)) => {
+                let unit = if days == 1 { "day" } else { "days" };
+                let _ = write!(tooltip, "\nFull moon in {days} {unit} ({d})");
+            }
+            _ => (),
+        }
+        let class = self.phase.name().replace(' ', "-");
+        format!(
+            r#"{{"text": {}, "tooltip": {}, "class": {}}}"#,
+            json_string(&text),
+            json_string(&tooltip),
+            json_string(&class),
+        )
+    }
+
+    /// Returns a segment for a tmux status line showing the phase's glyph
+    /// and the countdown to the next full moon, colored yellow during a full
+    /// moon and blue during a new moon
+    pub(crate) fn to_tmux(self) -> String {
+        let style = match self.phase {
+            LunarPhase::Full => "fg=yellow,bold",
+            LunarPhase::New => "fg=blue",
+            _ => "fg=default",
+        };
+        format!(
+            "#[{style}]{} {}#[default]",
+            self.phase.glyph(self.hemisphere),
+            self.countdown()
+        )
+    }
+
+    /// Returns a minimal string for a shell prompt: the full or new moon in
+    /// effect, or else whichever of them comes next followed by the number
+    /// of days until it, e.g., "full-2d".  If `glyph` is true, the moons are
+    /// shown as emoji instead of words, e.g., "🌕-2d".
+    pub(crate) fn to_plain(self, glyph: bool) -> String {
+        let mark = |phase: LunarPhase| {
+            if glyph {
+                phase.glyph(self.hemisphere).to_string()
+            } else if phase == LunarPhase::Full {
+                String::from("full")
+            } else {
+                String::from("new")
+            }
+        };
+        if self.is_notable() {
+            return mark(self.phase);
+        }
+        let next = [
+            (LunarPhase::Full, self.full_moon),
+            (LunarPhase::New, self.new_moon),
+        ]
+        .into_iter()
+        .filter_map(|(phase, date)| Some((phase, date?)))
+        .min_by_key(|&(_, date)| date);
+        match next {
+            Some((phase, date)) => {
+                format!("{}-{}d", mark(phase), (date - self.date).whole_days())
+            }
+            None => String::new(),
+        }
+    }
+}
+
+/// The formats in which `nhmoon status` can output its summary
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum StatusFormat {
+    #[default]
+    Waybar,
+    Tmux,
+    Plain,
+}
+
+impl StatusFormat {
+    pub(crate) const ALL: [StatusFormat; 3] = [
+        StatusFormat::Waybar,
+        StatusFormat::Tmux,
+        StatusFormat::Plain,
+    ];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            StatusFormat::Waybar => "waybar",
+            StatusFormat::Tmux => "tmux",
+            StatusFormat::Plain => "plain",
+        }
+    }
+
+    /// Render `status` in this format.  `glyph` only affects the plain
+    /// format.
+    pub(crate) fn render(self, status: MoonStatus, glyph: bool) -> String {
+        match self {
+            StatusFormat::Waybar => status.to_waybar(),
+            StatusFormat::Tmux => status.to_tmux(),
+            StatusFormat::Plain => status.to_plain(glyph),
+        }
+    }
+}
+
+impl FromStr for StatusFormat {
+    type Err = ParseStatusFormatError;
+
+    fn from_str(s: &str) -> Result<StatusFormat, ParseStatusFormatError> {
+        StatusFormat::ALL
+            .into_iter()
+            .find(|fmt| fmt.name().eq_ignore_ascii_case(s))
+            .ok_or(ParseStatusFormatError)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("status format must be \"waybar\", \"tmux\", or \"plain\"")]
+pub(crate) struct ParseStatusFormatError;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moon::NetHack;
+    use time::macros::date;
+
+    fn status(date: Date) -> MoonStatus {
+        MoonStatus::new(&NetHack, date, Hemisphere::North)
+    }
+
+    #[test]
+    fn test_waybar() {
+        assert_eq!(
+            status(date!(2023 - 11 - 20)).to_waybar(),
+            r#"{"text": "🌓 7d", "tooltip": "2023-11-20: first quarter\nFull moon in 7 days (2023-11-27)", "class": "first-quarter"}"#
+        );
+        assert_eq!(
+            status(date!(2023 - 11 - 28)).to_waybar(),
+            r#"{"text": "🌕 full", "tooltip": "2023-11-28: full moon\nFull moon tonight", "class": "full-moon"}"#
+        );
+    }
+
+    #[test]
+    fn test_tmux() {
+        assert_eq!(
+            status(date!(2023 - 11 - 20)).to_tmux(),
+            "#[fg=default]🌓 7d#[default]"
+        );
+        assert_eq!(
+            status(date!(2023 - 11 - 28)).to_tmux(),
+            "#[fg=yellow,bold]🌕 full#[default]"
+        );
+        assert_eq!("TMUX".parse::<StatusFormat>(), Ok(StatusFormat::Tmux));
+    }
+
+    #[test]
+    fn test_plain() {
+        let normal = status(date!(2023 - 11 - 20));
+        assert_eq!(normal.to_plain(false), "full-7d");
+        assert_eq!(normal.to_plain(true), "🌕-7d");
+        assert!(!normal.is_notable());
+        let after_full = status(date!(2023 - 12 - 01));
+        assert_eq!(after_full.to_plain(false), "new-10d");
+        assert_eq!(after_full.to_plain(true), "🌑-10d");
+        let full = status(date!(2023 - 11 - 28));
+        assert_eq!(full.to_plain(false), "full");
+        assert_eq!(full.to_plain(true), "🌕");
+        assert!(full.is_notable());
+    }
+}
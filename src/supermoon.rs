@@ -0,0 +1,96 @@
+//! Detection of full moons occurring near lunar perigee ("supermoons") or
+//! apogee ("micromoons")
+use crate::astro::moon_distance;
+use crate::calendar::DateStyler;
+use crate::lunation::{local_time_on, Lunation};
+use ratatui::style::Style;
+use std::fmt;
+use time::{Date, UtcOffset};
+
+/// A full moon closer to the Earth than this many kilometers is considered a
+/// supermoon
+const SUPERMOON_DISTANCE: f64 = 360_000.0;
+
+/// A full moon farther from the Earth than this many kilometers is considered
+/// a micromoon
+const MICROMOON_DISTANCE: f64 = 405_000.0;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum FullMoonSize {
+    Supermoon,
+    Micromoon,
+}
+
+impl fmt::Display for FullMoonSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FullMoonSize::Supermoon => write!(f, "supermoon"),
+            FullMoonSize::Micromoon => write!(f, "micromoon"),
+        }
+    }
+}
+
+/// If a full moon occurs on `date` in the timezone given by `offset` and it is
+/// a supermoon or micromoon, return which it is along with the Earth-Moon
+/// distance in kilometers
+pub(crate) fn full_moon_size(date: Date, offset: UtcOffset) -> Option<(FullMoonSize, f64)> {
+    let jd = Lunation::full_moons_near(date)
+        .into_iter()
+        .map(|lun| lun.full_moon())
+        .find(|&jd| local_time_on(jd, date, offset).is_some())?;
+    let distance = moon_distance(jd);
+    if distance < SUPERMOON_DISTANCE {
+        Some((FullMoonSize::Supermoon, distance))
+    } else if distance > MICROMOON_DISTANCE {
+        Some((FullMoonSize::Micromoon, distance))
+    } else {
+        None
+    }
+}
+
+/// A `DateStyler` that highlights supermoons and micromoons with the given
+/// styles
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Supermoons {
+    pub(crate) offset: UtcOffset,
+    pub(crate) supermoon: Style,
+    pub(crate) micromoon: Style,
+}
+
+impl DateStyler for Supermoons {
+    fn date_style(&self, date: Date) -> Style {
+        match full_moon_size(date, self.offset) {
+            Some((FullMoonSize::Supermoon, _)) => self.supermoon,
+            Some((FullMoonSize::Micromoon, _)) => self.micromoon,
+            None => Style::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{date, offset};
+
+    #[test]
+    fn test_supermoon() {
+        // The full moon of 2023-08-31 was the closest of the year, at about
+        // 357,300 km.
+        let size = full_moon_size(date!(2023 - 08 - 31), offset!(UTC));
+        assert!(matches!(size, Some((FullMoonSize::Supermoon, _))));
+    }
+
+    #[test]
+    fn test_micromoon() {
+        // The full moon of 2023-02-05 was the farthest of the year, at about
+        // 406,500 km.
+        let size = full_moon_size(date!(2023 - 02 - 05), offset!(UTC));
+        assert!(matches!(size, Some((FullMoonSize::Micromoon, _))));
+    }
+
+    #[test]
+    fn test_ordinary_full_moon() {
+        assert_eq!(full_moon_size(date!(2023 - 11 - 27), offset!(UTC)), None);
+        assert_eq!(full_moon_size(date!(2023 - 08 - 30), offset!(UTC)), None);
+    }
+}
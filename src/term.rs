@@ -0,0 +1,530 @@
+//! Detecting what the terminal is able to display and toning down the
+//! calendar's output to match
+use crate::theme::{Theme, DEFAULT_THEME};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use ratatui::style::{Color, Modifier, Style};
+use std::env;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+
+/// How long to wait for the terminal to report its background color
+const BACKGROUND_QUERY_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// What the terminal is able to display
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Capabilities {
+    /// Whether box-drawing characters and emoji can be displayed
+    pub(crate) unicode: bool,
+    /// Whether italic text is displayed as such rather than, e.g., in
+    /// reverse video
+    pub(crate) italic: bool,
+    pub(crate) colors: ColorSupport,
+}
+
+impl Capabilities {
+    /// A terminal that can display everything
+    pub(crate) const FULL: Capabilities = Capabilities {
+        unicode: true,
+        italic: true,
+        colors: ColorSupport::TrueColor,
+    };
+
+    /// A Windows console without support for VT escape sequences, which
+    /// crossterm drives through the Windows console API instead
+    pub(crate) const LEGACY_CONSOLE: Capabilities = Capabilities {
+        unicode: false,
+        italic: false,
+        colors: ColorSupport::Basic,
+    };
+
+    /// Determine the capabilities of the terminal on standard output
+    pub(crate) fn detect() -> Capabilities {
+        if is_legacy_console() {
+            Capabilities::LEGACY_CONSOLE
+        } else {
+            let term = env::var("TERM").ok();
+            let colorterm = env::var("COLORTERM").ok();
+            Capabilities::from_env(term.as_deref(), colorterm.as_deref())
+        }
+    }
+
+    /// Determine the capabilities of a terminal from the values of the `TERM`
+    /// and `COLORTERM` environment variables.  If `TERM` is unset (as in most
+    /// Windows terminals), the terminal is assumed to support everything.
+    ///
+    /// GNU Screen, and tmux when `TERM` is still set to Screen's `screen` or
+    /// `screen-256color` entries, show italics in reverse video, as do older
+    /// terminfo entries like `linux` and `vt100`.  Only terminals whose
+    /// `TERM` ends in `256color` are assumed to support more than the 16
+    /// named colors, unless `COLORTERM` advertises true color support.
+    fn from_env(term: Option<&str>, colorterm: Option<&str>) -> Capabilities {
+        let Some(term) = term else {
+            return Capabilities::FULL;
+        };
+        let colors = if matches!(colorterm, Some("truecolor" | "24bit")) {
+            ColorSupport::TrueColor
+        } else if term.ends_with("256color") {
+            ColorSupport::Indexed
+        } else {
+            ColorSupport::Basic
+        };
+        let base = term.split('-').next().unwrap_or_default();
+        let old = matches!(base, "linux" | "vt100" | "vt220" | "ansi" | "dumb");
+        Capabilities {
+            unicode: !matches!(base, "vt100" | "vt220" | "dumb"),
+            italic: !(old || base == "screen"),
+            colors,
+        }
+    }
+
+    /// Returns the capabilities with the color depth replaced by `colors`, if
+    /// given (e.g., by `--color-depth`)
+    pub(crate) fn with_colors(self, colors: Option<ColorSupport>) -> Capabilities {
+        Capabilities {
+            colors: colors.unwrap_or(self.colors),
+            ..self
+        }
+    }
+
+    /// Returns `theme` with any colors that the terminal can't display
+    /// replaced by the closest ones that it can and, if the terminal can't
+    /// display italics, with italic text underlined instead
+    pub(crate) fn adapt_theme(self, mut theme: Theme) -> Theme {
+        for style in theme.styles_mut() {
+            *style = self.adapt_style(*style);
+        }
+        theme
+    }
+
+    fn adapt_style(self, mut style: Style) -> Style {
+        style.fg = style.fg.map(|c| self.colors.adapt(c));
+        style.bg = style.bg.map(|c| self.colors.adapt(c));
+        if !self.italic && style.add_modifier.contains(Modifier::ITALIC) {
+            style.add_modifier.remove(Modifier::ITALIC);
+            style.add_modifier.insert(Modifier::UNDERLINED);
+        }
+        style
+    }
+}
+
+/// How many colors the terminal can display
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ColorSupport {
+    /// The 16 named ANSI colors
+    Basic,
+    /// The 256-color xterm palette
+    Indexed,
+    /// Arbitrary RGB colors
+    TrueColor,
+}
+
+impl FromStr for ColorSupport {
+    type Err = ParseColorSupportError;
+
+    fn from_str(s: &str) -> Result<ColorSupport, ParseColorSupportError> {
+        match s.to_ascii_lowercase().as_str() {
+            "16" => Ok(ColorSupport::Basic),
+            "256" => Ok(ColorSupport::Indexed),
+            "truecolor" | "24bit" => Ok(ColorSupport::TrueColor),
+            _ => Err(ParseColorSupportError),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
+#[error("color depth must be \"16\", \"256\", or \"truecolor\"")]
+pub(crate) struct ParseColorSupportError;
+
+impl ColorSupport {
+    fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::Basic, Color::Rgb(r, g, b)) => nearest_basic((r, g, b)),
+            (ColorSupport::Basic, Color::Indexed(i)) => BASIC_COLORS
+                .get(usize::from(i))
+                .map_or_else(|| nearest_basic(indexed_to_rgb(i)), |&(basic, _)| basic),
+            (ColorSupport::Indexed, Color::Rgb(r, g, b)) => nearest_indexed((r, g, b)),
+            (_, color) => color,
+        }
+    }
+}
+
+/// Whether the terminal's background is dark or light, which determines the
+/// theme used when none is selected
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum Background {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Background {
+    /// Ask the terminal on standard output for its background color with an
+    /// OSC 11 query.  If the terminal doesn't answer in time (or standard
+    /// output isn't a terminal), the background is assumed to be dark.
+    pub(crate) fn detect() -> Background {
+        if !io::stdout().is_terminal() || enable_raw_mode().is_err() {
+            return Background::Dark;
+        }
+        let rgb = query_background().ok().flatten();
+        let _ = disable_raw_mode();
+        rgb.map_or(Background::Dark, Background::from_rgb)
+    }
+
+    fn from_rgb((r, g, b): (u8, u8, u8)) -> Background {
+        // Relative luminance per ITU-R BT.709, scaled by 255 × 10000
+        let luma = 2126 * u32::from(r) + 7152 * u32::from(g) + 722 * u32::from(b);
+        if luma > 1_275_000 {
+            Background::Light
+        } else {
+            Background::Dark
+        }
+    }
+
+    /// The name of the built-in theme suited to the background
+    pub(crate) fn default_theme(self) -> &'static str {
+        match self {
+            Background::Dark => DEFAULT_THEME,
+            Background::Light => "light",
+        }
+    }
+}
+
+/// Returns the OSC 52 escape sequence asking the terminal to put `text` on
+/// the system clipboard
+pub(crate) fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Encode `data` in standard, padded base64
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .chain(std::iter::repeat(&0))
+            .take(3)
+            .fold(0usize, |n, &b| (n << 8) | usize::from(b));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let sextet = (n >> (18 - 6 * i)) & 0x3F;
+                s.push(char::from(ALPHABET[sextet]));
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+/// Send an OSC 11 query to the terminal and return the color in its reply.
+/// The terminal must be in raw mode.
+#[cfg(unix)]
+fn query_background() -> io::Result<Option<(u8, u8, u8)>> {
+    use mio::{unix::SourceFd, Events, Interest, Poll, Token};
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+    let mut poll = Poll::new()?;
+    poll.registry().register(
+        &mut SourceFd(&tty.as_raw_fd()),
+        Token(0),
+        Interest::READABLE,
+    )?;
+    // Follow the query with a request for the primary device attributes,
+    // which every terminal answers, so that terminals that ignore OSC 11
+    // don't make us wait for the full timeout
+    tty.write_all(b"\x1b]11;?\x07\x1b[c")?;
+    tty.flush()?;
+    let deadline = Instant::now() + BACKGROUND_QUERY_TIMEOUT;
+    let mut events = Events::with_capacity(1);
+    let mut reply = Vec::new();
+    let mut buf = [0; 256];
+    while !has_device_attributes(&reply) {
+        let Some(timeout) = deadline.checked_duration_since(Instant::now()) else {
+            break;
+        };
+        match poll.poll(&mut events, Some(timeout)) {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+        if events.is_empty() {
+            break;
+        }
+        let n = tty.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        reply.extend_from_slice(&buf[..n]);
+    }
+    Ok(parse_background_reply(&reply))
+}
+
+#[cfg(not(unix))]
+fn query_background() -> io::Result<Option<(u8, u8, u8)>> {
+    Ok(None)
+}
+
+/// Returns true if `reply` contains a complete primary device attributes
+/// report of the form `ESC [ ? ... c`
+#[cfg_attr(not(unix), allow(dead_code))]
+fn has_device_attributes(reply: &[u8]) -> bool {
+    reply
+        .windows(3)
+        .position(|w| w == b"\x1b[?")
+        .is_some_and(|i| reply[i..].contains(&b'c'))
+}
+
+/// Extract the color from a reply to an OSC 11 query of the form
+/// `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` terminated by BEL or `ESC \`, where each
+/// component has one to four hex digits
+fn parse_background_reply(reply: &[u8]) -> Option<(u8, u8, u8)> {
+    let reply = String::from_utf8_lossy(reply);
+    let (_, rest) = reply.split_once("\x1b]11;rgb:")?;
+    let end = rest.find(['\x07', '\x1b'])?;
+    let mut components = rest[..end].split('/').map(|c| {
+        let digits = match c.len() {
+            1 => 1,
+            2 => 2,
+            3 => 3,
+            4 => 4,
+            _ => return None,
+        };
+        let value = u32::from_str_radix(c, 16).ok()?;
+        u8::try_from(value * 255 / (16u32.pow(digits) - 1)).ok()
+    });
+    let rgb = (
+        components.next()??,
+        components.next()??,
+        components.next()??,
+    );
+    components.next().is_none().then_some(rgb)
+}
+
+/// The named ANSI colors in palette order, along with their RGB values in
+/// xterm's default palette
+static BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (128, 0, 0)),
+    (Color::Green, (0, 128, 0)),
+    (Color::Yellow, (128, 128, 0)),
+    (Color::Blue, (0, 0, 128)),
+    (Color::Magenta, (128, 0, 128)),
+    (Color::Cyan, (0, 128, 128)),
+    (Color::Gray, (192, 192, 192)),
+    (Color::DarkGray, (128, 128, 128)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (0, 0, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// The intensities of the levels of each channel in the 6×6×6 color cube
+/// occupying indices 16 through 231 of the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn indexed_to_rgb(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..=15 => BASIC_COLORS[usize::from(i)].1,
+        16..=231 => {
+            let i = usize::from(i - 16);
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[(i / 6) % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        _ => {
+            let level = 8 + (i - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+fn nearest_basic(rgb: (u8, u8, u8)) -> Color {
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|&&(_, c)| distance(rgb, c))
+        .map_or(Color::Reset, |&(color, _)| color)
+}
+
+/// Returns the closest color in the 256-color palette outside of the named
+/// colors, whose actual values vary between terminals
+fn nearest_indexed(rgb: (u8, u8, u8)) -> Color {
+    (16..=255)
+        .min_by_key(|&i| distance(rgb, indexed_to_rgb(i)))
+        .map_or(Color::Reset, Color::Indexed)
+}
+
+/// Squared Euclidean distance between two colors
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    [(r1, r2), (g1, g2), (b1, b2)]
+        .into_iter()
+        .map(|(a, b)| u32::from(a.abs_diff(b)).pow(2))
+        .sum()
+}
+
+/// Returns true if standard output is a Windows console that doesn't
+/// understand VT escape sequences
+#[cfg(windows)]
+fn is_legacy_console() -> bool {
+    !crossterm::ansi_support::supports_ansi()
+}
+
+#[cfg(not(windows))]
+fn is_legacy_console() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Stylize;
+
+    #[test]
+    fn test_adapt_color() {
+        let basic = ColorSupport::Basic;
+        assert_eq!(basic.adapt(Color::Rgb(250, 10, 20)), Color::LightRed);
+        assert_eq!(basic.adapt(Color::Indexed(3)), Color::Yellow);
+        assert_eq!(basic.adapt(Color::Indexed(208)), Color::LightYellow);
+        assert_eq!(basic.adapt(Color::Indexed(244)), Color::DarkGray);
+        assert_eq!(basic.adapt(Color::LightBlue), Color::LightBlue);
+        let indexed = ColorSupport::Indexed;
+        assert_eq!(
+            indexed.adapt(Color::Rgb(0xff, 0x87, 0x00)),
+            Color::Indexed(208)
+        );
+        assert_eq!(indexed.adapt(Color::Indexed(208)), Color::Indexed(208));
+        assert_eq!(
+            ColorSupport::TrueColor.adapt(Color::Rgb(1, 2, 3)),
+            Color::Rgb(1, 2, 3)
+        );
+    }
+
+    #[test]
+    fn test_legacy_console_theme() {
+        let theme = Theme {
+            full_moon: Style::new()
+                .fg(Color::Rgb(0xff, 0xd7, 0x00))
+                .bg(Color::Indexed(17)),
+            ..Theme::default()
+        };
+        let theme = Capabilities::LEGACY_CONSOLE.adapt_theme(theme);
+        assert_eq!(
+            theme.full_moon,
+            Style::new().fg(Color::LightYellow).bg(Color::Blue)
+        );
+        assert_eq!(theme.new_moon, Theme::default().new_moon);
+    }
+
+    #[test]
+    fn test_parse_color_support() {
+        assert_eq!("16".parse::<ColorSupport>(), Ok(ColorSupport::Basic));
+        assert_eq!("256".parse::<ColorSupport>(), Ok(ColorSupport::Indexed));
+        assert_eq!(
+            "TrueColor".parse::<ColorSupport>(),
+            Ok(ColorSupport::TrueColor)
+        );
+        assert_eq!("8".parse::<ColorSupport>(), Err(ParseColorSupportError));
+        let caps = Capabilities::LEGACY_CONSOLE.with_colors(Some(ColorSupport::Indexed));
+        assert_eq!(caps.colors, ColorSupport::Indexed);
+        assert!(!caps.unicode);
+        assert_eq!(caps.with_colors(None), caps);
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foob"), "Zm9vYg==");
+        assert_eq!(base64(b"| \xFF |"), "fCD/IHw=");
+    }
+
+    #[test]
+    fn test_parse_background_reply() {
+        assert_eq!(
+            parse_background_reply(b"\x1b]11;rgb:ffff/ffff/ffff\x07\x1b[?62;22c"),
+            Some((255, 255, 255))
+        );
+        assert_eq!(
+            parse_background_reply(b"\x1b]11;rgb:1e1e/2020/3030\x1b\\"),
+            Some((30, 32, 48))
+        );
+        assert_eq!(
+            parse_background_reply(b"\x1b]11;rgb:f/80/000\x07"),
+            Some((255, 128, 0))
+        );
+        assert_eq!(parse_background_reply(b"\x1b[?62;22c"), None);
+        assert_eq!(parse_background_reply(b"\x1b]11;rgb:ffff/ffff"), None);
+        assert!(has_device_attributes(b"\x1b]11;rgb:0/0/0\x07\x1b[?1;2c"));
+        assert!(!has_device_attributes(b"\x1b]11;rgb:0/0/0\x07\x1b[?1;2"));
+    }
+
+    #[test]
+    fn test_background_from_rgb() {
+        assert_eq!(Background::from_rgb((255, 255, 255)), Background::Light);
+        assert_eq!(Background::from_rgb((253, 246, 227)), Background::Light);
+        assert_eq!(Background::from_rgb((0, 43, 54)), Background::Dark);
+        assert_eq!(Background::from_rgb((0, 0, 255)), Background::Dark);
+    }
+
+    #[test]
+    fn test_from_env() {
+        assert_eq!(Capabilities::from_env(None, None), Capabilities::FULL);
+        assert_eq!(
+            Capabilities::from_env(Some("xterm-256color"), Some("truecolor")),
+            Capabilities::FULL
+        );
+        assert_eq!(
+            Capabilities::from_env(Some("tmux-256color"), None),
+            Capabilities {
+                unicode: true,
+                italic: true,
+                colors: ColorSupport::Indexed,
+            }
+        );
+        assert_eq!(
+            Capabilities::from_env(Some("screen-256color"), None),
+            Capabilities {
+                unicode: true,
+                italic: false,
+                colors: ColorSupport::Indexed,
+            }
+        );
+        assert_eq!(
+            Capabilities::from_env(Some("linux"), None),
+            Capabilities {
+                unicode: true,
+                italic: false,
+                colors: ColorSupport::Basic,
+            }
+        );
+        assert_eq!(
+            Capabilities::from_env(Some("vt100"), None),
+            Capabilities {
+                unicode: false,
+                italic: false,
+                colors: ColorSupport::Basic,
+            }
+        );
+    }
+
+    #[test]
+    fn test_screen_italics() {
+        let caps = Capabilities::from_env(Some("screen"), None);
+        let theme = caps.adapt_theme(Theme::builtin("mono").unwrap());
+        assert_eq!(theme.micromoon, Style::new().underlined());
+        assert_eq!(theme.supermoon, Style::new().bold().underlined());
+    }
+}
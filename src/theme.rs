@@ -0,0 +1,598 @@
+//! Color schemes for the calendar and its popups.  Besides the built-in themes,
+//! a theme can be loaded from a file `NAME.toml` in the `themes`
+//! subdirectory of the configuration directory or from an explicit path.
+use crate::calendar::CalendarStyles;
+use crate::config::{show_errors, ParseError};
+use crate::dirs;
+use crate::popup::PopupStyle;
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use toml::{Spanned, Value};
+
+/// The name of the theme used when none is selected and the terminal's
+/// background isn't known to be light
+pub(crate) const DEFAULT_THEME: &str = "default";
+
+/// The styles applied to each element of the display
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Theme {
+    /// The style of the screen as a whole, over which the other styles are
+    /// applied, and of the text in popups
+    pub(crate) base: Style,
+    pub(crate) header: Style,
+    pub(crate) year: Style,
+    pub(crate) month: Style,
+    pub(crate) border: Style,
+    /// The lines between December and January
+    pub(crate) year_border: Style,
+    /// The dates given on the command line
+    pub(crate) marked: Style,
+    pub(crate) today: Style,
+    pub(crate) today_marker: Style,
+    /// The style of the borders & titles of popups
+    pub(crate) popup_border: Style,
+    pub(crate) full_moon: Style,
+    pub(crate) new_moon: Style,
+    pub(crate) supermoon: Style,
+    pub(crate) micromoon: Style,
+    pub(crate) eclipse: Style,
+    pub(crate) friday_13th: Style,
+    pub(crate) community_date: Style,
+    /// Every other week or month (with `--shade`)
+    pub(crate) shade: Style,
+}
+
+impl Theme {
+    /// Names of the built-in themes
+    pub(crate) const BUILTINS: [&'static str; 3] = [DEFAULT_THEME, "light", "mono"];
+
+    /// The theme file keys of the elements, in the same order as
+    /// [`Theme::styles_mut()`]
+    pub(crate) const ELEMENTS: [&'static str; 18] = [
+        "base",
+        "header",
+        "year",
+        "month",
+        "border",
+        "year-border",
+        "marked",
+        "today",
+        "today-marker",
+        "popup-border",
+        "full-moon",
+        "new-moon",
+        "supermoon",
+        "micromoon",
+        "eclipse",
+        "friday-13th",
+        "community-date",
+        "shade",
+    ];
+
+    pub(crate) fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            DEFAULT_THEME => Some(Theme::default()),
+            "light" => Some(Theme {
+                base: Style::new().black().on_white(),
+                header: Style::new().bold(),
+                year: Style::new().bold(),
+                month: Style::new().bold(),
+                border: Style::new(),
+                year_border: Style::new().cyan(),
+                marked: Style::new().on_gray(),
+                today: Style::new(),
+                today_marker: Style::new(),
+                popup_border: Style::new(),
+                full_moon: Style::new().yellow().bold(),
+                new_moon: Style::new().blue(),
+                supermoon: Style::new().magenta().bold(),
+                micromoon: Style::new().magenta(),
+                eclipse: Style::new().red().bold(),
+                friday_13th: Style::new().on_light_red(),
+                community_date: Style::new().underlined(),
+                shade: Style::new().bg(Color::Indexed(254)),
+            }),
+            "mono" => Some(Theme {
+                base: Style::new(),
+                header: Style::new().bold(),
+                year: Style::new().bold(),
+                month: Style::new().bold(),
+                border: Style::new(),
+                year_border: Style::new().bold(),
+                marked: Style::new().underlined(),
+                today: Style::new().reversed(),
+                today_marker: Style::new().bold(),
+                popup_border: Style::new(),
+                full_moon: Style::new().bold(),
+                new_moon: Style::new().dim(),
+                supermoon: Style::new().bold().italic(),
+                micromoon: Style::new().italic(),
+                eclipse: Style::new().bold().underlined(),
+                friday_13th: Style::new().reversed(),
+                community_date: Style::new().underlined(),
+                shade: Style::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn calendar_styles(&self) -> CalendarStyles {
+        CalendarStyles {
+            header: self.header,
+            year: self.year,
+            month: self.month,
+            border: self.border,
+            year_border: self.year_border,
+            marked: self.marked,
+            today: self.today,
+            today_marker: self.today_marker,
+        }
+    }
+
+    pub(crate) fn popup_style(&self) -> PopupStyle {
+        PopupStyle {
+            text: self.base,
+            border: self.popup_border,
+            ascii: false,
+        }
+    }
+
+    /// Returns mutable references to all of the theme's styles
+    pub(crate) fn styles_mut(&mut self) -> [&mut Style; 18] {
+        [
+            &mut self.base,
+            &mut self.header,
+            &mut self.year,
+            &mut self.month,
+            &mut self.border,
+            &mut self.year_border,
+            &mut self.marked,
+            &mut self.today,
+            &mut self.today_marker,
+            &mut self.popup_border,
+            &mut self.full_moon,
+            &mut self.new_moon,
+            &mut self.supermoon,
+            &mut self.micromoon,
+            &mut self.eclipse,
+            &mut self.friday_13th,
+            &mut self.community_date,
+            &mut self.shade,
+        ]
+    }
+
+    /// Turn the foreground colors of the moon phase styles into backgrounds
+    /// filling the dates' whole cells, with the digits drawn in the color of
+    /// the base background.  Styles without a foreground color are reversed
+    /// instead.
+    fn fill_phases(&mut self) {
+        let digits = self.base.bg.unwrap_or(Color::Reset);
+        for style in [
+            &mut self.full_moon,
+            &mut self.new_moon,
+            &mut self.supermoon,
+            &mut self.micromoon,
+            &mut self.eclipse,
+        ] {
+            *style = match style.fg {
+                Some(color) => style.bg(color).fg(digits),
+                None => style.reversed(),
+            };
+        }
+    }
+
+    /// Returns the contents of a theme file setting every element to its
+    /// style in this theme
+    pub(crate) fn to_toml(mut self) -> String {
+        let mut s = String::new();
+        for (key, style) in Theme::ELEMENTS.into_iter().zip(self.styles_mut()) {
+            let _ = writeln!(s, "{key} = \"{}\"", format_style(*style));
+        }
+        s
+    }
+
+    /// Returns the style for the theme file key `key`
+    fn element_mut(&mut self, key: &str) -> Option<&mut Style> {
+        match key {
+            "base" => Some(&mut self.base),
+            "header" => Some(&mut self.header),
+            "year" => Some(&mut self.year),
+            "month" => Some(&mut self.month),
+            "border" => Some(&mut self.border),
+            "year-border" => Some(&mut self.year_border),
+            "marked" => Some(&mut self.marked),
+            "today" => Some(&mut self.today),
+            "today-marker" => Some(&mut self.today_marker),
+            "popup-border" => Some(&mut self.popup_border),
+            "full-moon" => Some(&mut self.full_moon),
+            "new-moon" => Some(&mut self.new_moon),
+            "supermoon" => Some(&mut self.supermoon),
+            "micromoon" => Some(&mut self.micromoon),
+            "eclipse" => Some(&mut self.eclipse),
+            "friday-13th" => Some(&mut self.friday_13th),
+            "community-date" => Some(&mut self.community_date),
+            "shade" => Some(&mut self.shade),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            base: Style::new().white().on_black(),
+            header: Style::new().bold(),
+            year: Style::new().bold(),
+            month: Style::new().bold(),
+            border: Style::new(),
+            year_border: Style::new().light_cyan(),
+            marked: Style::new().on_dark_gray(),
+            today: Style::new(),
+            today_marker: Style::new(),
+            popup_border: Style::new(),
+            full_moon: Style::new().light_yellow().bold(),
+            new_moon: Style::new().light_blue(),
+            supermoon: Style::new().light_magenta().bold(),
+            micromoon: Style::new().magenta(),
+            eclipse: Style::new().light_red().bold(),
+            // Only a background, so that it combines with the phase's style
+            friday_13th: Style::new().on_red(),
+            community_date: Style::new().underlined(),
+            shade: Style::new().bg(Color::Indexed(236)),
+        }
+    }
+}
+
+/// Load the theme named `name`.  A name containing a path separator or
+/// ending in `.toml` is treated as the path to a theme file; otherwise,
+/// `NAME.toml` in the themes directory is used if it exists, else the
+/// built-in theme of that name.
+pub(crate) fn load_theme(name: &str) -> Result<Theme, ThemeError> {
+    let path = if name.contains(['/', std::path::MAIN_SEPARATOR]) || name.ends_with(".toml") {
+        PathBuf::from(name)
+    } else {
+        match dirs::themes_dir().map(|dir| dir.join(format!("{name}.toml"))) {
+            Some(path) if path.exists() => path,
+            _ => return Theme::builtin(name).ok_or_else(|| ThemeError::Unknown(name.to_owned())),
+        }
+    };
+    read_theme(&path)
+}
+
+/// Save `theme` as `NAME.toml` in the themes directory so that it can be
+/// selected with `--theme NAME`, returning the path written to
+pub(crate) fn save_theme(name: &str, theme: Theme) -> Result<PathBuf, ThemeError> {
+    if name.is_empty() || name.contains(['/', '\\', '.']) {
+        return Err(ThemeError::BadName(name.to_owned()));
+    }
+    let dir = dirs::themes_dir().ok_or(ThemeError::NoDir)?;
+    let path = dir.join(format!("{name}.toml"));
+    let r = fs::create_dir_all(&dir).and_then(|()| fs::write(&path, theme.to_toml()));
+    match r {
+        Ok(()) => Ok(path),
+        Err(source) => Err(ThemeError::Write { path, source }),
+    }
+}
+
+fn read_theme(path: &Path) -> Result<Theme, ThemeError> {
+    let src = fs::read_to_string(path).map_err(|source| ThemeError::Read {
+        path: path.to_owned(),
+        source,
+    })?;
+    parse_theme(&src).map_err(|errors| ThemeError::Invalid {
+        path: path.to_owned(),
+        errors,
+    })
+}
+
+/// Parse the contents of a theme file.  Each top-level key sets the style of
+/// one element, the optional `inherits` key names the built-in theme that
+/// supplies the styles of any elements not set [default: `default`], and the
+/// optional boolean `phase-fill` key turns the moon phases' colors into cell
+/// backgrounds.
+fn parse_theme(src: &str) -> Result<Theme, Vec<ParseError>> {
+    let table = toml::from_str::<BTreeMap<String, Spanned<Value>>>(src)
+        .map_err(|e| vec![ParseError::from_toml(src, &e)])?;
+    let mut errors = Vec::new();
+    let mut theme = Theme::default();
+    let mut phase_fill = false;
+    if let Some(value) = table.get("inherits") {
+        let err = |msg: String| ParseError::at(src, value.span().start, msg);
+        match value.get_ref() {
+            Value::String(name) => match Theme::builtin(name) {
+                Some(base) => theme = base,
+                None => errors.push(err(format!("unknown built-in theme {name:?}"))),
+            },
+            value => errors.push(err(format!(
+                "expected string for \"inherits\", got {}",
+                value.type_str()
+            ))),
+        }
+    }
+    for (key, value) in &table {
+        let err = |msg: String| ParseError::at(src, value.span().start, msg);
+        match (key.as_str(), value.get_ref()) {
+            ("inherits", _) => continue,
+            (_, Value::Table(_)) => {
+                errors.push(err(format!("unknown table [{key}]")));
+                continue;
+            }
+            ("phase-fill", &Value::Boolean(b)) => {
+                phase_fill = b;
+                continue;
+            }
+            ("phase-fill", value) => {
+                errors.push(err(format!(
+                    "expected boolean for \"phase-fill\", got {}",
+                    value.type_str()
+                )));
+                continue;
+            }
+            _ => (),
+        }
+        let Some(slot) = theme.element_mut(key) else {
+            errors.push(err(format!("unknown key {key:?}")));
+            continue;
+        };
+        let Value::String(spec) = value.get_ref() else {
+            errors.push(err(format!(
+                "expected string for {key:?}, got {}",
+                value.get_ref().type_str()
+            )));
+            continue;
+        };
+        match parse_style(spec) {
+            Ok(style) => *slot = style,
+            Err(e) => errors.push(err(format!("invalid value for {key:?}: {e}"))),
+        }
+    }
+    if phase_fill {
+        theme.fill_phases();
+    }
+    if errors.is_empty() {
+        Ok(theme)
+    } else {
+        errors.sort_by_key(|e| e.line);
+        Err(errors)
+    }
+}
+
+/// Parse a style specification: a space-separated list of a foreground
+/// color, a background color prefixed with `on-`, and/or modifiers.  Colors
+/// may be given by name, as `#rrggbb`, or as an index from 0 to 255.  The
+/// specification `none` denotes no styling.
+pub(crate) fn parse_style(spec: &str) -> Result<Style, ParseStyleError> {
+    let mut style = Style::new();
+    for word in spec.split_whitespace() {
+        let word = word.to_ascii_lowercase();
+        if word == "none" {
+            continue;
+        } else if let Some(modifier) = parse_modifier(&word) {
+            style = style.add_modifier(modifier);
+        } else if let Some(bg) = word.strip_prefix("on-") {
+            style = style.bg(parse_color(bg).ok_or_else(|| ParseStyleError(bg.to_owned()))?);
+        } else {
+            style = style.fg(parse_color(&word).ok_or(ParseStyleError(word))?);
+        }
+    }
+    Ok(style)
+}
+
+/// Returns the specification of `style` as parsed by [`parse_style()`]
+pub(crate) fn format_style(style: Style) -> String {
+    let mut words = Vec::new();
+    if let Some(fg) = style.fg {
+        words.push(format_color(fg));
+    }
+    if let Some(bg) = style.bg {
+        words.push(format!("on-{}", format_color(bg)));
+    }
+    for (name, modifier) in MODIFIERS {
+        if style.add_modifier.contains(modifier) {
+            words.push(name.to_owned());
+        }
+    }
+    if words.is_empty() {
+        String::from("none")
+    } else {
+        words.join(" ")
+    }
+}
+
+/// The modifiers that can be given in style specifications
+pub(crate) const MODIFIERS: [(&str, Modifier); 5] = [
+    ("bold", Modifier::BOLD),
+    ("dim", Modifier::DIM),
+    ("italic", Modifier::ITALIC),
+    ("underlined", Modifier::UNDERLINED),
+    ("reversed", Modifier::REVERSED),
+];
+
+fn parse_modifier(s: &str) -> Option<Modifier> {
+    MODIFIERS
+        .into_iter()
+        .find(|&(name, _)| name == s)
+        .map(|(_, modifier)| modifier)
+}
+
+/// The colors that have names in style specifications, in the order that the
+/// theme editor cycles through them
+pub(crate) const NAMED_COLORS: [(&str, Color); 17] = [
+    ("reset", Color::Reset),
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("gray", Color::Gray),
+    ("dark-gray", Color::DarkGray),
+    ("light-red", Color::LightRed),
+    ("light-green", Color::LightGreen),
+    ("light-yellow", Color::LightYellow),
+    ("light-blue", Color::LightBlue),
+    ("light-magenta", Color::LightMagenta),
+    ("light-cyan", Color::LightCyan),
+    ("white", Color::White),
+];
+
+pub(crate) fn format_color(color: Color) -> String {
+    match color {
+        Color::Rgb(r, g, b) => format!("#{r:02x}{g:02x}{b:02x}"),
+        Color::Indexed(i) => i.to_string(),
+        color => NAMED_COLORS
+            .into_iter()
+            .find(|&(_, c)| c == color)
+            .map_or_else(|| String::from("reset"), |(name, _)| name.to_owned()),
+    }
+}
+
+fn parse_color(s: &str) -> Option<Color> {
+    let s = match s {
+        "grey" => "gray",
+        "dark-grey" => "dark-gray",
+        s => s,
+    };
+    if let Some(&(_, color)) = NAMED_COLORS.iter().find(|&&(name, _)| name == s) {
+        return Some(color);
+    }
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let rgb = u32::from_str_radix(hex, 16).ok()?;
+        let [_, r, g, b] = rgb.to_be_bytes();
+        Some(Color::Rgb(r, g, b))
+    } else {
+        s.parse::<u8>().ok().map(Color::Indexed)
+    }
+}
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+#[error("unknown color or modifier {0:?}")]
+pub(crate) struct ParseStyleError(String);
+
+#[derive(Debug, Error)]
+pub(crate) enum ThemeError {
+    #[error("unknown theme {0:?}; built-in themes are: {}", Theme::BUILTINS.join(", "))]
+    Unknown(String),
+    #[error("failed to read theme file {}", .path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("theme names must be nonempty and not contain slashes or periods: {0:?}")]
+    BadName(String),
+    #[error("could not determine themes directory")]
+    NoDir,
+    #[error("failed to write theme file {}", .path.display())]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("invalid theme file {}:{}", .path.display(), show_errors(.errors))]
+    Invalid {
+        path: PathBuf,
+        errors: Vec<ParseError>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!(
+            parse_style("light-yellow on-#102030 bold"),
+            Ok(Style::new()
+                .fg(Color::LightYellow)
+                .bg(Color::Rgb(0x10, 0x20, 0x30))
+                .bold())
+        );
+        assert_eq!(
+            parse_style("208 Underlined"),
+            Ok(Style::new().fg(Color::Indexed(208)).underlined())
+        );
+        assert_eq!(parse_style("none"), Ok(Style::new()));
+        assert_eq!(
+            parse_style("bold purple"),
+            Err(ParseStyleError("purple".into()))
+        );
+        assert_eq!(
+            parse_style("on-#12345"),
+            Err(ParseStyleError("#12345".into()))
+        );
+    }
+
+    #[test]
+    fn test_format_style() {
+        for spec in ["light-yellow on-#102030 bold", "208 underlined", "none"] {
+            assert_eq!(format_style(parse_style(spec).unwrap()), spec);
+        }
+        let theme = Theme::builtin("light").unwrap();
+        assert_eq!(parse_theme(&theme.to_toml()), Ok(theme));
+    }
+
+    #[test]
+    fn test_parse_theme() {
+        let src = "inherits = \"mono\"\nfull-moon = \"yellow bold\"\n";
+        let theme = parse_theme(src).unwrap();
+        assert_eq!(theme.full_moon, Style::new().yellow().bold());
+        assert_eq!(theme.new_moon, Theme::builtin("mono").unwrap().new_moon);
+        let theme = parse_theme("eclipse = \"none\"\n").unwrap();
+        assert_eq!(theme.eclipse, Style::new());
+        assert_eq!(theme.full_moon, Theme::default().full_moon);
+    }
+
+    #[test]
+    fn test_phase_fill() {
+        let src = "phase-fill = true\nnew-moon = \"bold\"\n";
+        let theme = parse_theme(src).unwrap();
+        assert_eq!(
+            theme.full_moon,
+            Style::new().black().on_light_yellow().bold()
+        );
+        assert_eq!(theme.new_moon, Style::new().bold().reversed());
+        assert_eq!(theme.friday_13th, Theme::default().friday_13th);
+        let errors = parse_theme("phase-fill = \"yes\"\n").unwrap_err();
+        assert_eq!(
+            errors[0].msg,
+            "expected boolean for \"phase-fill\", got string"
+        );
+    }
+
+    #[test]
+    fn test_parse_theme_errors() {
+        let src = "inherits = \"neon\"\nnew-moon = \"blurple\"\nmoon = \"red\"\neclipse = true\n";
+        let errors = parse_theme(src).unwrap_err();
+        assert_eq!(
+            errors.iter().map(|e| e.msg.as_str()).collect::<Vec<_>>(),
+            [
+                "unknown built-in theme \"neon\"",
+                "invalid value for \"new-moon\": unknown color or modifier \"blurple\"",
+                "unknown key \"moon\"",
+                "expected string for \"eclipse\", got boolean",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builtins() {
+        for name in Theme::BUILTINS {
+            assert!(Theme::builtin(name).is_some());
+        }
+        assert!(matches!(
+            load_theme("no-such-theme"),
+            Err(ThemeError::Unknown(_))
+        ));
+    }
+}
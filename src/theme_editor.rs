@@ -0,0 +1,176 @@
+//! The theme editor popup, for adjusting the current theme's styles while
+//! previewing them on the calendar
+use crate::popup::{Popup, PopupStyle};
+use crate::theme::{format_color, format_style, Theme, MODIFIERS, NAMED_COLORS};
+use ratatui::prelude::*;
+
+/// The number of elements listed at once
+const VISIBLE: usize = 7;
+
+/// A part of a style that can be adjusted in the editor
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum StyleField {
+    Foreground,
+    Background,
+    Modifier(Modifier),
+}
+
+impl StyleField {
+    pub(crate) const ALL: [StyleField; 7] = [
+        StyleField::Foreground,
+        StyleField::Background,
+        StyleField::Modifier(Modifier::BOLD),
+        StyleField::Modifier(Modifier::DIM),
+        StyleField::Modifier(Modifier::ITALIC),
+        StyleField::Modifier(Modifier::UNDERLINED),
+        StyleField::Modifier(Modifier::REVERSED),
+    ];
+
+    /// Returns the field's label and current value in `style`, e.g.,
+    /// "fg: light-yellow" or "[x] bold"
+    fn show(self, style: Style) -> String {
+        match self {
+            StyleField::Foreground => format!("fg: {}", show_color(style.fg)),
+            StyleField::Background => format!("bg: {}", show_color(style.bg)),
+            StyleField::Modifier(m) => {
+                let name = MODIFIERS
+                    .into_iter()
+                    .find(|&(_, m2)| m2 == m)
+                    .map_or("?", |(name, _)| name);
+                let check = if style.add_modifier.contains(m) {
+                    'x'
+                } else {
+                    ' '
+                };
+                format!("[{check}] {name}")
+            }
+        }
+    }
+
+    /// Change the field in `style`: colors step through "none" and the named
+    /// colors, and modifiers are toggled
+    pub(crate) fn adjust(self, mut style: Style, forwards: bool) -> Style {
+        match self {
+            StyleField::Foreground => style.fg = step_color(style.fg, forwards),
+            StyleField::Background => style.bg = step_color(style.bg, forwards),
+            StyleField::Modifier(m) => {
+                style.add_modifier.toggle(m);
+                style.sub_modifier.remove(m);
+            }
+        }
+        style
+    }
+}
+
+fn show_color(color: Option<Color>) -> String {
+    color.map_or_else(|| String::from("none"), format_color)
+}
+
+/// Returns the color before or after `color` in the cycle of no color
+/// followed by the named colors.  Colors given by number start the cycle
+/// over.
+fn step_color(color: Option<Color>, forwards: bool) -> Option<Color> {
+    let qty = NAMED_COLORS.len() + 1;
+    let pos = color
+        .and_then(|c| NAMED_COLORS.iter().position(|&(_, c2)| c2 == c))
+        .map_or(0, |i| i + 1);
+    let pos = if forwards {
+        (pos + 1) % qty
+    } else {
+        (pos + qty - 1) % qty
+    };
+    pos.checked_sub(1).map(|i| NAMED_COLORS[i].1)
+}
+
+/// Popup listing the theme's elements around the one at index `element`,
+/// with the parts of its style below and the one at index `field`
+/// highlighted
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct ThemeEditor<'a> {
+    pub(crate) theme: Theme,
+    pub(crate) element: usize,
+    pub(crate) field: usize,
+    /// A message about the last attempt to save the theme, if any
+    pub(crate) status: Option<&'a str>,
+    pub(crate) style: PopupStyle,
+}
+
+impl Widget for ThemeEditor<'_> {
+    fn render(mut self, area: Rect, buf: &mut Buffer) {
+        let base = self.theme.base;
+        let styles = self.theme.styles_mut().map(|s| *s);
+        let start = self
+            .element
+            .saturating_sub(VISIBLE / 2)
+            .min(styles.len() - VISIBLE);
+        let mut lines = Theme::ELEMENTS
+            .into_iter()
+            .zip(styles)
+            .enumerate()
+            .skip(start)
+            .take(VISIBLE)
+            .map(|(i, (name, style))| {
+                let marker = if i == self.element { '>' } else { ' ' };
+                Line::from(vec![
+                    Span::raw(format!("{marker} {name:<15}")),
+                    Span::styled(" 27 ", base.patch(style)),
+                    Span::raw(format!("  {}", format_style(style))),
+                ])
+            })
+            .collect::<Vec<_>>();
+        lines.push(Line::raw(""));
+        let selected = styles[self.element];
+        let fields = StyleField::ALL
+            .into_iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let text = field.show(selected);
+                if i == self.field {
+                    Span::styled(text, Style::new().reversed())
+                } else {
+                    Span::raw(text)
+                }
+            })
+            .collect::<Vec<_>>();
+        let (colors, modifiers) = fields.split_at(2);
+        for spans in [colors, modifiers] {
+            let mut line = Vec::new();
+            for span in spans {
+                if !line.is_empty() {
+                    line.push(Span::raw("  "));
+                }
+                line.push(span.clone());
+            }
+            lines.push(Line::from(line));
+        }
+        lines.push(Line::raw(""));
+        if let Some(status) = self.status {
+            lines.push(Line::raw(status.to_owned()));
+            lines.push(Line::raw(""));
+        }
+        lines.push(Line::raw("UP/DOWN: element    TAB: next part"));
+        lines.push(Line::raw("LEFT/RIGHT: change  s: save  ESC: close"));
+        Popup::new(" Theme Editor ", lines, self.style).render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjust() {
+        let style = Style::new().light_yellow().bold();
+        let style = StyleField::Foreground.adjust(style, true);
+        assert_eq!(style, Style::new().light_blue().bold());
+        let style = StyleField::Background.adjust(style, false);
+        assert_eq!(style, Style::new().light_blue().on_white().bold());
+        let style = StyleField::Modifier(Modifier::BOLD).adjust(style, true);
+        assert_eq!(format_style(style), "light-blue on-white");
+        assert_eq!(step_color(Some(Color::White), true), None);
+        assert_eq!(
+            step_color(Some(Color::Indexed(236)), true),
+            Some(Color::Reset)
+        );
+    }
+}